@@ -224,7 +224,7 @@ impl crate::prelude::Renderer {
                     module: &shader,
                     entry_point: Some("fs_main"),
                     targets: &[Some(wgpu::ColorTargetState {
-                        format: self.config.format,
+                        format: self.render_target_format(),
                         write_mask: wgpu::ColorWrites::ALL,
                         blend: Some(wgpu::BlendState::ALPHA_BLENDING),
                     })],
@@ -248,7 +248,12 @@ impl crate::prelude::Renderer {
                     bias: wgpu::DepthBiasState::default(),
                 }),
                 multisample: wgpu::MultisampleState {
-                    count: settings.count,
+                    // every pipeline has to match the MSAA of the render pass
+                    count: if self.sample_count > 1 {
+                        self.sample_count
+                    } else {
+                        settings.count
+                    },
                     mask: settings.mask,
                     alpha_to_coverage_enabled: settings.alpha_to_coverage_enabled,
                 },
@@ -363,6 +368,7 @@ impl crate::prelude::Renderer {
         label: impl AsRef<str>,
         device: &wgpu::Device,
         config: &wgpu::SurfaceConfiguration,
+        sample_count: u32,
     ) -> (Texture, TextureView, Sampler) {
         let size = wgpu::Extent3d {
             width: config.width,
@@ -373,10 +379,18 @@ impl crate::prelude::Renderer {
             label: Some(label.as_ref()),
             size,
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count,
             dimension: wgpu::TextureDimension::D2,
             format: crate::DEPTH_FORMAT,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            // multisampled depth can not be copied or sampled as a regular texture, and the
+            // extra usages break the MSAA resolve on the GL backend
+            usage: if sample_count > 1 {
+                wgpu::TextureUsages::RENDER_ATTACHMENT
+            } else {
+                wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::COPY_SRC
+            },
             view_formats: &[wgpu::TextureFormat::Depth32Float],
         };
         let texture = device.create_texture(&desc);
@@ -498,20 +512,51 @@ impl crate::prelude::Renderer {
     }
 }
 
+/// An addition or removal of a signal, applied once the running hooks finish
+pub(crate) enum SignalChange {
+    Add(String, Box<dyn crate::Signal>),
+    Remove(String),
+}
+
+/// Takes the place of a signal while its own hook runs
+struct VacantSignal;
+impl crate::Signal for VacantSignal {}
+
 impl crate::SignalStorage {
     /// Creates a new live event storage
     pub fn new() -> Self {
-        Self { events: vec![] }
+        Self {
+            events: vec![],
+            pending: vec![],
+            running: 0,
+            initialized: false,
+        }
     }
 
-    /// Adds an event
+    /// Adds an event. While the engine is running, the event is added and initialized
+    /// right before the next hooks run, so it is safe to add events within them.
     pub fn add_signal(&mut self, key: impl AsRef<str>, event: Box<dyn crate::Signal>) {
-        self.events.push((key.as_ref().to_string(), event));
+        if self.running > 0 || self.initialized {
+            self.pending
+                .push(SignalChange::Add(key.as_ref().to_string(), event));
+        } else {
+            self.insert(key.as_ref().to_string(), event);
+        }
     }
 
-    /// Removes an event
+    /// Removes an event. Within the hooks it is removed once they finish.
     pub fn remove_signal(&mut self, key: impl AsRef<str>) {
-        self.events.retain(|k| k.0 != key.as_ref());
+        if self.running > 0 {
+            self.pending
+                .push(SignalChange::Remove(key.as_ref().to_string()));
+        } else {
+            self.remove(key.as_ref());
+        }
+    }
+
+    /// Checks if an event of the key exists
+    pub fn contains_signal(&self, key: impl AsRef<str>) -> bool {
+        self.iter_all().any(|k| k.0 == key.as_ref())
     }
 
     /// Gets an event
@@ -521,17 +566,107 @@ impl crate::SignalStorage {
     ) -> Option<Result<&mut T, downcast::TypeMismatch>> {
         // fetch the event
         let event = self
+            .iter_all_mut()
+            .find(|k| k.0 == key.as_ref())
+            .map(|k| k.1);
+
+        // downcast the event
+        event.map(|event| event.downcast_mut::<T>())
+    }
+
+    /// Gets the first event of the type, regardless of its key
+    pub fn get_signal_by_type<T: 'static>(&mut self) -> Option<&mut T> {
+        self.iter_all_mut()
+            .find_map(|k| k.1.downcast_mut::<T>().ok())
+    }
+
+    /// Inserts the event after the ones of the same or lower priority
+    pub(crate) fn insert(&mut self, key: String, event: Box<dyn crate::Signal>) {
+        let index = self
             .events
+            .iter()
+            .position(|k| k.1.priority() > event.priority())
+            .unwrap_or(self.events.len());
+        self.events.insert(index, (key, event));
+    }
+
+    fn remove(&mut self, key: &str) {
+        self.events.retain(|k| k.0 != key);
+        self.pending
+            .retain(|change| !matches!(change, SignalChange::Add(k, _) if k == key));
+    }
+
+    /// Iterates the events, and then the ones waiting to be added
+    fn iter_all(&self) -> impl Iterator<Item = (&String, &Box<dyn crate::Signal>)> {
+        self.events
+            .iter()
+            .map(|k| (&k.0, &k.1))
+            .chain(self.pending.iter().filter_map(|change| match change {
+                SignalChange::Add(key, event) => Some((key, event)),
+                SignalChange::Remove(_) => None,
+            }))
+    }
+
+    /// Iterates the events, and then the ones waiting to be added
+    fn iter_all_mut(&mut self) -> impl Iterator<Item = (&String, &mut Box<dyn crate::Signal>)> {
+        self.events
             .iter_mut()
-            .find(|k| k.0 == key.as_ref())
-            .map(|k| &mut k.1);
+            .map(|k| (&k.0, &mut k.1))
+            .chain(self.pending.iter_mut().filter_map(|change| match change {
+                SignalChange::Add(key, event) => Some((&*key, event)),
+                SignalChange::Remove(_) => None,
+            }))
+    }
+}
 
-        if let Some(event) = event {
-            // downcast the event
-            let event_type = event.downcast_mut::<T>();
-            Some(event_type)
-        } else {
-            None
+impl crate::Engine {
+    /// Runs the hook on every signal in order. Each signal is taken out only while its own
+    /// hook runs, so the others stay reachable, and the signals added or removed meanwhile
+    /// are applied once all hooks finish.
+    pub(crate) fn run_signals(
+        &mut self,
+        mut hook: impl FnMut(&mut Box<dyn crate::Signal>, &mut crate::Engine),
+    ) {
+        self.apply_signal_changes();
+
+        self.signals.running += 1;
+        for index in 0..self.signals.events.len() {
+            let mut event =
+                std::mem::replace(&mut self.signals.events[index].1, Box::new(VacantSignal));
+            hook(&mut event, self);
+            self.signals.events[index].1 = event;
+        }
+        self.signals.running -= 1;
+
+        self.apply_signal_changes();
+    }
+
+    /// Runs the `init` hooks of the signals, once
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub(crate) fn init_signals(&mut self) {
+        if self.signals.initialized {
+            return;
+        }
+        self.run_signals(|signal, engine| signal.init(engine));
+        self.signals.initialized = true;
+    }
+
+    /// Adds and removes the signals that were waiting for the hooks to finish
+    pub(crate) fn apply_signal_changes(&mut self) {
+        while self.signals.running == 0 && !self.signals.pending.is_empty() {
+            for change in std::mem::take(&mut self.signals.pending) {
+                match change {
+                    SignalChange::Add(key, mut event) => {
+                        if self.signals.initialized {
+                            self.signals.running += 1;
+                            event.init(self);
+                            self.signals.running -= 1;
+                        }
+                        self.signals.insert(key, event);
+                    }
+                    SignalChange::Remove(key) => self.signals.remove(&key),
+                }
+            }
         }
     }
 }
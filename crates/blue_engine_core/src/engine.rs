@@ -4,15 +4,45 @@ use crate::{CameraContainer, ObjectStorage, Renderer, SignalStorage};
 #[cfg(all(feature = "window", not(feature = "headless")))]
 use winit::{event_loop::EventLoop, window::WindowAttributes};
 
+/// Whether the engine opens a window or renders offscreen, decided at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum RunMode {
+    /// Opens a window and presents the frames to it. Needs the `window` feature
+    Windowed,
+    /// Renders to an offscreen texture without opening a window. The frames are copied
+    /// to `Renderer::headless_texture_data`
+    Headless,
+    /// Renders to a surface or texture views given by the application the engine is
+    /// embedded in, which drives the frames, see [`Engine::new_embedded`]
+    Embedded,
+}
+impl std::default::Default for RunMode {
+    /// Windowed when the `window` feature is enabled, and headless otherwise
+    fn default() -> Self {
+        if cfg!(all(feature = "window", not(feature = "headless"))) {
+            Self::Windowed
+        } else {
+            Self::Headless
+        }
+    }
+}
+
 /// Descriptor and settings for a window.
-#[derive(Debug, Clone)]
+///
+/// The settings can be loaded from a TOML or RON file, and overridden by environment
+/// variables and command-line arguments, see [`EngineSettings::load`] and
+/// [`EngineSettings::apply_overrides`]. Fields missing from a file keep their defaults.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct EngineSettings {
     /// The width of the window
     pub width: u32,
     /// The height of the window
     pub height: u32,
     /// The title of the window
-    pub title: &'static str,
+    pub title: String,
+    /// Whether to open a window or render offscreen
+    pub run_mode: RunMode,
     // winit
     /// Should the window contain the keys like minimize, maximize, or resize?
     #[cfg(all(feature = "window", not(feature = "headless")))]
@@ -20,15 +50,27 @@ pub struct EngineSettings {
     /// Should the window be resizable
     #[cfg(all(feature = "window", not(feature = "headless")))]
     pub resizable: bool,
-    /// Controls how the events are processed
+    /// Should the window cover the monitor it opens on, without borders
+    #[cfg(all(feature = "window", not(feature = "headless")))]
+    pub fullscreen: bool,
+    /// Controls how the events are processed. Not saved to config files
     ///
     /// read more at [winit::event_loop::ControlFlow]
     #[cfg(all(feature = "window", not(feature = "headless")))]
+    #[serde(skip)]
     pub control_flow: crate::winit::event_loop::ControlFlow,
     // wgpu
     /// Define how much power should the app ask for
     pub power_preference: crate::PowerPreference,
-    /// The backend to use for the draw
+    /// Which adapter to render with
+    pub adapter: crate::utils::adapter_selection::AdapterSelection,
+    /// Use the software adapter, such as llvmpipe or lavapipe, instead of a GPU
+    pub force_fallback_adapter: bool,
+    /// Use the software adapter when no adapter fits the `adapter` selection
+    pub fallback_to_software: bool,
+    /// The backend to use for the draw. Saved to config files as a comma separated list of
+    /// backend names, such as `"vulkan,metal"`, or `"all"`
+    #[serde(with = "crate::utils::settings::backends_list")]
     pub backends: crate::Backends,
     /// The features to be enabled on a backend
     ///
@@ -53,6 +95,15 @@ pub struct EngineSettings {
     ///
     /// read more at [wgpu::MemoryHints]
     pub memory_hints: crate::wgpu::MemoryHints,
+    /// Limits the amount of frames per second, regardless of the present mode.
+    /// `None` means no limit.
+    pub fps_cap: Option<f32>,
+    /// The amount of samples per pixel for multisample anti-aliasing (MSAA). `1` disables
+    /// it, and other counts the adapter supports, usually `4`, enable it
+    pub msaa_samples: u32,
+    /// The data copied to `Renderer::headless_texture_data` every headless frame
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub headless_output: crate::utils::headless_output::HeadlessOutputFormat,
 }
 impl std::default::Default for EngineSettings {
     /// Will quickly create a window with default settings
@@ -60,16 +111,22 @@ impl std::default::Default for EngineSettings {
         Self {
             width: 800,
             height: 600,
-            title: "Blue Engine",
+            title: "Blue Engine".to_string(),
+            run_mode: RunMode::default(),
             // winit
             #[cfg(all(feature = "window", not(feature = "headless")))]
             decorations: true,
             #[cfg(all(feature = "window", not(feature = "headless")))]
             resizable: true,
             #[cfg(all(feature = "window", not(feature = "headless")))]
+            fullscreen: false,
+            #[cfg(all(feature = "window", not(feature = "headless")))]
             control_flow: crate::winit::event_loop::ControlFlow::Poll,
             // wgpu
             power_preference: crate::PowerPreference::LowPower,
+            adapter: crate::utils::adapter_selection::AdapterSelection::Default,
+            force_fallback_adapter: false,
+            fallback_to_software: true,
             backends: crate::Backends::all(),
             features: wgpu::Features::empty(),
             present_mode: crate::wgpu::PresentMode::AutoNoVsync,
@@ -77,12 +134,19 @@ impl std::default::Default for EngineSettings {
             alpha_mode: crate::wgpu::CompositeAlphaMode::Auto,
             desired_maximum_frame_latency: 2,
             memory_hints: crate::MemoryHints::Performance,
+            fps_cap: None,
+            msaa_samples: 1,
+            #[cfg(any(feature = "window", feature = "headless"))]
+            headless_output: crate::utils::headless_output::HeadlessOutputFormat::Rgba8,
         }
     }
 }
 unsafe impl Send for EngineSettings {}
 unsafe impl Sync for EngineSettings {}
 
+/// A function that runs on the engine, such as the update loop
+pub type UpdateFunction = Box<dyn 'static + FnMut(&mut Engine)>;
+
 /// The engine is the main starting point of using the Blue Engine.
 /// Everything that runs on Blue Engine will be under this struct.
 /// The structure of engine is monolithic, but the underlying data and the way it works is not.
@@ -134,6 +198,9 @@ pub struct Engine {
     /// ### The window is not available before update_loop.
     #[cfg(all(feature = "window", not(feature = "headless")))]
     pub window: Window,
+    /// Windows opened next to the main one, see [`Engine::open_window`]
+    #[cfg(all(feature = "window", not(feature = "headless")))]
+    pub extra_windows: std::collections::HashMap<std::sync::Arc<str>, crate::window::ExtraWindow>,
     /// The object system is a way to make it easier to work with the engine.
     /// Obviously you can work without it, but it's for those who
     /// do not have the know-how, or wish to handle all the work of rendering data manually.
@@ -143,17 +210,64 @@ pub struct Engine {
     pub camera: CameraContainer,
     /// Handles all engine plugins
     pub signals: SignalStorage,
+    /// Shared state keyed by its type, reachable from the signals and the update loop
+    pub resources: crate::utils::resources::Resources,
+    /// Typed events sent between the signals and the update loop, readable the next frame
+    pub event_bus: crate::utils::event_bus::EventBus,
+    /// Gameplay data attached to the objects, or to other entities, by name
+    pub components: crate::utils::components::Components,
+    /// Game logic that runs every frame before the update loop
+    pub systems: crate::utils::components::Systems,
+    /// The menus, levels and pause screens of the game, each with their own objects
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub states: crate::utils::game_state::StateStack,
 
     /// holds the update_loop function
-    pub update_loop: Option<Box<dyn 'static + FnMut(&mut Engine)>>,
+    pub update_loop: Option<UpdateFunction>,
+    /// holds the fixed update function, see [`Engine::fixed_update`]
+    pub fixed_update_loop: Option<UpdateFunction>,
+    /// Decides how many fixed updates run each frame
+    pub fixed_timestep: crate::utils::fixed_timestep::FixedTimestep,
+    /// Frame timing, game time and the frame limiter
+    pub time: crate::utils::time::Time,
+    /// Set by [`Engine::request_exit`] to stop the update loop
+    exit_requested: bool,
+    /// Returned by the update loop, see [`Engine::request_exit_with`]
+    exit_code: i32,
+    /// Whether [`Engine::shutdown`] ran
+    shut_down: bool,
+    /// Whether the signals and default data are ready for [`Engine::step`]
+    #[cfg(any(feature = "window", feature = "headless"))]
+    headless_initialized: bool,
+    /// Timers, scheduled callbacks and coroutines, ran every frame against the game time
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub scheduler: crate::utils::scheduler::Scheduler,
 
     /// Simplified input events
-    #[cfg(all(feature = "window", not(feature = "headless")))]
+    #[cfg(any(feature = "window", feature = "headless"))]
     pub simple_input: crate::utils::winit_input_helper::WinitInputHelper,
 
     /// Simplified input events
     #[cfg(all(feature = "window", not(feature = "headless")))]
     pub raw_input: Option<winit::event::DeviceEvent>,
+
+    /// Named actions and axes mapped to inputs, updated every frame from `simple_input`
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub actions: crate::utils::input_actions::InputActions,
+
+    /// Gamepad connections, buttons and axes, updated every frame
+    pub gamepads: crate::utils::gamepad::Gamepads,
+
+    /// Input injected by code, given to `simple_input` every frame
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub synthetic_input: crate::utils::synthetic_input::SyntheticInput,
+
+    /// Records the input events given to `simple_input` while set
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub input_recorder: Option<crate::utils::input_recording::InputRecorder>,
+    /// Plays back recorded input instead of the real one while set
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub input_replay: Option<crate::utils::input_recording::InputReplay>,
 }
 unsafe impl Send for Engine {}
 unsafe impl Sync for Engine {}
@@ -163,6 +277,7 @@ impl Engine {
     pub fn new() -> Result<Self, crate::error::Error> {
         Self::new_inner(
             EngineSettings::default(),
+            None,
             #[cfg(all(target_os = "android", feature = "window", not(feature = "headless")))]
             None,
         )
@@ -172,6 +287,7 @@ impl Engine {
     pub fn new_config(settings: EngineSettings) -> Result<Self, crate::error::Error> {
         Self::new_inner(
             settings,
+            None,
             #[cfg(all(target_os = "android", feature = "window", not(feature = "headless")))]
             None,
         )
@@ -183,18 +299,39 @@ impl Engine {
         settings: EngineSettings,
         app: winit::platform::android::activity::AndroidApp,
     ) -> Result<Self, crate::error::Error> {
-        Self::new_inner(settings, Some(app))
+        Self::new_inner(settings, None, Some(app))
+    }
+
+    /// Creates the engine on the device of another application or renderer, to embed it.
+    /// The engine does not open a window nor run an event loop. Attach a surface with
+    /// [`Engine::attach_surface`], or render into texture views with
+    /// [`Engine::step_into_view`], and call [`Engine::step`] every frame.
+    ///
+    /// The run mode is set to [`RunMode::Embedded`].
+    pub fn new_embedded(
+        mut settings: EngineSettings,
+        context: crate::render::GpuContext,
+    ) -> Result<Self, crate::error::Error> {
+        settings.run_mode = RunMode::Embedded;
+        Self::new_inner(
+            settings,
+            Some(context),
+            #[cfg(all(target_os = "android", feature = "window", not(feature = "headless")))]
+            None,
+        )
     }
 
     /// Creates a new window in current thread.
     #[allow(unreachable_code)]
     pub(crate) fn new_inner(
         settings: EngineSettings,
+        context: Option<crate::render::GpuContext>,
         #[cfg(all(target_os = "android", feature = "window", not(feature = "headless")))]
         android_app: Option<winit::platform::android::activity::AndroidApp>,
     ) -> Result<Self, crate::error::Error> {
         #[cfg(feature = "debug")]
-        env_logger::init();
+        // more than one engine can be created, such as in tests
+        let _ = env_logger::try_init();
         // Dimensions of the window, as width and height
         // and then are set as a logical size that the window can accept
         let dimension = (settings.width, settings.height);
@@ -210,28 +347,66 @@ impl Engine {
                 width: settings.width,   // Which sets the width of the window
                 height: settings.height, // And sets the height of the window
             }) // sets the width and height of window
-            .with_title(String::from(settings.title)) // sets title of the window
+            .with_title(settings.title.clone()) // sets title of the window
             .with_decorations(settings.decorations) // sets if the window should have borders
-            .with_resizable(settings.resizable); // sets the window to be resizable
+            .with_resizable(settings.resizable) // sets the window to be resizable
+            .with_fullscreen(
+                settings
+                    .fullscreen
+                    .then_some(winit::window::Fullscreen::Borderless(None)),
+            );
 
         // The renderer init on current window
-        let mut renderer = pollster::block_on(Renderer::new(dimension, settings.clone()))?;
+        let mut renderer = match context {
+            Some(context) => Renderer::with_context(dimension, settings.clone(), context)?,
+            None => pollster::block_on(Renderer::new(dimension, settings.clone()))?,
+        };
         let camera = CameraContainer::new(dimension, &mut renderer);
 
+        let mut time = crate::utils::time::Time::new();
+        time.set_fps_cap(settings.fps_cap);
+
         Ok(Self {
             #[cfg(all(not(feature = "headless"), feature = "window"))]
             window: Window::new(default_attributes),
             #[cfg(all(not(feature = "headless"), feature = "window"))]
             event_loop_control_flow: settings.control_flow,
             #[cfg(all(not(feature = "headless"), feature = "window"))]
+            extra_windows: std::collections::HashMap::new(),
+            #[cfg(any(feature = "window", feature = "headless"))]
             simple_input: crate::utils::winit_input_helper::WinitInputHelper::new(),
             #[cfg(all(not(feature = "headless"), feature = "window"))]
             raw_input: None,
+            #[cfg(any(feature = "window", feature = "headless"))]
+            actions: crate::utils::input_actions::InputActions::new(),
+            gamepads: crate::utils::gamepad::Gamepads::new(),
+            #[cfg(any(feature = "window", feature = "headless"))]
+            input_recorder: None,
+            #[cfg(any(feature = "window", feature = "headless"))]
+            synthetic_input: crate::utils::synthetic_input::SyntheticInput::new(),
+            #[cfg(any(feature = "window", feature = "headless"))]
+            input_replay: None,
             renderer,
             objects: ObjectStorage::new(),
             camera,
             signals: crate::SignalStorage::new(),
+            resources: crate::utils::resources::Resources::new(),
+            event_bus: crate::utils::event_bus::EventBus::new(),
+            components: crate::utils::components::Components::new(),
+            systems: crate::utils::components::Systems::new(),
+            #[cfg(any(feature = "window", feature = "headless"))]
+            states: crate::utils::game_state::StateStack::new(),
             update_loop: None,
+            fixed_update_loop: None,
+            fixed_timestep: crate::utils::fixed_timestep::FixedTimestep::default(),
+            time,
+            exit_requested: false,
+            exit_code: 0,
+            shut_down: false,
+            #[cfg(any(feature = "window", feature = "headless"))]
+            headless_initialized: false,
+            #[cfg(any(feature = "window", feature = "headless"))]
+            scheduler: crate::utils::scheduler::Scheduler::new(),
         })
     }
 
@@ -240,6 +415,10 @@ impl Engine {
     /// happy in the engine!
     ///
     /// Renderer, window, vec of objects, events, and camera are passed to the update code.
+    ///
+    /// Once the window closes or an exit is requested, the engine shuts down, see
+    /// [`Engine::shutdown`], and the exit code set with [`Engine::request_exit_with`] is
+    /// returned, which is `0` otherwise.
     #[allow(unreachable_code)]
     pub fn update_loop(
         &mut self,
@@ -248,11 +427,26 @@ impl Engine {
             // Coreall(target_os = "android", not(feature = "headless"))
             &mut Engine,
         ),
-    ) -> Result<(), crate::error::Error> {
-        #[cfg(all(not(feature = "headless"), feature = "window"))]
+    ) -> Result<i32, crate::error::Error> {
+        #[cfg(any(feature = "window", feature = "headless"))]
         {
             self.update_loop = Some(Box::new(update_function));
         }
+        #[cfg(not(any(feature = "window", feature = "headless")))]
+        let _ = update_function;
+
+        // rendering offscreen or embedded does not need the event loop
+        #[cfg(any(feature = "window", feature = "headless"))]
+        if self.renderer.run_mode != crate::RunMode::Windowed {
+            self.reset_exit();
+            let mut result = Ok(());
+            while !self.exit_requested && result.is_ok() {
+                result = self.step();
+            }
+            self.shutdown();
+            self.finish_headless();
+            return result.map(|_| self.exit_code);
+        }
 
         // will create the main event loop of the window.
         // and will contain all the callbacks and button press
@@ -294,57 +488,572 @@ impl Engine {
         event_loop.set_control_flow(self.event_loop_control_flow);
         #[cfg(all(not(feature = "headless"), feature = "window"))]
         event_loop.run_app(self)?;
+        // in case the event loop stopped without exiting
+        self.shutdown();
 
-        #[cfg(all(
-            not(target_os = "android"),
-            not(feature = "window"),
-            feature = "headless"
-        ))]
-        {
-            let window_size = (self.renderer.config.width, self.renderer.config.height);
+        Ok(self.exit_code)
+    }
 
-            let mut events = std::mem::take(&mut self.signals.events);
-            events.iter_mut().for_each(|i| {
-                i.1.init(self);
-            });
+    /// Runs the update loop for the given amount of frames, or until an exit is requested,
+    /// and then shuts the engine down like `update_loop` does, see [`Engine::shutdown`].
+    /// The last frame stays in `renderer.headless_texture_data`, and the exit code set with
+    /// [`Engine::request_exit_with`] is returned, which is `0` otherwise. Useful for tests and
+    /// offline renders. Not available in the windowed run mode.
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub fn run_frames(
+        &mut self,
+        frames: u64,
+        update_function: impl 'static + FnMut(&mut Engine),
+    ) -> Result<i32, crate::error::Error> {
+        if self.renderer.run_mode == crate::RunMode::Windowed {
+            return Err(crate::error::Error::Custom(
+                "Frames can only be run in the headless or embedded run mode".to_string(),
+            ));
+        }
 
-            self.renderer.build_default_data();
-            self.objects.iter_mut().for_each(|i| {
-                i.1.update(&mut self.renderer);
-            });
+        self.update_loop = Some(Box::new(update_function));
+        self.reset_exit();
+        let mut result = Ok(());
+        for _ in 0..frames {
+            if self.exit_requested || result.is_err() {
+                break;
+            }
+            result = self.step();
+        }
+        self.shutdown();
+        self.finish_headless();
+
+        result.map(|_| self.exit_code)
+    }
 
-            self.camera.set_resolution(window_size);
-            self.camera.update_view_projection(&mut self.renderer);
+    /// Renders exactly one frame and returns, running the signals, fixed updates, scheduler
+    /// and the update loop function stored in `update_loop` if any.
+    ///
+    /// In the headless run mode the rendered frame is available at
+    /// `renderer.headless_texture_data` afterwards, and in the embedded run mode it is
+    /// presented to the attached surface. Not available in the windowed run mode, nor
+    /// once the engine is shut down, see [`Engine::shutdown`].
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub fn step(&mut self) -> Result<(), crate::error::Error> {
+        if self.renderer.run_mode == crate::RunMode::Windowed {
+            return Err(crate::error::Error::Custom(
+                "Frames can only be stepped in the headless or embedded run mode".to_string(),
+            ));
+        }
+        if self.shut_down {
+            return Err(crate::error::Error::Custom(
+                "Frames can not be stepped once the engine is shut down".to_string(),
+            ));
+        }
+        self.init_headless();
+        self.check_device()?;
+        let window_size = (self.renderer.config.width, self.renderer.config.height);
 
-            let mut update_function = update_function;
-            loop {
-                self.renderer.headless_texture_data =
-                    Vec::<u8>::with_capacity((window_size.0 * window_size.1) as usize * 4);
+        self.time.tick();
+        self.event_bus.next_frame();
+        self.apply_state_transitions();
+        self.rebuild_outdated_pipelines();
+        self.renderer
+            .update_dynamic_resolution(self.time.real_delta());
+        self.simple_input.set_time(self.time.clock().now());
+        self.simple_input.step();
+        if self.renderer.run_mode == crate::RunMode::Headless {
+            self.renderer.headless_texture_data = Vec::<u8>::with_capacity(
+                (window_size.0 * window_size.1 * self.renderer.headless_output.bytes_per_pixel())
+                    as usize,
+            );
+        }
 
-                if let Ok(Some((mut encoder, view, frame, headless_output))) = self
-                    .renderer
-                    .pre_render(&self.objects, window_size, &self.camera)
-                {
-                    events.iter_mut().for_each(|i| {
-                        i.1.frame(self, &mut encoder, &view);
-                    });
+        let mut result = Ok(());
+        let layers = self.states.render_layers(&self.objects, &self.camera);
+        match self.renderer.pre_render(&layers, window_size) {
+            Ok(Some((mut encoder, view, frame, headless_output))) => {
+                self.run_signals(|signal, engine| signal.frame(engine, &mut encoder, &view));
 
-                    for camera_value in self.camera.values_mut() {
-                        camera_value.update_view_projection(&mut self.renderer);
+                for camera_value in self.camera.values_mut() {
+                    camera_value.update_view_projection(&mut self.renderer);
+                }
+                self.objects.iter_mut().for_each(|i| {
+                    if i.1.changed {
+                        i.1.update(&mut self.renderer);
                     }
-                    self.objects.iter_mut().for_each(|i| {
-                        if i.1.changed {
-                            i.1.update(&mut self.renderer);
-                        }
-                    });
+                });
+
+                if let Err(error) = self.renderer.render(encoder, frame, headless_output) {
+                    result = Err(error);
+                }
+                self.run_signals(|signal, engine| signal.post_render(engine));
 
-                    self.renderer.render(encoder, frame, headless_output);
+                self.update_input();
+                self.run_fixed_updates();
+                self.run_scheduler();
+                self.run_signals(|signal, engine| signal.pre_update(engine));
+                self.run_systems();
+                self.update_states();
 
+                let mut update_function = self.update_loop.take();
+                if let Some(ref mut update_function) = update_function {
                     update_function(self);
                 }
+                self.update_loop = update_function;
+                self.end_input_frame();
             }
+            Ok(None) => {}
+            Err(error) => result = Err(error.into()),
+        }
+
+        self.simple_input.end_step();
+        self.time.limit_frame_rate();
+
+        result
+    }
+
+    /// Like [`Engine::step`], but renders the frame into the texture view of the embedding
+    /// application instead of the attached surface. The view must be in the format set
+    /// with [`Engine::set_target_format`], and of the size of the engine, see
+    /// [`Engine::resize`]. Needs the embedded run mode.
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub fn step_into_view(&mut self, view: &wgpu::TextureView) -> Result<(), crate::error::Error> {
+        if self.renderer.run_mode != crate::RunMode::Embedded {
+            return Err(crate::error::Error::Custom(
+                "Rendering into a texture view needs the embedded run mode".to_string(),
+            ));
+        }
+
+        self.renderer.target_view = Some(view.clone());
+        let result = self.step();
+        self.renderer.target_view = None;
+        result
+    }
+
+    /// Initializes the signals and the default data before the first frame that is stepped
+    #[cfg(any(feature = "window", feature = "headless"))]
+    fn init_headless(&mut self) {
+        if self.headless_initialized {
+            return;
+        }
+        self.headless_initialized = true;
+
+        self.init_signals();
+
+        self.renderer.build_default_data();
+        self.objects.iter_mut().for_each(|i| {
+            i.1.update(&mut self.renderer);
+        });
+
+        let window_size = (self.renderer.config.width, self.renderer.config.height);
+        self.camera.set_resolution(window_size);
+        self.camera.update_view_projection(&mut self.renderer);
+    }
+
+    /// Waits for the GPU to finish its work once the headless update loop stops
+    #[cfg(any(feature = "window", feature = "headless"))]
+    fn finish_headless(&mut self) {
+        self.update_loop = None;
+        let _ = self
+            .renderer
+            .device
+            .poll(wgpu::PollType::wait_indefinitely());
+    }
+
+    /// Stops the update loop at the end of the current frame. In windowed mode the window
+    /// is closed, and in headless mode `update_loop` and `run_frames` return.
+    pub fn request_exit(&mut self) {
+        self.exit_requested = true;
+    }
+
+    /// Like [`Engine::request_exit`], and makes `update_loop` return the exit code
+    pub fn request_exit_with(&mut self, exit_code: i32) {
+        self.exit_code = exit_code;
+        self.exit_requested = true;
+    }
+
+    /// Forgets the exit requested by a previous run, before the next one starts
+    #[cfg(any(feature = "window", feature = "headless"))]
+    fn reset_exit(&mut self) {
+        self.exit_requested = false;
+        self.exit_code = 0;
+    }
+
+    /// Returns true if an exit was requested
+    pub fn exit_requested(&self) -> bool {
+        self.exit_requested
+    }
+
+    /// Returns the exit code the update loop returns, see [`Engine::request_exit_with`]
+    pub fn exit_code(&self) -> i32 {
+        self.exit_code
+    }
+
+    /// Shuts the engine down in order, once: runs the exit hooks of the game states and the
+    /// shutdown hooks of the signals, waits for the GPU to finish its work, and then frees
+    /// the objects, cameras, windows, surface, signals and update functions. The resources,
+    /// components and event bus are kept so they can be read afterwards.
+    ///
+    /// The update loop does this when it ends. Embedding applications that drive the
+    /// engine with [`Engine::step`] should call it before dropping the engine.
+    pub fn shutdown(&mut self) {
+        if self.shut_down {
+            return;
+        }
+        self.shut_down = true;
+
+        // the states shut their own signals down as they exit, before the rest of them
+        #[cfg(any(feature = "window", feature = "headless"))]
+        {
+            self.states.clear();
+            self.apply_state_transitions();
+        }
+        self.run_signals(|signal, engine| signal.shutdown(engine));
+
+        let _ = self
+            .renderer
+            .device
+            .poll(wgpu::PollType::wait_indefinitely());
+
+        self.update_loop = None;
+        self.fixed_update_loop = None;
+        self.systems.clear();
+        self.signals = crate::SignalStorage::new();
+        self.objects = ObjectStorage::new();
+        self.camera.clear();
+        #[cfg(all(feature = "window", not(feature = "headless")))]
+        self.extra_windows.clear();
+        // the surface goes before the window it draws to
+        self.renderer.surface = None;
+        #[cfg(all(feature = "window", not(feature = "headless")))]
+        {
+            self.window.window = None;
         }
 
+        // releases the freed GPU resources
+        let _ = self
+            .renderer
+            .device
+            .poll(wgpu::PollType::wait_indefinitely());
+    }
+
+    /// Returns true once the engine is shut down
+    pub fn is_shut_down(&self) -> bool {
+        self.shut_down
+    }
+
+    /// Renders to the surface from now on, replacing the current one. The embedding
+    /// application can create it from its own window with `renderer.instance.create_surface`,
+    /// or from raw window handles with `create_surface_unsafe`. The shaders of the objects
+    /// are rebuilt for the format of the surface. Fails if the surface does not support
+    /// any format on the adapter of the engine.
+    pub fn attach_surface(
+        &mut self,
+        surface: wgpu::Surface<'static>,
+    ) -> Result<(), crate::error::Error> {
+        self.renderer.configure_surface(surface)?;
+        self.renderer.build_default_data();
+        self.objects.iter_mut().for_each(|i| {
+            i.1.update(&mut self.renderer);
+        });
         Ok(())
     }
+
+    /// Sets the format the scene is rendered in, such as the one of the texture views given
+    /// to [`Engine::step_into_view`]. The shaders of the objects are rebuilt for it.
+    pub fn set_target_format(&mut self, format: wgpu::TextureFormat) {
+        self.renderer.config.format = format;
+        self.renderer.config.view_formats = vec![format];
+        self.renderer.rebuild_frame_targets();
+        self.renderer.build_default_data();
+        self.objects.iter_mut().for_each(|i| {
+            i.1.update(&mut self.renderer);
+        });
+    }
+
+    /// Rebuilds the shaders of the objects and the targets of the extra windows once the
+    /// renderer changed in a way that needs it, such as the MSAA samples
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub(crate) fn rebuild_outdated_pipelines(&mut self) {
+        if !self.renderer.pipelines_outdated {
+            return;
+        }
+        self.renderer.pipelines_outdated = false;
+
+        self.objects.iter_mut().for_each(|i| {
+            i.1.update_shader(&mut self.renderer);
+        });
+        for objects in self.states.paused_objects_mut() {
+            objects.iter_mut().for_each(|i| {
+                i.1.update_shader(&mut self.renderer);
+            });
+        }
+        #[cfg(all(feature = "window", not(feature = "headless")))]
+        for window in self.extra_windows.values_mut() {
+            window.rebuild_targets(&self.renderer);
+        }
+    }
+
+    /// Resizes the surface or the offscreen output, and the aspect ratio of the main camera
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.renderer.resize((width, height));
+        self.camera.set_resolution((width, height));
+        self.camera.update_view_projection(&mut self.renderer);
+        #[cfg(any(feature = "window", feature = "headless"))]
+        for camera in self.states.paused_cameras_mut() {
+            camera.set_resolution((width, height));
+            camera.update_view_projection(&mut self.renderer);
+        }
+        self.run_signals(|signal, engine| signal.resize(engine, (width, height)));
+    }
+
+    /// Opens a new window next to the main one once the event loop runs, replacing any
+    /// window of the same name. The window renders the scene with the camera of the given
+    /// name, which is created if it does not exist.
+    #[cfg(all(feature = "window", not(feature = "headless")))]
+    pub fn open_window(
+        &mut self,
+        name: impl AsRef<str>,
+        attributes: WindowAttributes,
+        camera: impl AsRef<str>,
+    ) {
+        let camera: std::sync::Arc<str> = camera.as_ref().into();
+        if !self.camera.contains_key(&camera) {
+            let size = attributes
+                .inner_size
+                .map(|size| size.to_physical::<u32>(1.0))
+                .map(|size| (size.width, size.height))
+                .unwrap_or((self.renderer.config.width, self.renderer.config.height));
+            let new_camera = crate::Camera::new(size, &mut self.renderer);
+            self.camera.insert(camera.clone(), new_camera);
+        }
+
+        self.extra_windows.insert(
+            name.as_ref().into(),
+            crate::window::ExtraWindow::new(attributes, camera),
+        );
+    }
+
+    /// Closes a window opened with [`Engine::open_window`]
+    #[cfg(all(feature = "window", not(feature = "headless")))]
+    pub fn close_window(&mut self, name: impl AsRef<str>) {
+        self.extra_windows.remove(name.as_ref());
+    }
+
+    /// Returns the name of the extra window with the id, such as the one given to
+    /// `Signal::window_events`. Returns `None` for the main window.
+    #[cfg(all(feature = "window", not(feature = "headless")))]
+    pub fn extra_window_name(
+        &self,
+        id: crate::winit::window::WindowId,
+    ) -> Option<std::sync::Arc<str>> {
+        self.extra_windows
+            .iter()
+            .find(|(_, window)| window.id() == Some(id))
+            .map(|(name, _)| name.clone())
+    }
+
+    /// Renders the scene to the texture right away, next to the window or the headless
+    /// output, such as for a minimap or a second view. See [`Renderer::render_to_texture`]
+    pub fn render_to_texture(&self, texture: &wgpu::Texture) {
+        self.renderer
+            .render_to_texture(&self.objects, &self.camera, texture);
+    }
+
+    /// Runs the block of code that you pass to it at a fixed rate, separate from the
+    /// frame rate. This is where simulation such as physics and gameplay should happen,
+    /// so it advances by the same amount regardless of how fast frames are rendered.
+    ///
+    /// Depending on the time the last frame took, it can run zero or several times before
+    /// the update loop of a frame. Use `engine.fixed_timestep.delta_seconds()` as the time
+    /// step, and [`Engine::interpolation_alpha`] to blend between the previous and current
+    /// simulation states when rendering.
+    ///
+    /// Must be called before `update_loop`.
+    pub fn fixed_update(
+        &mut self,
+        hz: f64,
+        fixed_update_function: impl 'static + FnMut(&mut Engine),
+    ) {
+        self.fixed_timestep.set_hz(hz);
+        self.fixed_update_loop = Some(Box::new(fixed_update_function));
+    }
+
+    /// Returns how far between the last and the next fixed update the current frame is,
+    /// between 0 and 1
+    pub fn interpolation_alpha(&self) -> f32 {
+        self.fixed_timestep.alpha()
+    }
+
+    /// Runs as many fixed updates as the game time of the last frame requires
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub(crate) fn run_fixed_updates(&mut self) {
+        let steps = self.fixed_timestep.advance(self.time.delta());
+        let mut fixed_update_function = self.fixed_update_loop.take();
+        for _ in 0..steps {
+            self.run_signals(|signal, engine| signal.fixed_update(engine));
+            if let Some(ref mut fixed_update_function) = fixed_update_function {
+                fixed_update_function(self);
+            }
+        }
+        self.fixed_update_loop = fixed_update_function;
+    }
+
+    /// Starts a coroutine, which is polled once every frame until it finishes.
+    /// Use `wait_seconds`, `wait_frames` and `with_engine` within it.
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub fn spawn(
+        &mut self,
+        future: impl 'static + std::future::Future<Output = ()>,
+    ) -> crate::utils::scheduler::ScheduleId {
+        self.scheduler.spawn(future)
+    }
+
+    /// Runs the systems, right before the update loop
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub(crate) fn run_systems(&mut self) {
+        crate::utils::components::Systems::update(self);
+    }
+
+    /// Removes the object and all components of the entity of the name
+    pub fn despawn(&mut self, name: impl AsRef<str>) {
+        self.objects.remove(name.as_ref());
+        self.components.despawn(name);
+    }
+
+    /// Runs the timers, callbacks and coroutines that are due this frame
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub(crate) fn run_scheduler(&mut self) {
+        crate::utils::scheduler::Scheduler::update(self);
+    }
+
+    /// Starts recording the input events, replacing any recording in progress
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub fn start_input_recording(&mut self) {
+        self.input_recorder = Some(crate::utils::input_recording::InputRecorder::new(
+            self.time.real_elapsed(),
+        ));
+    }
+
+    /// Stops recording the input events and returns the recording
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub fn stop_input_recording(
+        &mut self,
+    ) -> Option<crate::utils::input_recording::InputRecording> {
+        self.input_recorder.take().map(|recorder| recorder.finish())
+    }
+
+    /// Plays back a recording from the next frame on. The real input is ignored until
+    /// the playback is finished.
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub fn replay_input(&mut self, recording: crate::utils::input_recording::InputRecording) {
+        self.input_replay = Some(crate::utils::input_recording::InputReplay::new(recording));
+    }
+
+    /// Gives an input event to `simple_input`, and the recorder if one is running
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub(crate) fn handle_input_event(&mut self, event: crate::utils::input_event::InputEvent) {
+        // the real input would interfere with the playback
+        if self.input_replay.is_some() {
+            return;
+        }
+
+        self.simple_input.process_input_event(&event);
+        if let Some(recorder) = self.input_recorder.as_mut() {
+            recorder.record(event, self.time.real_elapsed());
+        }
+    }
+
+    /// Applies the input of the current frame, right before the update loop runs
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub(crate) fn update_input(&mut self) {
+        for event in self.synthetic_input.next_frame() {
+            self.handle_input_event(event);
+        }
+        self.replay_input_frame();
+        self.actions.update(&self.simple_input);
+        self.gamepads.update();
+    }
+
+    /// Marks the end of the input of the current frame, right after the update loop runs
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub(crate) fn end_input_frame(&mut self) {
+        if let Some(recorder) = self.input_recorder.as_mut() {
+            recorder.next_frame();
+        }
+    }
+
+    /// Gives the events of the current frame of the playback to `simple_input`
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub(crate) fn replay_input_frame(&mut self) {
+        if let Some(replay) = self.input_replay.as_mut() {
+            for recorded in replay.next_frame() {
+                self.simple_input.process_input_event(&recorded.event);
+            }
+            if replay.is_finished() {
+                self.input_replay = None;
+            }
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "window", feature = "headless")))]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    struct Tracked(Rc<Cell<u32>>);
+    impl crate::Signal for Tracked {
+        fn shutdown(&mut self, _engine: &mut Engine) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn run_frames_shuts_down() {
+        let mut engine = crate::utils::headless_test_engine();
+        let shutdowns = Rc::new(Cell::new(0));
+        engine
+            .signals
+            .add_signal("tracked", Box::new(Tracked(shutdowns.clone())));
+        crate::primitive_shapes::triangle(
+            "triangle",
+            crate::ObjectSettings::default(),
+            &mut engine.renderer,
+            &mut engine.objects,
+        )
+        .unwrap();
+
+        let exit_code = engine
+            .run_frames(10, |engine| {
+                if engine.time.frame_count() == 2 {
+                    engine.request_exit_with(3);
+                }
+            })
+            .unwrap();
+        assert_eq!(exit_code, 3);
+        assert_eq!(engine.exit_code(), 3);
+        assert!(engine.exit_requested());
+        assert_eq!(engine.time.frame_count(), 2);
+        assert_eq!(shutdowns.get(), 1);
+        assert!(engine.is_shut_down());
+        assert!(engine.objects.is_empty());
+        assert!(engine.update_loop.is_none());
+        assert!(!engine.renderer.headless_texture_data.is_empty());
+    }
+
+    #[test]
+    fn step_after_shutdown_fails() {
+        let mut engine = crate::utils::headless_test_engine();
+        let ran = Rc::new(Cell::new(0));
+        let counter = ran.clone();
+        engine.update_loop = Some(Box::new(move |_| counter.set(counter.get() + 1)));
+
+        engine.step().unwrap();
+        engine.shutdown();
+        assert!(engine.step().is_err());
+        assert_eq!(ran.get(), 1);
+        assert_eq!(engine.time.frame_count(), 1);
+
+        // nor can a shut down engine run again
+        assert!(engine.run_frames(3, |_| {}).is_err());
+        assert_eq!(engine.time.frame_count(), 1);
+    }
 }
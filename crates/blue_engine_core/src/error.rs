@@ -10,17 +10,42 @@ pub enum Error {
     #[error("An error occurred with the update loop: {0}")]
     #[cfg(all(feature = "window", not(feature = "headless")))]
     UpdateLoopError(#[from] winit::error::EventLoopError),
+    #[error("Failed to create the window: {0}")]
+    #[cfg(all(feature = "window", not(feature = "headless")))]
+    WindowCreationError(#[from] winit::error::OsError),
 
     // ===== WGPU
     #[error("Failed to find an appropriate adapter")]
     AdapterNotFound(#[from] wgpu::RequestAdapterError),
     #[error("Failed to find an appropriate device for rendering")]
     DeviceNotFound(#[from] wgpu::RequestDeviceError),
+    #[error("Failed to create the surface of the window: {0}")]
+    CreateSurfaceError(#[from] wgpu::CreateSurfaceError),
+    #[error("Failed to acquire the frame to render to: {0}")]
+    SurfaceError(#[from] wgpu::SurfaceError),
+    #[error("Failed to wait for the device: {0}")]
+    PollError(#[from] wgpu::PollError),
+    #[error("Failed to read the rendered frame back: {0}")]
+    BufferMapError(#[from] wgpu::BufferAsyncError),
 
     // ===== Image
     #[error("Failed to load the texture data from given source")]
     LoadingTextureDataError(#[from] image::error::ImageError),
 
+    // ===== IO
+    #[error("Failed to read or write a file: {0}")]
+    IoError(#[from] std::io::Error),
+
+    // ===== Config
+    #[error("Failed to parse the config: {0}")]
+    ConfigParseError(#[from] toml::de::Error),
+    #[error("Failed to serialize the config: {0}")]
+    ConfigSerializeError(#[from] toml::ser::Error),
+    #[error("Failed to parse the RON config: {0}")]
+    RonConfigParseError(#[from] ron::error::SpannedError),
+    #[error("Failed to serialize the RON config: {0}")]
+    RonConfigSerializeError(#[from] ron::Error),
+
     #[error("{0}")]
     Custom(String),
 }
@@ -72,6 +72,8 @@ pub struct Object {
     pub is_visible: bool,
     /// Objects with higher number get rendered later and appear "on top" when occupying the same space
     pub render_order: usize,
+    /// The texture given to [`Object::set_texture`], to build it again on a new device
+    pub(crate) texture_source: Option<(Arc<str>, TextureData, crate::prelude::TextureMode)>,
 }
 unsafe impl Send for Object {}
 unsafe impl Sync for Object {}
@@ -190,6 +192,7 @@ impl Object {
             ],
             is_visible: true,
             render_order: 0,
+            texture_source: None,
         })
     }
 }
@@ -153,6 +153,8 @@ impl Object {
     /// Replaces the object's texture with provided one
     ///
     /// This function previously served the role of [crate::Object::set_texture_raw]
+    ///
+    /// The texture data is kept to build the texture again if the device is lost.
     pub fn set_texture(
         &mut self,
         name: impl AsRef<str>,
@@ -160,13 +162,19 @@ impl Object {
         texture_mode: TextureMode,
         renderer: &mut Renderer,
     ) -> Result<&mut Self, crate::error::Error> {
-        let texture = renderer.build_texture(name, texture_data, texture_mode)?;
-        Ok(self.set_texture_raw(texture))
+        let texture = renderer.build_texture(name.as_ref(), texture_data.clone(), texture_mode)?;
+        self.set_texture_raw(texture);
+        self.texture_source = Some((name.as_ref().into(), texture_data, texture_mode));
+        Ok(self)
     }
 
     /// Replaces the object's texture with provided one
+    ///
+    /// The texture can not be built again if the device is lost, and the default texture
+    /// replaces it then.
     pub fn set_texture_raw(&mut self, texture: Textures) -> &mut Self {
         self.pipeline.texture = PipelineData::Data(texture);
+        self.texture_source = None;
         self.changed = true;
 
         self
@@ -1,4 +1,5 @@
 use super::{Instance, Object};
+use crate::utils::default_resources::DEFAULT_TEXTURE;
 use crate::{Matrix4, PipelineData, Renderer};
 
 impl Object {
@@ -11,6 +12,53 @@ impl Object {
         self.changed = false;
     }
 
+    /// Builds all GPU resources of the object again from its data, such as on a new device
+    /// once the old one is lost. Resources referenced from other objects stay referenced.
+    /// The texture is built from the data given to [`Object::set_texture`], and the default
+    /// texture is used otherwise, or if that fails, which is returned as an error.
+    pub fn rebuild_gpu_resources(
+        &mut self,
+        renderer: &mut Renderer,
+    ) -> Result<(), crate::error::Error> {
+        let (uniform, uniform_layout) = self.update_uniform_buffer_inner(renderer);
+        self.uniform_layout = uniform_layout;
+        if let PipelineData::Data(_) = self.pipeline.uniform {
+            self.pipeline.uniform = PipelineData::Data(Some(uniform));
+        }
+        if let PipelineData::Data(_) = self.pipeline.vertex_buffer {
+            self.update_vertex_buffer(renderer);
+        }
+        if let PipelineData::Data(_) = self.pipeline.shader {
+            self.update_shader(renderer);
+        }
+        self.update_instance_buffer(renderer);
+        self.changed = false;
+
+        let mut result = Ok(());
+        if let PipelineData::Data(_) = self.pipeline.texture {
+            let default_texture = |renderer: &mut Renderer| {
+                renderer.build_texture(
+                    "Default Texture",
+                    crate::TextureData::Bytes(DEFAULT_TEXTURE.to_vec()),
+                    crate::prelude::TextureMode::Clamp,
+                )
+            };
+            let texture = match self.texture_source.clone() {
+                Some((name, data, mode)) => renderer.build_texture(name, data, mode),
+                None => default_texture(renderer),
+            };
+            let texture = match texture {
+                Ok(texture) => texture,
+                Err(error) => {
+                    result = Err(error);
+                    default_texture(renderer)?
+                }
+            };
+            self.pipeline.texture = PipelineData::Data(texture);
+        }
+        result
+    }
+
     /// Update and apply changes done to an object and returns a pipeline
     pub fn update_and_return(
         &mut self,
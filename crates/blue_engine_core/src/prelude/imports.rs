@@ -2,7 +2,7 @@ pub use downcast;
 pub use glam;
 pub use image;
 pub use wgpu;
-#[cfg(all(feature = "window", not(feature = "headless")))]
+#[cfg(any(feature = "window", feature = "headless"))]
 pub use winit;
 
 pub use wgpu::Backends;
@@ -18,21 +18,21 @@ pub use wgpu::TextureView;
 pub use winit::dpi::*;
 #[cfg(all(feature = "window", not(feature = "headless")))]
 pub use winit::event::DeviceEvent;
-#[cfg(all(feature = "window", not(feature = "headless")))]
+#[cfg(any(feature = "window", feature = "headless"))]
 pub use winit::event::ElementState;
 #[cfg(all(feature = "window", not(feature = "headless")))]
 pub use winit::event::Event;
 #[cfg(all(feature = "window", not(feature = "headless")))]
 pub use winit::event::KeyEvent;
-#[cfg(all(feature = "window", not(feature = "headless")))]
+#[cfg(any(feature = "window", feature = "headless"))]
 pub use winit::event::MouseButton;
 #[cfg(all(feature = "window", not(feature = "headless")))]
 pub use winit::event::WindowEvent;
 #[cfg(all(feature = "window", not(feature = "headless")))]
 pub use winit::event_loop::EventLoop;
-#[cfg(all(feature = "window", not(feature = "headless")))]
+#[cfg(any(feature = "window", feature = "headless"))]
 pub use winit::keyboard::Key;
-#[cfg(all(feature = "window", not(feature = "headless")))]
+#[cfg(any(feature = "window", feature = "headless"))]
 pub use winit::keyboard::KeyCode;
 #[cfg(all(feature = "window", not(feature = "headless")))]
 pub use winit::window::Fullscreen;
@@ -68,8 +68,60 @@ pub type Matrix4 = glam::Mat4;
 /// This type is 16 byte aligned.
 pub type Quaternion = glam::Quat;
 
+pub use crate::utils::adapter_selection::AdapterSelection;
+/// Adapter selection
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::utils::adapter_selection::enumerate_adapters;
+/// Entity components and systems
+pub use crate::utils::components::{Components, Systems};
+/// Typed resources and events
+pub use crate::utils::event_bus::EventBus;
+/// Fixed-timestep updates
+pub use crate::utils::fixed_timestep::FixedTimestep;
+/// Frame capture
+pub use crate::utils::frame_capture::{CaptureSequence, CapturedFrame, FrameCapture};
+/// Game states
+#[cfg(any(feature = "window", feature = "headless"))]
+pub use crate::utils::game_state::{GameState, StateStack};
+/// Gamepad input
+pub use crate::utils::gamepad::{
+    GamepadAxis, GamepadBackend, GamepadButton, GamepadEvent, GamepadId, Gamepads,
+    MockGamepadBackend, RumbleRequest,
+};
+/// Headless output
+#[cfg(any(feature = "window", feature = "headless"))]
+pub use crate::utils::headless_output::HeadlessOutputFormat;
+/// Input action mapping
+#[cfg(any(feature = "window", feature = "headless"))]
+pub use crate::utils::input_actions::{
+    ActionBinding, ActionState, AxisBinding, InputActions, InputBindings, InputModifiers,
+};
+/// Input recording and playback
+#[cfg(any(feature = "window", feature = "headless"))]
+pub use crate::utils::input_event::InputEvent;
+#[cfg(any(feature = "window", feature = "headless"))]
+pub use crate::utils::input_recording::{
+    InputRecorder, InputRecording, InputReplay, RecordedEvent,
+};
+/// Render resolution
+#[cfg(any(feature = "window", feature = "headless"))]
+pub use crate::utils::render_resolution::{DynamicResolution, UpscaleFilter};
+pub use crate::utils::resources::Resources;
+/// Timers and coroutines
+#[cfg(any(feature = "window", feature = "headless"))]
+pub use crate::utils::scheduler::{
+    ScheduleId, Scheduler, next_frame, wait_frames, wait_seconds, with_engine,
+};
+/// Synthetic input
+#[cfg(any(feature = "window", feature = "headless"))]
+pub use crate::utils::synthetic_input::SyntheticInput;
+/// Frame timing
+pub use crate::utils::time::{Clock, Time};
+/// Touch input
+#[cfg(any(feature = "window", feature = "headless"))]
+pub use crate::utils::touch::{Gesture, GestureSettings, TouchPhase, TouchPoint};
 /// Input helper
-#[cfg(all(feature = "window", not(feature = "headless")))]
+#[cfg(any(feature = "window", feature = "headless"))]
 pub use crate::utils::winit_input_helper::WinitInputHelper as InputHelper;
 pub use bytemuck::Pod;
 pub use bytemuck::Zeroable;
@@ -10,13 +10,13 @@ pub use crate::definition::{
     Pipeline, PipelineData, ShaderSettings, TextureData, TextureMode, VertexBuffers,
     pixel_to_cartesian,
 };
-pub use crate::engine::{Engine, EngineSettings};
+pub use crate::engine::{Engine, EngineSettings, RunMode, UpdateFunction};
 pub use crate::objects::{
     Instance, InstanceRaw, Object, ObjectSettings, ObjectStorage, RotateAmount, RotateAxis,
 };
-pub use crate::render::Renderer;
+pub use crate::render::{GpuContext, Renderer};
 #[cfg(all(feature = "window", not(feature = "headless")))]
-pub use crate::window::Window;
+pub use crate::window::{ExtraWindow, Window};
 
 /// The uint type used for indices and more
 #[cfg(not(feature = "u32"))]
@@ -107,7 +107,14 @@ unsafe impl Sync for Vertex {}
 
 /// Allows all events to be fetched directly, making it easier to add custom additions to the engine.
 pub trait Signal: Any {
-    /// This is ran as soon as the engine is properly initialized and all components are ready
+    /// Decides the order the signals run in, the lower first. Signals of the same
+    /// priority run in the order they were added
+    fn priority(&self) -> i32 {
+        0
+    }
+
+    /// This is ran as soon as the engine is properly initialized and all components are ready.
+    /// Signals added later are initialized right before the next hooks run
     #[allow(clippy::too_many_arguments)]
     fn init(&mut self, _engine: &mut crate::Engine) {}
 
@@ -116,10 +123,23 @@ pub trait Signal: Any {
     #[cfg(all(feature = "window", not(feature = "headless")))]
     fn device_events(&mut self, _engine: &mut crate::Engine, _events: &crate::DeviceEvent) {}
 
-    /// This is ran at the window events when available
+    /// This is ran at the window events when available. The id tells which window the
+    /// event belongs to, see `Engine::extra_window_name`
     #[allow(clippy::too_many_arguments)]
     #[cfg(all(feature = "window", not(feature = "headless")))]
-    fn window_events(&mut self, _engine: &mut crate::Engine, _events: &crate::WindowEvent) {}
+    fn window_events(
+        &mut self,
+        _engine: &mut crate::Engine,
+        _window_id: crate::winit::window::WindowId,
+        _events: &crate::WindowEvent,
+    ) {
+    }
+
+    /// This is ran at every fixed update, before the update loop of the frame
+    fn fixed_update(&mut self, _engine: &mut crate::Engine) {}
+
+    /// This is ran every frame, right before the update loop
+    fn pre_update(&mut self, _engine: &mut crate::Engine) {}
 
     /// ran before the frame is rendered
     #[allow(clippy::too_many_arguments)]
@@ -130,6 +150,19 @@ pub trait Signal: Any {
         _view: &crate::TextureView,
     ) {
     }
+
+    /// This is ran after the frame is submitted to the GPU
+    fn post_render(&mut self, _engine: &mut crate::Engine) {}
+
+    /// This is ran when the window or the output of the engine is resized
+    fn resize(&mut self, _engine: &mut crate::Engine, _size: WindowSize) {}
+
+    /// This is ran once the update loop stops, before the engine shuts down
+    fn shutdown(&mut self, _engine: &mut crate::Engine) {}
+
+    /// This is ran after the device was lost and the engine rebuilt its resources on a new
+    /// one. GPU resources the signal created on the old device must be created again
+    fn device_recreated(&mut self, _engine: &mut crate::Engine) {}
 }
 // The engine needs to know the functions of Signal to do things internally,
 // so we use downcast and not the std::any::Any
@@ -137,8 +170,14 @@ downcast!(dyn Signal);
 
 /// Handles the live events in the engine
 pub struct SignalStorage {
-    /// list of events with key and the event
+    /// list of events with key and the event, in the order they run
     pub events: Vec<(String, Box<dyn Signal>)>,
+    /// Additions and removals waiting for the running hooks to finish
+    pub(crate) pending: Vec<crate::definition::SignalChange>,
+    /// The amount of hooks running right now, as hooks can cause other hooks to run
+    pub(crate) running: u32,
+    /// Whether the `init` hooks already ran, so added events are initialized on their own
+    pub(crate) initialized: bool,
 }
 
 /// To hold the width and height of the engine frames
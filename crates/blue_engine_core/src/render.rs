@@ -29,6 +29,40 @@ pub struct Renderer {
     pub default_uniform_bind_group_layout: wgpu::BindGroupLayout,
     /// The depth buffer, used to render object depth
     pub depth_buffer: (wgpu::Texture, wgpu::TextureView, wgpu::Sampler),
+    /// The amount of samples per pixel for MSAA, `1` when it is disabled
+    pub(crate) sample_count: u32,
+    /// The multisampled color target the scene is drawn to and resolved from, when MSAA is
+    /// enabled
+    pub(crate) msaa_target: Option<wgpu::TextureView>,
+    /// Set when the pipelines of the objects have to be built again, such as after the
+    /// MSAA sample count changed
+    pub(crate) pipelines_outdated: bool,
+    /// The fixed resolution the scene is rendered at before it is scaled to the output,
+    /// instead of the render scale
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub(crate) render_resolution: Option<WindowSize>,
+    /// The size of the render resolution relative to the output
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub(crate) render_scale: f32,
+    /// The filter that scales the scene to the output
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub(crate) upscale_filter: crate::utils::render_resolution::UpscaleFilter,
+    /// The automatic render scale, if it is on
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub(crate) dynamic_resolution: Option<crate::utils::render_resolution::DynamicScaler>,
+    /// The offscreen targets of the render resolution
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub(crate) scaled_target: Option<crate::utils::render_resolution::ScaledTarget>,
+    /// The pass that scales the scene to the output, kept while the render resolution changes
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub(crate) upscale_pipeline: Option<crate::utils::render_resolution::UpscalePipeline>,
+    /// The settings the renderer was created with, to request a new device with once the
+    /// device is lost
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub(crate) settings: crate::EngineSettings,
+    /// Set by wgpu once the device the engine requested is lost
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub(crate) device_lost: Option<std::sync::Arc<std::sync::atomic::AtomicBool>>,
     /// The default data used within the renderer
     pub default_data: Option<(crate::Textures, crate::Shaders, crate::UniformBuffers)>,
     /// The camera used in the engine
@@ -38,13 +72,40 @@ pub struct Renderer {
     /// Scissor cut section of the screen to render to
     /// (x, y, width, height)
     pub scissor_rect: Option<(u32, u32, u32, u32)>,
-    /// The texture data that holds data for the headless mode
-    #[cfg(feature = "headless")]
+    /// Whether the frames are presented to a window or rendered offscreen
+    pub(crate) run_mode: crate::RunMode,
+    /// The texture view of the embedding application the next frame is rendered into
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub(crate) target_view: Option<wgpu::TextureView>,
+    /// The texture data that holds data for the headless mode.
+    /// The pixels are tightly packed, in the format of `headless_output`
+    #[cfg(any(feature = "window", feature = "headless"))]
     pub headless_texture_data: Vec<u8>,
+    /// The format of the headless output, set through `EngineSettings::headless_output`
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub(crate) headless_output: crate::utils::headless_output::HeadlessOutputFormat,
+    /// Frames requested through [`Renderer::capture_frame`], copied at the next render
+    pub(crate) frame_captures: Vec<crate::utils::frame_capture::FrameCapture>,
+    /// Captures every rendered frame to an image sequence while set
+    pub capture_sequence: Option<crate::utils::frame_capture::CaptureSequence>,
 }
 unsafe impl Sync for Renderer {}
 unsafe impl Send for Renderer {}
 
+/// An existing wgpu instance, adapter, device and queue, to embed the engine into another
+/// application or renderer that already owns them
+#[derive(Debug, Clone)]
+pub struct GpuContext {
+    /// The instance the adapter and the surfaces come from
+    pub instance: wgpu::Instance,
+    /// The adapter the device was requested from
+    pub adapter: wgpu::Adapter,
+    /// The device the engine creates its resources on
+    pub device: wgpu::Device,
+    /// The queue the engine submits its work to
+    pub queue: wgpu::Queue,
+}
+
 impl Renderer {
     /// Creates a new renderer.
     pub(crate) async fn new(
@@ -57,128 +118,218 @@ impl Renderer {
             ..Default::default()
         });
 
-        match instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: settings.power_preference,
-                compatible_surface: None,
-                force_fallback_adapter: false,
-            })
-            .await
+        let adapter = crate::utils::adapter_selection::select_adapter(&instance, &settings).await?;
+
+        let (device, queue) = adapter
+            .request_device(&Self::device_descriptor(&settings))
+            .await?;
+
+        #[allow(unused_mut)]
+        let mut renderer = Self::with_context(
+            size,
+            settings,
+            GpuContext {
+                instance,
+                adapter,
+                device,
+                queue,
+            },
+        )?;
+        // the device of an embedding application is recovered by the application
+        #[cfg(any(feature = "window", feature = "headless"))]
+        renderer.watch_device();
+
+        Ok(renderer)
+    }
+
+    /// The device the engine requests with the settings
+    pub(crate) fn device_descriptor(
+        settings: &crate::EngineSettings,
+    ) -> wgpu::DeviceDescriptor<'static> {
+        wgpu::DeviceDescriptor {
+            label: Some("Device"),
+            required_features: settings.features,
+            required_limits: settings.limits.clone(),
+            memory_hints: if settings.power_preference == wgpu::PowerPreference::HighPerformance {
+                wgpu::MemoryHints::Performance
+            } else {
+                wgpu::MemoryHints::MemoryUsage
+            },
+            trace: wgpu::Trace::Off,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a new renderer on an existing device
+    pub(crate) fn with_context(
+        size: WindowSize,
+        settings: crate::EngineSettings,
+        context: GpuContext,
+    ) -> Result<Self, crate::error::Error> {
+        let GpuContext {
+            instance,
+            adapter,
+            device,
+            queue,
+        } = context;
+
+        #[cfg(not(all(feature = "window", not(feature = "headless"))))]
+        if settings.run_mode == crate::RunMode::Windowed {
+            return Err(crate::error::Error::Custom(
+                "Opening a window needs the `window` feature".to_string(),
+            ));
+        }
+
+        #[cfg(any(feature = "window", feature = "headless"))]
+        if settings.run_mode == crate::RunMode::Headless
+            && settings.headless_output
+                == crate::utils::headless_output::HeadlessOutputFormat::Depth
+            && !adapter
+                .get_downlevel_capabilities()
+                .flags
+                .contains(wgpu::DownlevelFlags::DEPTH_TEXTURE_AND_BUFFER_COPIES)
         {
-            Ok(adapter) => {
-                let (device, queue) = adapter
-                    .request_device(&wgpu::DeviceDescriptor {
-                        label: Some("Device"),
-                        required_features: settings.features,
-                        required_limits: settings.limits,
-                        memory_hints: if settings.power_preference
-                            == wgpu::PowerPreference::HighPerformance
-                        {
-                            wgpu::MemoryHints::Performance
-                        } else {
-                            wgpu::MemoryHints::MemoryUsage
-                        },
-                        trace: wgpu::Trace::Off,
-                        ..Default::default()
-                    })
-                    .await?;
-
-                let texture_format = wgpu::TextureFormat::Bgra8UnormSrgb;
-
-                #[cfg(target_os = "android")]
-                let texture_format = wgpu::TextureFormat::Rgba8UnormSrgb;
-
-                let config = wgpu::SurfaceConfiguration {
-                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-                    format: texture_format, //wgpu::TextureFormat::Bgra8UnormSrgb,
-                    #[cfg(target_os = "android")]
-                    width: 1080,
-                    #[cfg(not(feature = "android"))]
-                    width: size.0,
-                    #[cfg(target_os = "android")]
-                    height: 2300,
-                    #[cfg(not(target_os = "android"))]
-                    height: size.1,
-                    #[cfg(target_os = "android")]
-                    present_mode: wgpu::PresentMode::Mailbox,
-                    #[cfg(not(target_os = "android"))]
-                    present_mode: settings.present_mode,
-                    alpha_mode: settings.alpha_mode,
-                    view_formats: vec![texture_format],
-                    desired_maximum_frame_latency: settings.desired_maximum_frame_latency,
-                };
-
-                let texture_bind_group_layout =
-                    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                        entries: &[
-                            wgpu::BindGroupLayoutEntry {
-                                binding: 0,
-                                visibility: wgpu::ShaderStages::FRAGMENT,
-                                ty: wgpu::BindingType::Texture {
-                                    sample_type: wgpu::TextureSampleType::Float {
-                                        filterable: true,
-                                    },
-                                    view_dimension: wgpu::TextureViewDimension::D2,
-                                    multisampled: false,
-                                },
-                                count: None,
-                            },
-                            wgpu::BindGroupLayoutEntry {
-                                binding: 1,
-                                visibility: wgpu::ShaderStages::FRAGMENT,
-                                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering), //comparison: false,
-                                // filtering: true,
-                                count: None,
-                            },
-                        ],
-                        label: Some("texture_bind_group_layout"),
-                    });
-
-                let default_uniform_bind_group_layout =
-                    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                        label: Some("uniform dynamic bind group layout"),
-                        entries: &[wgpu::BindGroupLayoutEntry {
-                            binding: 0,
-                            visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                            ty: wgpu::BindingType::Buffer {
-                                ty: wgpu::BufferBindingType::Uniform,
-                                has_dynamic_offset: false,
-                                min_binding_size: None,
-                            },
-                            count: None,
-                        }],
-                    });
-
-                let depth_buffer = Renderer::build_depth_buffer("Depth Buffer", &device, &config);
-
-                let mut renderer = Self {
-                    instance,
-                    adapter,
-                    surface: None,
-                    device,
-                    queue,
-                    config,
-                    size,
-
-                    texture_bind_group_layout,
-                    default_uniform_bind_group_layout,
-                    depth_buffer,
-
-                    default_data: None,
-                    camera: None,
-                    clear_color: wgpu::Color::BLACK,
-                    scissor_rect: None,
-
-                    #[cfg(feature = "headless")]
-                    headless_texture_data: Vec::<u8>::with_capacity((size.0 * size.1) as usize * 4),
-                };
-
-                renderer.build_default_data();
-
-                Ok(renderer)
-            }
-            Err(e) => Err(crate::error::Error::AdapterNotFound(e)),
+            return Err(crate::error::Error::Custom(
+                "The adapter does not support copying the depth buffer".to_string(),
+            ));
         }
+
+        let texture_format = wgpu::TextureFormat::Bgra8UnormSrgb;
+
+        #[cfg(target_os = "android")]
+        let texture_format = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: texture_format, //wgpu::TextureFormat::Bgra8UnormSrgb,
+            #[cfg(target_os = "android")]
+            width: 1080,
+            #[cfg(not(feature = "android"))]
+            width: size.0,
+            #[cfg(target_os = "android")]
+            height: 2300,
+            #[cfg(not(target_os = "android"))]
+            height: size.1,
+            #[cfg(target_os = "android")]
+            present_mode: wgpu::PresentMode::Mailbox,
+            #[cfg(not(target_os = "android"))]
+            present_mode: settings.present_mode,
+            alpha_mode: settings.alpha_mode,
+            view_formats: vec![texture_format],
+            desired_maximum_frame_latency: settings.desired_maximum_frame_latency,
+        };
+
+        let (texture_bind_group_layout, default_uniform_bind_group_layout) =
+            Self::build_bind_group_layouts(&device);
+
+        let sample_count = settings.msaa_samples.max(1);
+        let depth_buffer =
+            Renderer::build_depth_buffer("Depth Buffer", &device, &config, sample_count);
+
+        let mut renderer = Self {
+            instance,
+            adapter,
+            surface: None,
+            device,
+            queue,
+            config,
+            size,
+
+            texture_bind_group_layout,
+            default_uniform_bind_group_layout,
+            depth_buffer,
+            sample_count,
+            msaa_target: None,
+            pipelines_outdated: false,
+            #[cfg(any(feature = "window", feature = "headless"))]
+            render_resolution: None,
+            #[cfg(any(feature = "window", feature = "headless"))]
+            render_scale: 1.0,
+            #[cfg(any(feature = "window", feature = "headless"))]
+            upscale_filter: Default::default(),
+            #[cfg(any(feature = "window", feature = "headless"))]
+            dynamic_resolution: None,
+            #[cfg(any(feature = "window", feature = "headless"))]
+            scaled_target: None,
+            #[cfg(any(feature = "window", feature = "headless"))]
+            upscale_pipeline: None,
+            #[cfg(any(feature = "window", feature = "headless"))]
+            settings: settings.clone(),
+            #[cfg(any(feature = "window", feature = "headless"))]
+            device_lost: None,
+
+            default_data: None,
+            camera: None,
+            clear_color: wgpu::Color::BLACK,
+            scissor_rect: None,
+
+            run_mode: settings.run_mode,
+            #[cfg(any(feature = "window", feature = "headless"))]
+            target_view: None,
+            #[cfg(any(feature = "window", feature = "headless"))]
+            headless_texture_data: Vec::<u8>::new(),
+            #[cfg(any(feature = "window", feature = "headless"))]
+            headless_output: settings.headless_output,
+            frame_captures: Vec::new(),
+            capture_sequence: None,
+        };
+
+        renderer.check_msaa_samples(sample_count)?;
+        renderer.msaa_target = renderer.build_msaa_target(
+            "MSAA Target",
+            renderer.config.width,
+            renderer.config.height,
+        );
+
+        renderer.build_default_data();
+
+        Ok(renderer)
+    }
+
+    /// Creates the layouts of the texture and uniform bind groups every object uses
+    pub(crate) fn build_bind_group_layouts(
+        device: &wgpu::Device,
+    ) -> (wgpu::BindGroupLayout, wgpu::BindGroupLayout) {
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering), //comparison: false,
+                        // filtering: true,
+                        count: None,
+                    },
+                ],
+                label: Some("texture_bind_group_layout"),
+            });
+
+        let default_uniform_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("uniform dynamic bind group layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+
+        (texture_bind_group_layout, default_uniform_bind_group_layout)
     }
 
     pub(crate) fn build_default_data(&mut self) {
@@ -207,8 +358,7 @@ impl Renderer {
         }
     }
 
-    /// Resize the window.
-    #[cfg(all(not(feature = "headless"), feature = "window"))]
+    /// Resize the window, or the offscreen output when there is no surface.
     pub(crate) fn resize(&mut self, new_size: WindowSize) {
         // check if new_size is non-zero
         if new_size.0 != 0 && new_size.1 != 0 {
@@ -218,18 +368,228 @@ impl Renderer {
             #[cfg(not(target_os = "android"))]
             if let Some(surface) = self.surface.as_ref() {
                 surface.configure(&self.device, &self.config);
-                self.depth_buffer =
-                    Self::build_depth_buffer("Depth Buffer", &self.device, &self.config);
+            }
+            self.rebuild_frame_targets();
+        }
+    }
+
+    /// Rebuilds the depth buffer, the MSAA target and the targets of the render resolution
+    /// for the size and format of `config`
+    pub(crate) fn rebuild_frame_targets(&mut self) {
+        self.depth_buffer = Self::build_depth_buffer(
+            "Depth Buffer",
+            &self.device,
+            &self.config,
+            self.sample_count,
+        );
+        self.msaa_target =
+            self.build_msaa_target("MSAA Target", self.config.width, self.config.height);
+        #[cfg(any(feature = "window", feature = "headless"))]
+        self.rebuild_scaled_target();
+    }
+
+    /// Configures the surface again with `config`, and rebuilds the depth buffer and the
+    /// other targets that depend on it. Changes made to `config` directly are applied this way
+    pub fn reconfigure_surface(&mut self) {
+        #[cfg(not(target_os = "android"))]
+        if let Some(surface) = self.surface.as_ref() {
+            surface.configure(&self.device, &self.config);
+        }
+        self.rebuild_frame_targets();
+    }
+
+    /// Returns the present mode of the surface
+    pub fn present_mode(&self) -> wgpu::PresentMode {
+        self.config.present_mode
+    }
+
+    /// Switches the present mode, such as to turn VSync on or off, and reconfigures the
+    /// surface. Fails if the surface does not support the mode, while the `Auto` modes are
+    /// always supported
+    pub fn set_present_mode(
+        &mut self,
+        present_mode: wgpu::PresentMode,
+    ) -> Result<(), crate::error::Error> {
+        let automatic = matches!(
+            present_mode,
+            wgpu::PresentMode::AutoVsync | wgpu::PresentMode::AutoNoVsync
+        );
+        if let Some(surface) = self.surface.as_ref()
+            && !automatic
+            && !surface
+                .get_capabilities(&self.adapter)
+                .present_modes
+                .contains(&present_mode)
+        {
+            return Err(crate::error::Error::Custom(format!(
+                "The surface does not support the present mode {present_mode:?}"
+            )));
+        }
+
+        self.config.present_mode = present_mode;
+        self.reconfigure_surface();
+        Ok(())
+    }
+
+    /// Turns VSync on or off, picking the best present mode the surface supports
+    pub fn set_vsync(&mut self, vsync: bool) {
+        self.config.present_mode = if vsync {
+            wgpu::PresentMode::AutoVsync
+        } else {
+            wgpu::PresentMode::AutoNoVsync
+        };
+        self.reconfigure_surface();
+    }
+
+    /// Sets how many frames may be queued for presentation, and reconfigures the surface
+    pub fn set_desired_maximum_frame_latency(&mut self, frame_latency: u32) {
+        self.config.desired_maximum_frame_latency = frame_latency;
+        self.reconfigure_surface();
+    }
+
+    /// Sets how the alpha channel is composited with the windows behind, and reconfigures
+    /// the surface. Fails if the surface does not support the mode, while
+    /// [`wgpu::CompositeAlphaMode::Auto`] is always supported
+    pub fn set_alpha_mode(
+        &mut self,
+        alpha_mode: wgpu::CompositeAlphaMode,
+    ) -> Result<(), crate::error::Error> {
+        if let Some(surface) = self.surface.as_ref()
+            && alpha_mode != wgpu::CompositeAlphaMode::Auto
+            && !surface
+                .get_capabilities(&self.adapter)
+                .alpha_modes
+                .contains(&alpha_mode)
+        {
+            return Err(crate::error::Error::Custom(format!(
+                "The surface does not support the alpha mode {alpha_mode:?}"
+            )));
+        }
+
+        self.config.alpha_mode = alpha_mode;
+        self.reconfigure_surface();
+        Ok(())
+    }
+
+    /// Returns the amount of samples per pixel of MSAA, `1` when it is disabled
+    pub fn msaa_samples(&self) -> u32 {
+        self.sample_count
+    }
+
+    /// Enables MSAA with the amount of samples per pixel, or disables it with `1`. The depth
+    /// buffer and the MSAA targets are rebuilt right away, and the pipelines of the objects
+    /// before the next frame
+    pub fn set_msaa_samples(&mut self, samples: u32) -> Result<(), crate::error::Error> {
+        let samples = samples.max(1);
+        if samples == self.sample_count {
+            return Ok(());
+        }
+        self.check_msaa_samples(samples)?;
+
+        self.sample_count = samples;
+        self.rebuild_frame_targets();
+        self.build_default_data();
+        self.pipelines_outdated = true;
+        Ok(())
+    }
+
+    /// Checks that the adapter supports the MSAA sample count for the render targets
+    pub(crate) fn check_msaa_samples(&self, samples: u32) -> Result<(), crate::error::Error> {
+        #[cfg(any(feature = "window", feature = "headless"))]
+        if samples > 1
+            && self.run_mode == crate::RunMode::Headless
+            && self.headless_output == crate::utils::headless_output::HeadlessOutputFormat::Depth
+        {
+            return Err(crate::error::Error::Custom(
+                "The depth buffer can not be copied with MSAA enabled".to_string(),
+            ));
+        }
+
+        for format in [self.render_target_format(), crate::DEPTH_FORMAT] {
+            if !self
+                .adapter
+                .get_texture_format_features(format)
+                .flags
+                .sample_count_supported(samples)
+            {
+                return Err(crate::error::Error::Custom(format!(
+                    "The adapter does not support {samples}x MSAA for {format:?}"
+                )));
             }
         }
+        Ok(())
     }
 
-    /// Render the scene. Returns the command encoder, the texture view, and the surface texture.
+    /// Creates the multisampled color target of the given size in the render target format,
+    /// or `None` when MSAA is disabled
+    pub(crate) fn build_msaa_target(
+        &self,
+        label: &str,
+        width: u32,
+        height: u32,
+    ) -> Option<wgpu::TextureView> {
+        if self.sample_count <= 1 {
+            return None;
+        }
+        let format = self.render_target_format();
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: self.sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        Some(texture.create_view(&wgpu::TextureViewDescriptor::default()))
+    }
+
+    /// Configures the surface to render to, picking an sRGB format it supports. Fails if the
+    /// surface can not be used with the adapter
+    pub(crate) fn configure_surface(
+        &mut self,
+        surface: wgpu::Surface<'static>,
+    ) -> Result<(), crate::error::Error> {
+        let surface_capabilities = surface.get_capabilities(&self.adapter);
+        let Some(tex_format) = surface_capabilities
+            .formats
+            .iter()
+            .copied()
+            .find(|f| f.is_srgb())
+            .or(surface_capabilities.formats.first().copied())
+        else {
+            return Err(crate::error::Error::Custom(
+                "The surface is not compatible with the adapter".to_string(),
+            ));
+        };
+
+        self.config.format = tex_format;
+        self.config.view_formats = vec![tex_format];
+        // allows the frames to be captured
+        if surface_capabilities
+            .usages
+            .contains(wgpu::TextureUsages::COPY_SRC)
+        {
+            self.config.usage |= wgpu::TextureUsages::COPY_SRC;
+        }
+
+        surface.configure(&self.device, &self.config);
+        self.rebuild_frame_targets();
+        self.surface = Some(surface);
+        Ok(())
+    }
+
+    /// Render the scene. Returns the command encoder, the texture view, the surface texture,
+    /// and the staging buffer and render target of the headless mode.
     pub(crate) fn pre_render(
         &mut self,
-        objects: &ObjectStorage,
+        layers: &[(&ObjectStorage, &CameraContainer)],
         window_size: WindowSize,
-        camera: &CameraContainer,
     ) -> Result<
         Option<(
             wgpu::CommandEncoder,
@@ -239,17 +599,59 @@ impl Renderer {
         )>,
         wgpu::SurfaceError,
     > {
-        #[cfg(not(feature = "headless"))]
-        let surface = if let Some(ref surface) = self.surface {
-            surface
+        #[cfg(any(feature = "window", feature = "headless"))]
+        let headless = if self.run_mode == crate::RunMode::Headless {
+            Some(self.build_headless_target())
         } else {
-            return Ok(None);
+            None
         };
-        #[cfg(not(feature = "headless"))]
-        let frame = if let Ok(frame) = surface.get_current_texture() {
-            frame
+        #[cfg(not(any(feature = "window", feature = "headless")))]
+        let headless: Option<(wgpu::Buffer, wgpu::Texture)> = None;
+        #[cfg(any(feature = "window", feature = "headless"))]
+        let target_view = self.target_view.clone();
+        #[cfg(not(any(feature = "window", feature = "headless")))]
+        let target_view: Option<wgpu::TextureView> = None;
+
+        let frame = if headless.is_some() || target_view.is_some() {
+            None
         } else {
-            return Ok(None);
+            let surface = if let Some(ref surface) = self.surface {
+                surface
+            } else {
+                return Ok(None);
+            };
+            match surface.get_current_texture() {
+                Ok(frame) => Some(frame),
+                // the surface changed, such as the window being resized or moved to
+                // another screen, and needs to be configured again
+                Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                    surface.configure(&self.device, &self.config);
+                    match surface.get_current_texture() {
+                        Ok(frame) => Some(frame),
+                        // the resize event of the window is still on its way
+                        Err(wgpu::SurfaceError::Outdated) => return Ok(None),
+                        Err(error) => return Err(error),
+                    }
+                }
+                Err(wgpu::SurfaceError::Timeout) => {
+                    log::warn!("Timed out waiting for the next frame of the surface, skipping it");
+                    return Ok(None);
+                }
+                Err(error) => return Err(error),
+            }
+        };
+
+        let view = match (&headless, &frame) {
+            (Some((_, render_target)), _) => {
+                render_target.create_view(&wgpu::TextureViewDescriptor::default())
+            }
+            (None, Some(frame)) => frame
+                .texture
+                .create_view(&wgpu::TextureViewDescriptor::default()),
+            (None, None) => match target_view {
+                Some(target_view) => target_view,
+                None => return Ok(None),
+            },
         };
 
         let mut encoder = self
@@ -257,8 +659,38 @@ impl Renderer {
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
                 label: Some("Render Encoder"),
             });
+        #[cfg(any(feature = "window", feature = "headless"))]
+        if let Some(scaled_target) = self.scaled_target.as_ref() {
+            // render at the render resolution, then scale it to the output
+            self.encode_scene(
+                &mut encoder,
+                &scaled_target.color_view,
+                &scaled_target.depth_buffer.1,
+                scaled_target.msaa_target.as_ref(),
+                layers,
+                scaled_target.size,
+                "main",
+            );
+            scaled_target.encode_upscale(&mut encoder, &view);
+            return Ok(Some((encoder, view, frame, headless)));
+        }
 
-        #[cfg(feature = "headless")]
+        self.encode_scene(
+            &mut encoder,
+            &view,
+            &self.depth_buffer.1,
+            self.msaa_target.as_ref(),
+            layers,
+            window_size,
+            "main",
+        );
+
+        Ok(Some((encoder, view, frame, headless)))
+    }
+
+    /// Creates the offscreen render target of a headless frame, and the buffer it is copied to
+    #[cfg(any(feature = "window", feature = "headless"))]
+    fn build_headless_target(&self) -> (wgpu::Buffer, wgpu::Texture) {
         let render_target = self.device.create_texture(&wgpu::TextureDescriptor {
             size: wgpu::Extent3d {
                 width: self.config.width,
@@ -268,42 +700,87 @@ impl Renderer {
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: self.config.format,
+            format: self.headless_output.color_format(),
             usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::RENDER_ATTACHMENT,
             label: None,
-            view_formats: &[self.config.format],
+            view_formats: &[self.headless_output.color_format()],
         });
 
-        // ? There might be a way to enable both headless and normal rendering,
-        // ? However the cost of it can increase a lot
-        #[cfg(feature = "headless")]
-        let view = render_target.create_view(&wgpu::TextureViewDescriptor::default());
-        #[cfg(not(feature = "headless"))]
-        let view = frame
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-
-        #[cfg(feature = "headless")]
         let headless_output_staging_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
             label: None,
-            size: self.headless_texture_data.capacity() as u64,
+            size: (crate::utils::frame_capture::padded_bytes_per_row(
+                self.config.width,
+                self.headless_output.bytes_per_pixel(),
+            ) * self.config.height) as u64,
             usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
             mapped_at_creation: false,
         });
 
+        (headless_output_staging_buffer, render_target)
+    }
+
+    /// Records a render pass for each layer of objects into the encoder, drawing to the given
+    /// color and depth views. The first layer clears the color, and the depth is cleared
+    /// between layers so each one is drawn over the ones before it. Objects without a
+    /// camera effect use the default camera of their layer. With MSAA, the layers are drawn
+    /// to the multisampled view and resolved to the color view
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn encode_scene(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        depth_view: &wgpu::TextureView,
+        msaa_view: Option<&wgpu::TextureView>,
+        layers: &[(&ObjectStorage, &CameraContainer)],
+        window_size: WindowSize,
+        default_camera: &str,
+    ) {
+        for (index, (objects, camera)) in layers.iter().enumerate() {
+            self.encode_layer(
+                encoder,
+                view,
+                depth_view,
+                msaa_view,
+                objects,
+                camera,
+                window_size,
+                default_camera,
+                index == 0,
+            );
+        }
+    }
+
+    /// Records the render pass of one layer of objects
+    #[allow(clippy::too_many_arguments)]
+    fn encode_layer(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        depth_view: &wgpu::TextureView,
+        msaa_view: Option<&wgpu::TextureView>,
+        objects: &ObjectStorage,
+        camera: &CameraContainer,
+        window_size: WindowSize,
+        default_camera: &str,
+        clear: bool,
+    ) {
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Render pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &view,
-                resolve_target: None,
+                view: msaa_view.unwrap_or(view),
+                resolve_target: msaa_view.map(|_| view),
                 ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(self.clear_color),
+                    load: if clear {
+                        wgpu::LoadOp::Clear(self.clear_color)
+                    } else {
+                        wgpu::LoadOp::Load
+                    },
                     store: wgpu::StoreOp::Store,
                 },
                 depth_slice: None,
             })],
             depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                view: &self.depth_buffer.1,
+                view: depth_view,
                 depth_ops: Some(wgpu::Operations {
                     load: wgpu::LoadOp::Clear(1.0),
                     store: wgpu::StoreOp::Store,
@@ -344,7 +821,7 @@ impl Renderer {
                     render_pass.set_bind_group(1, &camera.uniform_data, &[]);
                 }
             } else {
-                if let Some(main_camera) = camera.get("main") {
+                if let Some(main_camera) = camera.get(default_camera) {
                     render_pass.set_bind_group(1, &main_camera.uniform_data, &[]);
                 }
             }
@@ -383,52 +860,44 @@ impl Renderer {
                 }
             }
         }
-        drop(render_pass);
-
-        Ok(Some((
-            encoder,
-            view,
-            #[cfg(feature = "headless")]
-            None,
-            #[cfg(not(feature = "headless"))]
-            Some(frame),
-            #[cfg(feature = "headless")]
-            Some((headless_output_staging_buffer, render_target)),
-            #[cfg(not(feature = "headless"))]
-            None,
-        )))
-    }
-
-    /// Render the scene.
+    }
+
+    /// Render the scene. In the headless run mode, the frame is read back into
+    /// `headless_texture_data`, which fails if the device is lost meanwhile.
     pub(crate) fn render(
         &mut self,
         encoder: wgpu::CommandEncoder,
-        _frame: Option<wgpu::SurfaceTexture>,
-        _headless: Option<(wgpu::Buffer, wgpu::Texture)>,
-    ) {
-        #[cfg(feature = "headless")]
-        {
-            #[allow(clippy::expect_used)]
-            let (output_staging_buffer, render_target) =
-                _headless.expect("Error unpacking headless content. This should not error!");
-
+        frame: Option<wgpu::SurfaceTexture>,
+        headless: Option<(wgpu::Buffer, wgpu::Texture)>,
+    ) -> Result<(), crate::error::Error> {
+        #[cfg(any(feature = "window", feature = "headless"))]
+        if let Some((output_staging_buffer, render_target)) = headless {
             let mut encoder = encoder;
 
-            #[cfg(feature = "headless")]
+            let bytes_per_pixel = self.headless_output.bytes_per_pixel();
+            let padded_bytes_per_row = crate::utils::frame_capture::padded_bytes_per_row(
+                self.config.width,
+                bytes_per_pixel,
+            );
+            let (source, aspect) = match self.headless_output {
+                crate::utils::headless_output::HeadlessOutputFormat::Depth => {
+                    (&self.depth_buffer.0, wgpu::TextureAspect::DepthOnly)
+                }
+                _ => (&render_target, wgpu::TextureAspect::All),
+            };
             encoder.copy_texture_to_buffer(
                 wgpu::TexelCopyTextureInfo {
-                    texture: &render_target,
+                    texture: source,
                     mip_level: 0,
                     origin: wgpu::Origin3d::ZERO,
-                    aspect: wgpu::TextureAspect::All,
+                    aspect,
                 },
                 wgpu::TexelCopyBufferInfo {
                     buffer: &output_staging_buffer,
                     layout: wgpu::TexelCopyBufferLayout {
                         offset: 0,
-                        // This needs to be a multiple of 256. Normally we would need to pad
-                        // it but we here know it will work out anyways.
-                        bytes_per_row: Some(self.config.width * 4),
+                        // rows must be aligned to 256 bytes, the padding is removed below
+                        bytes_per_row: Some(padded_bytes_per_row),
                         rows_per_image: Some(self.config.height),
                     },
                 },
@@ -439,34 +908,276 @@ impl Renderer {
                 },
             );
 
+            let captures = self.encode_captures(&mut encoder, &render_target);
+
             // submit will accept anything that implements IntoIter
             self.queue.submit(Some(encoder.finish()));
+            self.map_captures(captures);
 
-            pollster::block_on(async {
-                let buffer_slice = output_staging_buffer.slice(..);
-                let (sender, receiver) = std::sync::mpsc::channel();
-                buffer_slice.map_async(wgpu::MapMode::Read, move |r| sender.send(r).unwrap());
-                self.device
-                    .poll(wgpu::PollType::wait_indefinitely())
-                    .unwrap();
-                receiver.recv().unwrap().unwrap();
+            let buffer_slice = output_staging_buffer.slice(..);
+            let (sender, receiver) = std::sync::mpsc::channel();
+            buffer_slice.map_async(wgpu::MapMode::Read, move |r| {
+                let _ = sender.send(r);
+            });
+            self.device.poll(wgpu::PollType::wait_indefinitely())?;
+            receiver.recv().map_err(|_| {
+                crate::error::Error::Custom(
+                    "The rendered frame was never mapped for reading".to_string(),
+                )
+            })??;
+            {
+                let view = buffer_slice.get_mapped_range();
+                let bytes_per_row = (self.config.width * bytes_per_pixel) as usize;
+                for row in view
+                    .chunks(padded_bytes_per_row as usize)
+                    .take(self.config.height as usize)
                 {
-                    let view = buffer_slice.get_mapped_range();
-                    self.headless_texture_data.extend_from_slice(&view[..]);
+                    self.headless_texture_data
+                        .extend_from_slice(&row[..bytes_per_row]);
                 }
-                output_staging_buffer.unmap();
-            });
+            }
+            output_staging_buffer.unmap();
+            return Ok(());
         }
+        #[cfg(not(any(feature = "window", feature = "headless")))]
+        let _ = headless;
+
+        if let Some(frame) = frame {
+            #[cfg(any(feature = "window", feature = "headless"))]
+            let mut encoder = encoder;
+            #[cfg(any(feature = "window", feature = "headless"))]
+            let captures = self.encode_captures(&mut encoder, &frame.texture);
 
-        #[cfg(not(feature = "headless"))]
-        if let Some(frame) = _frame {
             // submit will accept anything that implements IntoIter
             self.queue.submit(Some(encoder.finish()));
+            #[cfg(any(feature = "window", feature = "headless"))]
+            self.map_captures(captures);
 
             frame.present();
+        } else {
+            // rendered into the texture view of the embedding application
+            self.queue.submit(Some(encoder.finish()));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the format of the color target the scene is rendered to
+    pub fn render_target_format(&self) -> wgpu::TextureFormat {
+        #[cfg(any(feature = "window", feature = "headless"))]
+        if self.run_mode == crate::RunMode::Headless {
+            return self.headless_output.color_format();
+        }
+        self.config.format
+    }
+
+    /// Returns whether the frames are presented to a window or rendered offscreen
+    pub fn run_mode(&self) -> crate::RunMode {
+        self.run_mode
+    }
+
+    /// Creates a texture the scene can be rendered to with [`Renderer::render_to_texture`],
+    /// next to the window or the headless output. The texture can be sampled, and copied
+    /// to the CPU with [`Renderer::capture_texture`]
+    pub fn create_render_target(
+        &self,
+        label: impl AsRef<str>,
+        width: u32,
+        height: u32,
+    ) -> wgpu::Texture {
+        self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label.as_ref()),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.render_target_format(),
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        })
+    }
+
+    /// Renders the objects to the texture right away, with the object and camera data of
+    /// the last rendered frame. The texture must be in the [`Renderer::render_target_format`]
+    /// and have `RENDER_ATTACHMENT` usage, such as the ones from
+    /// [`Renderer::create_render_target`].
+    pub fn render_to_texture(
+        &self,
+        objects: &ObjectStorage,
+        camera: &CameraContainer,
+        texture: &wgpu::Texture,
+    ) {
+        let size = (texture.width(), texture.height());
+        let mut config = self.config.clone();
+        config.width = size.0;
+        config.height = size.1;
+        let depth_buffer = Self::build_depth_buffer(
+            "Render Target Depth Buffer",
+            &self.device,
+            &config,
+            self.sample_count,
+        );
+        let msaa_target = self.build_msaa_target("Render Target MSAA", size.0, size.1);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Target Encoder"),
+            });
+        self.encode_scene(
+            &mut encoder,
+            &view,
+            &depth_buffer.1,
+            msaa_target.as_ref(),
+            &[(objects, camera)],
+            size,
+            "main",
+        );
+        self.queue.submit(Some(encoder.finish()));
+    }
+
+    /// Returns the format of `headless_texture_data`
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub fn headless_output_format(&self) -> crate::utils::headless_output::HeadlessOutputFormat {
+        self.headless_output
+    }
+
+    /// Saves the last headless frame as a PNG file
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub fn save_headless_png(
+        &self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<(), crate::error::Error> {
+        crate::utils::headless_output::write_png(
+            &self.headless_texture_data,
+            self.config.width,
+            self.config.height,
+            self.headless_output,
+            path,
+        )
+    }
+
+    /// Saves the last headless frame as an OpenEXR file
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub fn save_headless_exr(
+        &self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<(), crate::error::Error> {
+        crate::utils::headless_output::write_exr(
+            &self.headless_texture_data,
+            self.config.width,
+            self.config.height,
+            self.headless_output,
+            path,
+        )
+    }
+
+    /// Copies the next rendered frame to the CPU. The copy happens at the end of the
+    /// next render, and the returned handle resolves once the copy is done.
+    ///
+    /// In windowed mode this needs the surface to support `COPY_SRC`, which the engine
+    /// requests whenever the platform allows it.
+    pub fn capture_frame(&mut self) -> crate::utils::frame_capture::FrameCapture {
+        let capture = crate::utils::frame_capture::FrameCapture::new(self.device.clone());
+        self.frame_captures.push(capture.share());
+        capture
+    }
+
+    /// Copies any texture to the CPU right away. The texture must have `COPY_SRC` usage
+    /// and a 4 bytes per pixel color format.
+    pub fn capture_texture(
+        &self,
+        texture: &wgpu::Texture,
+    ) -> crate::utils::frame_capture::FrameCapture {
+        let capture = crate::utils::frame_capture::FrameCapture::new(self.device.clone());
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Frame Capture Encoder"),
+            });
+        capture.encode(&self.device, &mut encoder, texture);
+        self.queue.submit(Some(encoder.finish()));
+        capture.map();
+        capture
+    }
+
+    /// Starts capturing every rendered frame to numbered PNG files in the directory
+    pub fn start_capture_sequence(
+        &mut self,
+        directory: impl Into<std::path::PathBuf>,
+        prefix: impl AsRef<str>,
+    ) -> Result<(), crate::error::Error> {
+        self.capture_sequence = Some(crate::utils::frame_capture::CaptureSequence::new(
+            directory, prefix,
+        )?);
+        Ok(())
+    }
+
+    /// Stops capturing the frames, saving the ones that are still being copied
+    pub fn stop_capture_sequence(&mut self) {
+        if let Some(mut sequence) = self.capture_sequence.take() {
+            sequence.finish();
         }
     }
 
+    /// Records the copies of the requested captures into the encoder
+    #[cfg(any(feature = "window", feature = "headless"))]
+    fn encode_captures(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+    ) -> Vec<crate::utils::frame_capture::FrameCapture> {
+        let mut captures = std::mem::take(&mut self.frame_captures);
+        if let Some(sequence) = self.capture_sequence.as_mut() {
+            let capture = crate::utils::frame_capture::FrameCapture::new(self.device.clone());
+            sequence.push(capture.share());
+            captures.push(capture);
+        }
+
+        for capture in captures.iter() {
+            capture.encode(&self.device, encoder, texture);
+        }
+        captures
+    }
+
+    /// Starts reading the captures back once their copies are submitted
+    #[cfg(any(feature = "window", feature = "headless"))]
+    fn map_captures(&mut self, captures: Vec<crate::utils::frame_capture::FrameCapture>) {
+        for capture in captures.iter() {
+            capture.map();
+        }
+        if let Some(sequence) = self.capture_sequence.as_mut() {
+            sequence.save_ready();
+        }
+    }
+
+    /// Returns the name, backend and type of the adapter the engine renders with
+    pub fn adapter_info(&self) -> wgpu::AdapterInfo {
+        self.adapter.get_info()
+    }
+
+    /// Returns the best limits the adapter supports
+    pub fn adapter_limits(&self) -> wgpu::Limits {
+        self.adapter.limits()
+    }
+
+    /// Returns the limits the device was created with
+    pub fn device_limits(&self) -> wgpu::Limits {
+        self.device.limits()
+    }
+
+    /// Returns true if the engine renders on the CPU instead of a GPU
+    pub fn is_software_adapter(&self) -> bool {
+        self.adapter.get_info().device_type == wgpu::DeviceType::Cpu
+    }
+
     /// Sets the background color
     pub fn set_clear_color(&mut self, r: f64, g: f64, b: f64, a: f64) {
         self.clear_color = wgpu::Color { r, g, b, a }
@@ -520,3 +1231,87 @@ fn get_pipeline_uniform_buffer<'a>(
         PipelineData::Data(data) => Some(data),
     }
 }
+
+#[cfg(all(test, any(feature = "window", feature = "headless")))]
+mod tests {
+    use crate::Engine;
+
+    /// A headless engine with a triangle in view
+    fn triangle_engine() -> Engine {
+        let mut engine = crate::utils::headless_test_engine();
+        crate::primitive_shapes::triangle(
+            "triangle",
+            crate::ObjectSettings::default(),
+            &mut engine.renderer,
+            &mut engine.objects,
+        )
+        .unwrap();
+        engine
+    }
+
+    /// Renders one frame and returns it
+    fn render(engine: &mut Engine) -> Vec<u8> {
+        engine.step().unwrap();
+        engine.renderer.headless_texture_data.clone()
+    }
+
+    #[test]
+    fn toggle_msaa() {
+        let mut engine = triangle_engine();
+        let aliased = render(&mut engine);
+        assert!(aliased.chunks_exact(4).any(|pixel| pixel != &aliased[..4]));
+
+        // the pipelines are rebuilt before the next frame
+        engine.renderer.set_msaa_samples(4).unwrap();
+        assert_eq!(engine.renderer.msaa_samples(), 4);
+        assert!(engine.renderer.pipelines_outdated);
+        let smoothed = render(&mut engine);
+        assert!(!engine.renderer.pipelines_outdated);
+        assert_eq!(smoothed.len(), aliased.len());
+        assert_ne!(smoothed, aliased);
+
+        engine.renderer.set_msaa_samples(1).unwrap();
+        assert_eq!(engine.renderer.msaa_samples(), 1);
+        assert_eq!(render(&mut engine), aliased);
+    }
+
+    #[test]
+    fn unsupported_msaa_samples() {
+        let mut engine = triangle_engine();
+        let before = render(&mut engine);
+
+        assert!(engine.renderer.set_msaa_samples(3).is_err());
+        assert_eq!(engine.renderer.msaa_samples(), 1);
+        assert!(!engine.renderer.pipelines_outdated);
+        assert_eq!(render(&mut engine), before);
+    }
+
+    #[test]
+    fn surface_settings_without_a_surface() {
+        let mut engine = triangle_engine();
+        let before = render(&mut engine);
+        let renderer = &mut engine.renderer;
+
+        renderer
+            .set_present_mode(wgpu::PresentMode::Immediate)
+            .unwrap();
+        assert_eq!(renderer.present_mode(), wgpu::PresentMode::Immediate);
+        renderer.set_vsync(true);
+        assert_eq!(renderer.present_mode(), wgpu::PresentMode::AutoVsync);
+        renderer.set_vsync(false);
+        assert_eq!(renderer.present_mode(), wgpu::PresentMode::AutoNoVsync);
+
+        renderer.set_desired_maximum_frame_latency(1);
+        assert_eq!(renderer.config.desired_maximum_frame_latency, 1);
+        renderer
+            .set_alpha_mode(wgpu::CompositeAlphaMode::PreMultiplied)
+            .unwrap();
+        assert_eq!(
+            renderer.config.alpha_mode,
+            wgpu::CompositeAlphaMode::PreMultiplied
+        );
+
+        // none of them change the offscreen output
+        assert_eq!(render(&mut engine), before);
+    }
+}
@@ -0,0 +1,196 @@
+/*
+ * Blue Engine by Elham Aryanpur
+ *
+ * The license is same as the one on the root.
+*/
+
+/// Decides which adapter (GPU or software renderer) the engine renders with
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub enum AdapterSelection {
+    /// Lets wgpu pick the adapter, based on `EngineSettings::power_preference`
+    #[default]
+    Default,
+    /// The first adapter whose name contains the text, ignoring case
+    Name(String),
+    /// The first adapter of the backend
+    Backend(wgpu::Backend),
+    /// The first adapter of the type, such as a discrete GPU or the CPU
+    DeviceType(wgpu::DeviceType),
+    /// The adapter at the index of [`enumerate_adapters`]
+    Index(usize),
+    /// The first adapter the function accepts. Can not be saved to config files
+    #[serde(skip)]
+    Filter(fn(&wgpu::AdapterInfo) -> bool),
+}
+
+impl AdapterSelection {
+    /// Checks if the adapter fits the selection
+    pub fn matches(&self, index: usize, info: &wgpu::AdapterInfo) -> bool {
+        match self {
+            Self::Default => true,
+            Self::Name(name) => info.name.to_lowercase().contains(&name.to_lowercase()),
+            Self::Backend(backend) => info.backend == *backend,
+            Self::DeviceType(device_type) => info.device_type == *device_type,
+            Self::Index(selected) => index == *selected,
+            Self::Filter(filter) => filter(info),
+        }
+    }
+}
+
+/// Lists the adapters available on the backends, in the order [`AdapterSelection::Index`] uses
+#[cfg(not(target_arch = "wasm32"))]
+pub fn enumerate_adapters(backends: wgpu::Backends) -> Vec<wgpu::AdapterInfo> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        backends,
+        ..Default::default()
+    });
+    instance
+        .enumerate_adapters(backends)
+        .iter()
+        .map(|adapter| adapter.get_info())
+        .collect()
+}
+
+/// Checks if the adapter fits the selection of the settings. With `force_fallback_adapter`
+/// set, only software adapters fit
+#[cfg(not(target_arch = "wasm32"))]
+fn fits(settings: &crate::EngineSettings, index: usize, info: &wgpu::AdapterInfo) -> bool {
+    settings.adapter.matches(index, info)
+        && (!settings.force_fallback_adapter || info.device_type == wgpu::DeviceType::Cpu)
+}
+
+/// Picks the adapter according to the settings, falling back to the software adapter
+/// if none fits and `fallback_to_software` is enabled
+pub(crate) async fn select_adapter(
+    instance: &wgpu::Instance,
+    settings: &crate::EngineSettings,
+) -> Result<wgpu::Adapter, crate::error::Error> {
+    let request = |force_fallback_adapter| {
+        instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: settings.power_preference,
+            compatible_surface: None,
+            force_fallback_adapter,
+        })
+    };
+
+    let selected = match &settings.adapter {
+        AdapterSelection::Default => request(settings.force_fallback_adapter).await,
+        #[cfg(not(target_arch = "wasm32"))]
+        selection => {
+            let found = instance
+                .enumerate_adapters(settings.backends)
+                .into_iter()
+                .enumerate()
+                .find(|(index, adapter)| fits(settings, *index, &adapter.get_info()))
+                .map(|(_, adapter)| adapter);
+            match found {
+                Some(adapter) => Ok(adapter),
+                None => {
+                    let reason = if settings.force_fallback_adapter {
+                        format!("No software adapter matches the selection {selection:?}")
+                    } else {
+                        format!("No adapter matches the selection {selection:?}")
+                    };
+                    if !settings.fallback_to_software {
+                        return Err(crate::error::Error::Custom(reason));
+                    }
+                    return fall_back(request(true).await, reason);
+                }
+            }
+        }
+        // adapters cannot be listed on the web
+        #[cfg(target_arch = "wasm32")]
+        _ => request(settings.force_fallback_adapter).await,
+    };
+
+    match selected {
+        Ok(adapter) => Ok(adapter),
+        Err(error) if settings.fallback_to_software && !settings.force_fallback_adapter => {
+            fall_back(request(true).await, error.to_string())
+        }
+        Err(error) => Err(crate::error::Error::AdapterNotFound(error)),
+    }
+}
+
+/// Returns the software adapter, once falling back was allowed
+fn fall_back(
+    software: Result<wgpu::Adapter, wgpu::RequestAdapterError>,
+    reason: String,
+) -> Result<wgpu::Adapter, crate::error::Error> {
+    let adapter = software?;
+    log::warn!(
+        "{reason}, falling back to the software adapter {}",
+        adapter.get_info().name
+    );
+    Ok(adapter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter(
+        name: &str,
+        device_type: wgpu::DeviceType,
+        backend: wgpu::Backend,
+    ) -> wgpu::AdapterInfo {
+        wgpu::AdapterInfo {
+            name: name.to_string(),
+            vendor: 0,
+            device: 0,
+            device_type,
+            driver: String::new(),
+            driver_info: String::new(),
+            backend,
+        }
+    }
+
+    #[test]
+    fn matches() {
+        let gpu = adapter(
+            "NVIDIA GeForce",
+            wgpu::DeviceType::DiscreteGpu,
+            wgpu::Backend::Vulkan,
+        );
+        let cpu = adapter(
+            "llvmpipe (LLVM 15)",
+            wgpu::DeviceType::Cpu,
+            wgpu::Backend::Gl,
+        );
+
+        assert!(AdapterSelection::Default.matches(0, &gpu));
+        assert!(AdapterSelection::Name("geforce".to_string()).matches(0, &gpu));
+        assert!(!AdapterSelection::Name("geforce".to_string()).matches(1, &cpu));
+        assert!(AdapterSelection::Backend(wgpu::Backend::Gl).matches(1, &cpu));
+        assert!(!AdapterSelection::Backend(wgpu::Backend::Gl).matches(0, &gpu));
+        assert!(AdapterSelection::DeviceType(wgpu::DeviceType::Cpu).matches(1, &cpu));
+        assert!(AdapterSelection::Index(1).matches(1, &cpu));
+        assert!(!AdapterSelection::Index(1).matches(0, &gpu));
+        assert!(AdapterSelection::Filter(|info| info.name.starts_with("llvm")).matches(1, &cpu));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn forced_fallback_only_fits_software() {
+        let gpu = adapter(
+            "NVIDIA GeForce",
+            wgpu::DeviceType::DiscreteGpu,
+            wgpu::Backend::Vulkan,
+        );
+        let cpu = adapter(
+            "llvmpipe (LLVM 15)",
+            wgpu::DeviceType::Cpu,
+            wgpu::Backend::Vulkan,
+        );
+        let mut settings = crate::EngineSettings {
+            adapter: AdapterSelection::Backend(wgpu::Backend::Vulkan),
+            ..Default::default()
+        };
+        assert!(fits(&settings, 0, &gpu));
+        assert!(fits(&settings, 1, &cpu));
+
+        settings.force_fallback_adapter = true;
+        assert!(!fits(&settings, 0, &gpu));
+        assert!(fits(&settings, 1, &cpu));
+    }
+}
@@ -0,0 +1,451 @@
+/*
+ * Blue Engine by Elham Aryanpur
+ *
+ * The license is same as the one on the root.
+*/
+
+use crate::{Engine, Object, ObjectStorage};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The components of one type, keyed by their entity
+struct ComponentStore<T> {
+    values: HashMap<Arc<str>, T>,
+}
+
+trait AnyStore: Any {
+    fn remove_entity(&mut self, entity: &str);
+    fn retain_entities(&mut self, keep: &dyn Fn(&str) -> bool);
+}
+
+impl<T: 'static> AnyStore for ComponentStore<T> {
+    fn remove_entity(&mut self, entity: &str) {
+        self.values.remove(entity);
+    }
+
+    fn retain_entities(&mut self, keep: &dyn Fn(&str) -> bool) {
+        self.values.retain(|entity, _| keep(entity));
+    }
+}
+
+/// Gameplay data attached to entities. An entity is a name, so every object is an entity
+/// by its name, and entities without an object can carry data as well.
+///
+/// There is at most one component of each type per entity. Queries go over one or two
+/// types of components, and further types are checked within the loop, such as with
+/// [`Components::has`].
+/// ```
+/// # use blue_engine_core::Components;
+/// struct Velocity(f32);
+/// struct Health(u32);
+///
+/// let mut components = Components::new();
+/// components.insert("player", Velocity(2.0));
+/// components.insert("player", Health(100));
+/// components.insert("tree", Health(10));
+///
+/// for (entity, velocity, health) in components.query2_mut::<Velocity, Health>() {
+///     velocity.0 *= 0.5;
+///     health.0 -= 1;
+/// }
+/// assert_eq!(components.get::<Health>("player").map(|i| i.0), Some(99));
+/// ```
+#[derive(Default)]
+pub struct Components {
+    stores: HashMap<TypeId, Box<dyn AnyStore>>,
+}
+
+impl Components {
+    /// Creates an empty component storage
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn store<T: 'static>(&self) -> Option<&ComponentStore<T>> {
+        let store: &dyn Any = self.stores.get(&TypeId::of::<T>())?.as_ref();
+        store.downcast_ref::<ComponentStore<T>>()
+    }
+
+    fn store_mut<T: 'static>(&mut self) -> Option<&mut ComponentStore<T>> {
+        let store: &mut dyn Any = self.stores.get_mut(&TypeId::of::<T>())?.as_mut();
+        store.downcast_mut::<ComponentStore<T>>()
+    }
+
+    /// Attaches the component to the entity, returning the previous one of the same type
+    pub fn insert<T: 'static>(&mut self, entity: impl AsRef<str>, component: T) -> Option<T> {
+        let store: &mut dyn Any = self
+            .stores
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| {
+                Box::new(ComponentStore::<T> {
+                    values: HashMap::new(),
+                })
+            })
+            .as_mut();
+
+        store
+            .downcast_mut::<ComponentStore<T>>()
+            .and_then(|store| store.values.insert(entity.as_ref().into(), component))
+    }
+
+    /// Gets the component of the entity
+    pub fn get<T: 'static>(&self, entity: impl AsRef<str>) -> Option<&T> {
+        self.store::<T>()?.values.get(entity.as_ref())
+    }
+
+    /// Gets the component of the entity mutably
+    pub fn get_mut<T: 'static>(&mut self, entity: impl AsRef<str>) -> Option<&mut T> {
+        self.store_mut::<T>()?.values.get_mut(entity.as_ref())
+    }
+
+    /// Detaches the component from the entity and returns it
+    pub fn remove<T: 'static>(&mut self, entity: impl AsRef<str>) -> Option<T> {
+        self.store_mut::<T>()?.values.remove(entity.as_ref())
+    }
+
+    /// Checks if the entity has a component of the type
+    pub fn has<T: 'static>(&self, entity: impl AsRef<str>) -> bool {
+        self.store::<T>()
+            .is_some_and(|store| store.values.contains_key(entity.as_ref()))
+    }
+
+    /// Removes all components of the entity
+    pub fn despawn(&mut self, entity: impl AsRef<str>) {
+        self.stores
+            .values_mut()
+            .for_each(|store| store.remove_entity(entity.as_ref()));
+    }
+
+    /// Removes the components of the entities that are not objects anymore
+    pub fn retain_objects(&mut self, objects: &ObjectStorage) {
+        self.stores
+            .values_mut()
+            .for_each(|store| store.retain_entities(&|entity| objects.contains_key(entity)));
+    }
+
+    /// Returns the amount of components of the type
+    pub fn count<T: 'static>(&self) -> usize {
+        self.store::<T>().map_or(0, |store| store.values.len())
+    }
+
+    /// Iterates over the entities with a component of the type
+    pub fn query<T: 'static>(&self) -> impl Iterator<Item = (&Arc<str>, &T)> {
+        self.store::<T>()
+            .into_iter()
+            .flat_map(|store| store.values.iter())
+    }
+
+    /// Iterates over the entities with a component of the type, mutably
+    pub fn query_mut<T: 'static>(&mut self) -> impl Iterator<Item = (&Arc<str>, &mut T)> {
+        self.store_mut::<T>()
+            .into_iter()
+            .flat_map(|store| store.values.iter_mut())
+    }
+
+    /// Iterates over the entities with components of both types
+    pub fn query2<A: 'static, B: 'static>(&self) -> impl Iterator<Item = (&Arc<str>, &A, &B)> {
+        let second = self.store::<B>();
+        self.query::<A>().filter_map(move |(entity, a)| {
+            second
+                .and_then(|store| store.values.get(entity))
+                .map(|b| (entity, a, b))
+        })
+    }
+
+    /// Iterates over the entities with components of both types, mutably.
+    /// Nothing is returned if both types are the same.
+    pub fn query2_mut<A: 'static, B: 'static>(
+        &mut self,
+    ) -> impl Iterator<Item = (&Arc<str>, &mut A, &mut B)> {
+        // the same component can not be borrowed mutably twice
+        let [first, second] = if TypeId::of::<A>() == TypeId::of::<B>() {
+            [None, None]
+        } else {
+            self.stores
+                .get_disjoint_mut([&TypeId::of::<A>(), &TypeId::of::<B>()])
+        };
+        let first = first.and_then(|store| {
+            let store: &mut dyn Any = store.as_mut();
+            store.downcast_mut::<ComponentStore<A>>()
+        });
+        let mut second = second
+            .and_then(|store| {
+                let store: &mut dyn Any = store.as_mut();
+                store.downcast_mut::<ComponentStore<B>>()
+            })
+            .map(|store| {
+                store
+                    .values
+                    .iter_mut()
+                    .map(|(entity, b)| (entity.as_ref(), b))
+                    .collect::<HashMap<_, _>>()
+            })
+            .unwrap_or_default();
+
+        first
+            .into_iter()
+            .flat_map(|store| store.values.iter_mut())
+            .filter_map(move |(entity, a)| second.remove(entity.as_ref()).map(|b| (entity, a, b)))
+    }
+
+    /// Iterates over the objects with a component of the type, together with the component
+    pub fn query_objects<'a, T: 'static>(
+        &'a self,
+        objects: &'a ObjectStorage,
+    ) -> impl Iterator<Item = (&'a Object, &'a T)> {
+        self.query::<T>()
+            .filter_map(|(entity, component)| objects.get(entity).map(|i| (i, component)))
+    }
+
+    /// Iterates over the objects with a component of the type, together with the
+    /// component, both mutably
+    pub fn query_objects_mut<'a, T: 'static>(
+        &'a mut self,
+        objects: &'a mut ObjectStorage,
+    ) -> impl Iterator<Item = (&'a mut Object, &'a mut T)> {
+        let mut components = self
+            .query_mut::<T>()
+            .map(|(entity, component)| (entity.as_ref(), component))
+            .collect::<HashMap<_, _>>();
+        objects
+            .iter_mut()
+            .filter_map(move |(name, object)| components.remove(name.as_ref()).map(|i| (object, i)))
+    }
+}
+
+impl std::fmt::Debug for Components {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Components")
+            .field("types", &self.stores.len())
+            .finish()
+    }
+}
+
+/// Game logic that runs every frame, in the order it was added, right before the
+/// update loop. Systems can add and remove systems while running, where the added ones
+/// start on the next frame and the removed ones do not run anymore.
+#[derive(Default)]
+pub struct Systems {
+    systems: Vec<System>,
+    next_id: u64,
+}
+
+// only run with a window or in headless mode
+#[cfg_attr(not(any(feature = "window", feature = "headless")), allow(dead_code))]
+struct System {
+    id: u64,
+    name: String,
+    // taken out while the system runs
+    function: Option<crate::UpdateFunction>,
+}
+
+impl Systems {
+    /// Creates an empty system list
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a system to the end of the list, replacing any system of the same name
+    pub fn add(&mut self, name: impl AsRef<str>, system: impl 'static + FnMut(&mut Engine)) {
+        self.remove(name.as_ref());
+        self.next_id += 1;
+        self.systems.push(System {
+            id: self.next_id,
+            name: name.as_ref().to_string(),
+            function: Some(Box::new(system)),
+        });
+    }
+
+    /// Removes a system
+    pub fn remove(&mut self, name: impl AsRef<str>) {
+        self.systems.retain(|i| i.name != name.as_ref());
+    }
+
+    /// Checks if a system of the name exists
+    pub fn contains(&self, name: impl AsRef<str>) -> bool {
+        self.systems.iter().any(|i| i.name == name.as_ref())
+    }
+
+    /// Returns the amount of systems
+    pub fn len(&self) -> usize {
+        self.systems.len()
+    }
+
+    /// Returns true if there are no systems
+    pub fn is_empty(&self) -> bool {
+        self.systems.is_empty()
+    }
+
+    /// Removes all systems
+    pub fn clear(&mut self) {
+        self.systems.clear();
+    }
+
+    #[cfg(any(feature = "window", feature = "headless"))]
+    fn function(&mut self, id: u64) -> Option<&mut Option<crate::UpdateFunction>> {
+        self.systems
+            .iter_mut()
+            .find(|i| i.id == id)
+            .map(|i| &mut i.function)
+    }
+
+    /// Runs the systems of the engine once
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub(crate) fn update(engine: &mut Engine) {
+        let ids = engine
+            .systems
+            .systems
+            .iter()
+            .map(|i| i.id)
+            .collect::<Vec<_>>();
+        for id in ids {
+            // skips the systems removed by an earlier one
+            let Some(mut system) = engine.systems.function(id).and_then(Option::take) else {
+                continue;
+            };
+            system(engine);
+
+            // unless it was removed or replaced while running
+            if let Some(function) = engine.systems.function(id) {
+                *function = Some(system);
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for Systems {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.systems.iter().map(|i| &i.name))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Velocity(f32);
+    struct Health(u32);
+
+    #[test]
+    fn query2_mut_pairs_components() {
+        let mut components = Components::new();
+        components.insert("player", Velocity(2.0));
+        components.insert("player", Health(100));
+        components.insert("tree", Health(10));
+
+        for (entity, velocity, health) in components.query2_mut::<Velocity, Health>() {
+            assert_eq!(entity.as_ref(), "player");
+            velocity.0 *= 0.5;
+            health.0 -= 1;
+        }
+        assert_eq!(components.get::<Velocity>("player").map(|i| i.0), Some(1.0));
+        assert_eq!(components.get::<Health>("player").map(|i| i.0), Some(99));
+        assert_eq!(components.get::<Health>("tree").map(|i| i.0), Some(10));
+    }
+
+    #[test]
+    fn query2_mut_same_type_is_empty() {
+        let mut components = Components::new();
+        components.insert("player", Health(100));
+
+        assert_eq!(components.query2_mut::<Health, Health>().count(), 0);
+        assert_eq!(components.get::<Health>("player").map(|i| i.0), Some(100));
+    }
+
+    #[test]
+    #[cfg(any(feature = "window", feature = "headless"))]
+    fn clear_while_running() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        let mut engine = crate::utils::headless_test_engine();
+
+        let runs = Rc::new(Cell::new(0));
+        let counter = runs.clone();
+        engine.systems.add("first", move |engine| {
+            engine.systems.clear();
+            let counter = counter.clone();
+            engine
+                .systems
+                .add("added", move |_| counter.set(counter.get() + 10));
+        });
+        engine.systems.add("second", |_| {});
+
+        engine.step().unwrap();
+        assert!(!engine.systems.contains("first"));
+        assert!(!engine.systems.contains("second"));
+        assert!(engine.systems.contains("added"));
+        assert_eq!(engine.systems.len(), 1);
+
+        engine.step().unwrap();
+        assert_eq!(runs.get(), 10);
+    }
+
+    #[test]
+    #[cfg(any(feature = "window", feature = "headless"))]
+    fn add_and_remove_while_running() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let mut engine = crate::utils::headless_test_engine();
+
+        let ran = Rc::new(RefCell::new(Vec::new()));
+        let log = |name: &'static str| {
+            let ran = ran.clone();
+            move |_: &mut Engine| ran.borrow_mut().push(name)
+        };
+        engine.systems.add("first", {
+            let log = log("first");
+            move |engine| {
+                log(engine);
+                // added and removed again within the run
+                engine.systems.add("replaced", |_| {});
+                engine.systems.remove("replaced");
+                assert!(!engine.systems.contains("replaced"));
+                assert!(engine.systems.contains("first"));
+                engine.systems.remove("removed");
+            }
+        });
+        engine.systems.add("replaced", log("replaced"));
+        engine.systems.add("removed", log("removed"));
+
+        engine.step().unwrap();
+        assert_eq!(*ran.borrow(), ["first"]);
+        assert!(engine.systems.contains("first"));
+        assert!(!engine.systems.contains("replaced"));
+        assert!(!engine.systems.contains("removed"));
+
+        engine.step().unwrap();
+        assert_eq!(*ran.borrow(), ["first", "first"]);
+    }
+
+    #[test]
+    #[cfg(any(feature = "window", feature = "headless"))]
+    fn replace_while_running() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        let mut engine = crate::utils::headless_test_engine();
+
+        let runs = Rc::new(Cell::new(0));
+        let counter = runs.clone();
+        engine.systems.add("first", move |engine| {
+            let counter = counter.clone();
+            // replaces itself, and starts on the next frame
+            engine
+                .systems
+                .add("first", move |_| counter.set(counter.get() + 1));
+        });
+
+        engine.step().unwrap();
+        assert_eq!(runs.get(), 0);
+        assert_eq!(engine.systems.len(), 1);
+
+        engine.step().unwrap();
+        engine.step().unwrap();
+        assert_eq!(runs.get(), 2);
+    }
+}
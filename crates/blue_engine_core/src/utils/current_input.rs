@@ -1,6 +1,9 @@
 // taken from -- https://github.com/rukai/winit_input_helper
 
-use winit::event::{DeviceEvent, ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+use super::input_event::InputEvent;
+use super::touch::{TouchPhase, TouchPoint};
+use std::collections::HashMap;
+use winit::event::{ElementState, MouseButton, MouseScrollDelta};
 use winit::keyboard::{Key, PhysicalKey};
 
 #[derive(Debug, Clone)]
@@ -17,6 +20,7 @@ pub struct CurrentInput {
     pub y_scroll_diff: f32,
     pub x_scroll_diff: f32,
     pub text: Vec<Key>,
+    pub touches: HashMap<u64, TouchPoint>,
 }
 
 impl CurrentInput {
@@ -34,6 +38,7 @@ impl CurrentInput {
             y_scroll_diff: 0.0,
             x_scroll_diff: 0.0,
             text: vec![],
+            touches: HashMap::new(),
         }
     }
 
@@ -46,13 +51,22 @@ impl CurrentInput {
         self.y_scroll_diff = 0.0;
         self.x_scroll_diff = 0.0;
         self.text.clear();
+        // lifted fingers are kept for one step so they can still be queried
+        self.touches.retain(|_, touch| !touch.ended());
+        for touch in self.touches.values_mut() {
+            touch.previous_position = touch.position;
+            touch.started_this_step = false;
+        }
     }
 
-    pub fn handle_event(&mut self, event: &WindowEvent) {
+    pub fn handle_event(&mut self, event: &InputEvent, now: std::time::Duration) {
         match event {
-            WindowEvent::KeyboardInput { event, .. } => match event.state {
+            InputEvent::Keyboard {
+                physical_key,
+                logical_key,
+                state,
+            } => match state {
                 ElementState::Pressed => {
-                    let logical_key = &event.logical_key;
                     if !self.key_held.contains(logical_key) {
                         self.key_actions
                             .push(KeyAction::Pressed(logical_key.clone()));
@@ -63,7 +77,6 @@ impl CurrentInput {
                         .push(KeyAction::PressedOs(logical_key.clone()));
                     self.text.push(logical_key.clone());
 
-                    let physical_key = &event.physical_key;
                     if !self.scancode_held.contains(physical_key) {
                         self.scancode_actions
                             .push(ScanCodeAction::Pressed(*physical_key));
@@ -74,39 +87,35 @@ impl CurrentInput {
                         .push(ScanCodeAction::PressedOs(*physical_key));
                 }
                 ElementState::Released => {
-                    let logical_key = &event.logical_key;
                     self.key_held.retain(|x| x != logical_key);
                     self.key_actions
                         .push(KeyAction::Released(logical_key.clone()));
 
-                    let physical_key = &event.physical_key;
                     self.scancode_held.retain(|x| x != physical_key);
                     self.scancode_actions
                         .push(ScanCodeAction::Released(*physical_key));
                 }
             },
-            WindowEvent::CursorMoved { position, .. } => {
-                self.cursor_point = Some((position.x as f32, position.y as f32));
+            InputEvent::CursorMoved { x, y } => {
+                self.cursor_point = Some((*x as f32, *y as f32));
             }
-            WindowEvent::MouseInput {
+            InputEvent::MouseInput {
                 state: ElementState::Pressed,
                 button,
-                ..
             } => {
                 let button_usize = mouse_button_to_int(button);
                 self.mouse_held[button_usize] = true;
                 self.mouse_actions.push(MouseAction::Pressed(*button));
             }
-            WindowEvent::MouseInput {
+            InputEvent::MouseInput {
                 state: ElementState::Released,
                 button,
-                ..
             } => {
                 let button_usize = mouse_button_to_int(button);
                 self.mouse_held[button_usize] = false;
                 self.mouse_actions.push(MouseAction::Released(*button));
             }
-            WindowEvent::MouseWheel { delta, .. } => {
+            InputEvent::MouseWheel { delta } => {
                 // I just took this from three-rs, no idea why this magic number was chosen ¯\_(ツ)_/¯
                 const PIXELS_PER_LINE: f64 = 38.0;
 
@@ -121,16 +130,47 @@ impl CurrentInput {
                     }
                 }
             }
-            _ => {}
-        }
-    }
+            InputEvent::Touch {
+                id,
+                phase,
+                x,
+                y,
+                force,
+            } => {
+                let position = (*x as f32, *y as f32);
+                let force = force.map(|force| force as f32);
 
-    pub fn handle_device_event(&mut self, event: &DeviceEvent) {
-        if let DeviceEvent::MouseMotion { delta, .. } = event {
-            match self.mouse_diff {
-                Some((x, y)) => self.mouse_diff = Some((x + delta.0 as f32, y + delta.1 as f32)),
-                None => self.mouse_diff = Some((delta.0 as f32, delta.1 as f32)),
+                match self.touches.get_mut(id) {
+                    Some(point) if *phase != TouchPhase::Started => {
+                        point.position = position;
+                        point.phase = *phase;
+                        point.force = force;
+                    }
+                    _ => {
+                        self.touches.insert(
+                            *id,
+                            TouchPoint {
+                                id: *id,
+                                position,
+                                previous_position: position,
+                                start_position: position,
+                                phase: *phase,
+                                force,
+                                started_at: now,
+                                started_this_step: true,
+                            },
+                        );
+                    }
+                }
             }
+            InputEvent::MouseMotion {
+                x: delta_x,
+                y: delta_y,
+            } => match self.mouse_diff {
+                Some((x, y)) => self.mouse_diff = Some((x + *delta_x as f32, y + *delta_y as f32)),
+                None => self.mouse_diff = Some((*delta_x as f32, *delta_y as f32)),
+            },
+            _ => {}
         }
     }
 }
@@ -0,0 +1,248 @@
+/*
+ * Blue Engine by Elham Aryanpur
+ *
+ * The license is same as the one on the root.
+*/
+
+use crate::{Engine, Renderer, error::Error, render::GpuContext};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+impl Renderer {
+    /// Returns true once the device the engine requested is lost, until a new one replaces
+    /// it. The device of an embedding application is not watched.
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost
+            .as_ref()
+            .is_some_and(|lost| lost.load(Ordering::Acquire))
+    }
+
+    /// Listens for the loss of the device. The errors of the device are logged instead of
+    /// panicking, and the ones of a lost device only in debug logs, as its resources are built
+    /// again on a new device
+    pub(crate) fn watch_device(&mut self) {
+        let device_lost = Arc::new(AtomicBool::new(false));
+        self.device_lost = Some(device_lost.clone());
+
+        let lost = device_lost.clone();
+        self.device
+            .set_device_lost_callback(move |reason, message| {
+                log::error!("The device was lost ({reason:?}): {message}");
+                lost.store(true, Ordering::Release);
+            });
+        self.device.on_uncaptured_error(Arc::new(move |error| {
+            if device_lost.load(Ordering::Acquire) {
+                log::debug!("Error on the lost device: {error}");
+            } else {
+                log::error!("wgpu error: {error}");
+            }
+        }));
+    }
+
+    /// Requests a new device from the adapter with the settings the engine was created with,
+    /// or from a newly selected adapter if the old one is gone too
+    pub(crate) async fn request_context(&self) -> Result<GpuContext, Error> {
+        let descriptor = Self::device_descriptor(&self.settings);
+        let (adapter, (device, queue)) = match self.adapter.request_device(&descriptor).await {
+            Ok(device) => (self.adapter.clone(), device),
+            Err(_) => {
+                let adapter =
+                    crate::utils::adapter_selection::select_adapter(&self.instance, &self.settings)
+                        .await?;
+                let device = adapter.request_device(&descriptor).await?;
+                (adapter, device)
+            }
+        };
+
+        Ok(GpuContext {
+            instance: self.instance.clone(),
+            adapter,
+            device,
+            queue,
+        })
+    }
+
+    /// Moves the renderer to the device, building its layouts, frame targets and default data
+    /// again and configuring the surface with it
+    pub(crate) fn replace_context(&mut self, context: GpuContext) {
+        let GpuContext {
+            instance,
+            adapter,
+            device,
+            queue,
+        } = context;
+        self.instance = instance;
+        self.adapter = adapter;
+        self.device = device;
+        self.queue = queue;
+        if self.device_lost.is_some() {
+            self.watch_device();
+        }
+
+        (
+            self.texture_bind_group_layout,
+            self.default_uniform_bind_group_layout,
+        ) = Self::build_bind_group_layouts(&self.device);
+        // the new adapter may not support the same MSAA
+        if let Err(error) = self.check_msaa_samples(self.sample_count) {
+            log::warn!("Disabling MSAA on the new device: {error}");
+            self.sample_count = 1;
+        }
+        #[cfg(not(target_os = "android"))]
+        if let Some(surface) = self.surface.as_ref() {
+            surface.configure(&self.device, &self.config);
+        }
+        // the upscale pass belongs to the old device
+        self.upscale_pipeline = None;
+        self.rebuild_frame_targets();
+        self.build_default_data();
+    }
+}
+
+impl Engine {
+    /// Recreates the lost device, and builds all GPU resources of the engine again from the
+    /// data kept on the CPU: the buffers, textures and shaders of the objects, including the
+    /// ones of paused game states, the cameras, the frame targets and the surfaces. The
+    /// `device_recreated` hook of the signals runs afterwards, to rebuild their own resources.
+    ///
+    /// The engine calls this at the start of a frame once its device is lost.
+    pub fn recover_device(&mut self) -> Result<(), Error> {
+        let context = pollster::block_on(self.renderer.request_context())?;
+        self.replace_device(context);
+        Ok(())
+    }
+
+    /// Moves the engine to another device, such as the one an embedding application created
+    /// after its device was lost, and builds all GPU resources again like
+    /// [`Engine::recover_device`]. If the instance changed, attach the surface again with
+    /// [`Engine::attach_surface`].
+    pub fn replace_device(&mut self, context: GpuContext) {
+        self.renderer.replace_context(context);
+
+        // textures of objects that failed to load again fall back to the default one
+        let mut failed = Vec::new();
+        for (name, object) in self.objects.iter_mut() {
+            if object.rebuild_gpu_resources(&mut self.renderer).is_err() {
+                failed.push(name.clone());
+            }
+        }
+        for objects in self.states.paused_objects_mut() {
+            for (name, object) in objects.iter_mut() {
+                if object.rebuild_gpu_resources(&mut self.renderer).is_err() {
+                    failed.push(name.clone());
+                }
+            }
+        }
+        for name in failed {
+            log::warn!("Could not load the texture of \"{name}\" again, using the default one");
+        }
+
+        let cameras = self.camera.values_mut().chain(
+            self.states
+                .paused_cameras_mut()
+                .flat_map(|i| i.values_mut()),
+        );
+        for camera in cameras {
+            camera.changed = true;
+            camera.update_view_projection(&mut self.renderer);
+        }
+        #[cfg(all(feature = "window", not(feature = "headless")))]
+        for window in self.extra_windows.values_mut() {
+            window.rebuild_targets(&self.renderer);
+        }
+        self.renderer.pipelines_outdated = false;
+
+        self.run_signals(|signal, engine| signal.device_recreated(engine));
+    }
+
+    /// Checks at the start of a frame whether the device is lost, and recovers it
+    pub(crate) fn check_device(&mut self) -> Result<(), Error> {
+        if self.renderer.device_lost.is_none() {
+            return Ok(());
+        }
+        // the loss is reported while the device is polled
+        let _ = self.renderer.device.poll(wgpu::PollType::Poll);
+        if self.renderer.is_device_lost() {
+            log::warn!("Recreating the lost device");
+            self.recover_device()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Renders one frame and returns it
+    fn render(engine: &mut Engine) -> Vec<u8> {
+        engine.step().unwrap();
+        engine.renderer.headless_texture_data.clone()
+    }
+
+    #[test]
+    fn replace_device() {
+        let mut engine = crate::utils::headless_test_engine();
+        crate::primitive_shapes::triangle(
+            "triangle",
+            crate::ObjectSettings::default(),
+            &mut engine.renderer,
+            &mut engine.objects,
+        )
+        .unwrap();
+        let before = render(&mut engine);
+        assert!(before.chunks_exact(4).any(|pixel| pixel != &before[..4]));
+
+        let adapter = pollster::block_on(engine.renderer.instance.request_adapter(
+            &wgpu::RequestAdapterOptions {
+                force_fallback_adapter: true,
+                ..Default::default()
+            },
+        ))
+        .expect("a software adapter");
+        let (device, queue) = pollster::block_on(
+            adapter.request_device(&Renderer::device_descriptor(&engine.renderer.settings)),
+        )
+        .unwrap();
+        let old_device = engine.renderer.device.clone();
+        engine.replace_device(GpuContext {
+            instance: engine.renderer.instance.clone(),
+            adapter,
+            device,
+            queue,
+        });
+        assert!(engine.renderer.device != old_device);
+        assert!(!engine.renderer.is_device_lost());
+
+        // the objects and cameras render the same on the new device
+        assert_eq!(render(&mut engine), before);
+
+        engine
+            .camera
+            .get_mut("main")
+            .unwrap()
+            .set_position([0.0, 0.0, 100.0]);
+        // the camera is updated after the frame is encoded, and used from the next one
+        render(&mut engine);
+        assert_ne!(render(&mut engine), before);
+    }
+
+    #[test]
+    fn failed_readback_returns_error() {
+        let mut engine = crate::utils::headless_test_engine();
+        engine.step().unwrap();
+
+        let layers = [(&engine.objects, &engine.camera)];
+        let (encoder, _view, frame, headless) = engine
+            .renderer
+            .pre_render(&layers, (32, 32))
+            .unwrap()
+            .expect("a headless frame");
+        engine.renderer.device.destroy();
+        assert!(engine.renderer.render(encoder, frame, headless).is_err());
+
+        // the next frame recovers on a new device
+        engine.step().unwrap();
+        assert!(!engine.renderer.is_device_lost());
+    }
+}
@@ -0,0 +1,198 @@
+/*
+ * Blue Engine by Elham Aryanpur
+ *
+ * The license is same as the one on the root.
+*/
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+/// The events of one type, of the last frame and of the current one
+struct EventQueue<T> {
+    previous: Vec<T>,
+    current: Vec<T>,
+}
+
+trait AnyQueue: Any {
+    fn next_frame(&mut self);
+    fn clear(&mut self);
+}
+
+impl<T: 'static> AnyQueue for EventQueue<T> {
+    fn next_frame(&mut self) {
+        self.previous = std::mem::take(&mut self.current);
+    }
+
+    fn clear(&mut self) {
+        self.previous.clear();
+        self.current.clear();
+    }
+}
+
+/// Typed events sent between signals and the update loop, so they can communicate
+/// without knowing each other.
+///
+/// Events sent during a frame can be read by everyone during the next frame, after which
+/// they are dropped. This way the order the signals run in does not matter.
+/// ```
+/// # use blue_engine_core::EventBus;
+/// struct Collision(u32, u32);
+///
+/// let mut events = EventBus::new();
+/// events.send(Collision(1, 2));
+/// // the engine moves to the next frame
+/// # events.next_frame();
+/// for collision in events.read::<Collision>() {
+///     println!("{} hit {}", collision.0, collision.1);
+/// }
+/// ```
+#[derive(Default)]
+pub struct EventBus {
+    queues: HashMap<TypeId, Box<dyn AnyQueue>>,
+}
+
+impl EventBus {
+    /// Creates an empty event bus
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn queue<T: 'static>(&self) -> Option<&EventQueue<T>> {
+        let queue: &dyn Any = self.queues.get(&TypeId::of::<T>())?.as_ref();
+        queue.downcast_ref::<EventQueue<T>>()
+    }
+
+    fn queue_mut<T: 'static>(&mut self) -> &mut EventQueue<T> {
+        let queue: &mut dyn Any = self
+            .queues
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| {
+                Box::new(EventQueue::<T> {
+                    previous: Vec::new(),
+                    current: Vec::new(),
+                })
+            })
+            .as_mut();
+        #[allow(clippy::expect_used)]
+        queue
+            .downcast_mut::<EventQueue<T>>()
+            .expect("The queue is stored under the id of its own event type")
+    }
+
+    /// Sends an event, to be read during the next frame
+    pub fn send<T: 'static>(&mut self, event: T) {
+        self.queue_mut::<T>().current.push(event);
+    }
+
+    /// Reads the events of the type that were sent during the last frame
+    pub fn read<T: 'static>(&self) -> impl Iterator<Item = &T> {
+        self.queue::<T>()
+            .map(|queue| queue.previous.as_slice())
+            .unwrap_or_default()
+            .iter()
+    }
+
+    /// Reads the events of the type that were sent so far during the current frame
+    pub fn read_current<T: 'static>(&self) -> impl Iterator<Item = &T> {
+        self.queue::<T>()
+            .map(|queue| queue.current.as_slice())
+            .unwrap_or_default()
+            .iter()
+    }
+
+    /// Removes the events of the type
+    pub fn clear<T: 'static>(&mut self) {
+        if let Some(queue) = self.queues.get_mut(&TypeId::of::<T>()) {
+            queue.clear();
+        }
+    }
+
+    /// Removes the events of all types
+    pub fn clear_all(&mut self) {
+        self.queues.values_mut().for_each(|queue| queue.clear());
+    }
+
+    /// Makes the events sent during the current frame readable, and drops the ones of the
+    /// last frame. The engine does this at the start of every frame.
+    pub fn next_frame(&mut self) {
+        self.queues
+            .values_mut()
+            .for_each(|queue| queue.next_frame());
+    }
+}
+
+impl std::fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventBus")
+            .field("types", &self.queues.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Ping(u32);
+
+    #[test]
+    fn events_last_one_frame() {
+        let mut events = EventBus::new();
+        events.send(Ping(1));
+        events.send(42u8);
+        assert_eq!(events.read::<Ping>().count(), 0);
+        assert_eq!(
+            events.read_current::<Ping>().collect::<Vec<_>>(),
+            [&Ping(1)]
+        );
+
+        events.next_frame();
+        assert_eq!(events.read::<Ping>().collect::<Vec<_>>(), [&Ping(1)]);
+        assert_eq!(events.read::<u8>().collect::<Vec<_>>(), [&42]);
+        assert_eq!(events.read_current::<Ping>().count(), 0);
+
+        events.send(Ping(2));
+        events.clear::<Ping>();
+        assert_eq!(events.read::<Ping>().count(), 0);
+        assert_eq!(events.read::<u8>().count(), 1);
+
+        events.next_frame();
+        assert_eq!(events.read::<u8>().count(), 0);
+    }
+
+    #[cfg(any(feature = "window", feature = "headless"))]
+    #[test]
+    fn events_are_read_on_the_next_frame() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        struct Sender;
+        impl crate::Signal for Sender {
+            fn pre_update(&mut self, engine: &mut crate::Engine) {
+                if engine.time.frame_count() == 1 {
+                    engine.event_bus.send(Ping(1));
+                }
+            }
+        }
+
+        let mut engine = crate::utils::headless_test_engine();
+        engine.signals.add_signal("sender", Box::new(Sender));
+        let reads = Rc::new(RefCell::new(Vec::new()));
+        let update_reads = reads.clone();
+        engine.update_loop = Some(Box::new(move |engine| {
+            update_reads.borrow_mut().push(
+                engine
+                    .event_bus
+                    .read::<Ping>()
+                    .map(|i| i.0)
+                    .collect::<Vec<_>>(),
+            );
+        }));
+
+        for _ in 0..3 {
+            engine.step().unwrap();
+        }
+        assert_eq!(*reads.borrow(), [vec![], vec![1], vec![]]);
+    }
+}
@@ -0,0 +1,197 @@
+/*
+ * Blue Engine by Elham Aryanpur
+ *
+ * The license is same as the one on the root.
+*/
+
+use std::time::Duration;
+
+/// Decides how many fixed updates should run each frame, for simulations that
+/// must advance by the same amount of time regardless of the frame rate.
+///
+/// Time is accumulated every frame and consumed in steps of `timestep`. What is left over
+/// is exposed as [`FixedTimestep::alpha`], which can be used to blend between the previous
+/// and the current simulation state when rendering:
+/// ```
+/// # use blue_engine_core::Vector3;
+/// # let (previous, current, alpha) = (Vector3::ZERO, Vector3::ONE, 0.5);
+/// let rendered_position = previous.lerp(current, alpha);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedTimestep {
+    /// The duration of a single fixed update
+    pub timestep: Duration,
+    /// The most fixed updates that can run in a single frame. When the frame took longer
+    /// than this many steps, the rest of the time is dropped so a slow frame does not cause
+    /// an ever growing amount of updates.
+    pub max_steps: u32,
+    accumulator: Duration,
+    alpha: f32,
+}
+
+impl FixedTimestep {
+    /// Creates a fixed timestep running at the given updates per second
+    pub fn new(hz: f64) -> Self {
+        Self {
+            timestep: Self::hz_to_timestep(hz),
+            max_steps: 8,
+            accumulator: Duration::ZERO,
+            alpha: 0.0,
+        }
+    }
+
+    fn hz_to_timestep(hz: f64) -> Duration {
+        Duration::from_secs_f64(1.0 / hz.max(f64::EPSILON))
+    }
+
+    /// Sets the most fixed updates that can run in a single frame
+    pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Changes the amount of fixed updates per second
+    pub fn set_hz(&mut self, hz: f64) {
+        self.timestep = Self::hz_to_timestep(hz);
+    }
+
+    /// Returns the amount of fixed updates per second
+    pub fn hz(&self) -> f64 {
+        1.0 / self.timestep.as_secs_f64()
+    }
+
+    /// Returns the duration of a single fixed update in seconds
+    pub fn delta_seconds(&self) -> f32 {
+        self.timestep.as_secs_f32()
+    }
+
+    /// Adds the time that passed during the last frame and returns how many fixed
+    /// updates should run
+    pub fn advance(&mut self, delta: Duration) -> u32 {
+        if self.timestep.is_zero() {
+            return 0;
+        }
+
+        self.accumulator = self.accumulator.saturating_add(delta);
+        let mut steps = 0;
+        while self.accumulator >= self.timestep && steps < self.max_steps {
+            self.accumulator -= self.timestep;
+            steps += 1;
+        }
+
+        // drop the time that could not be caught up on
+        if self.accumulator >= self.timestep {
+            // in whole nanoseconds, as float remainders can leave almost a full step
+            self.accumulator = Duration::from_nanos(
+                (self.accumulator.as_nanos() % self.timestep.as_nanos()) as u64,
+            );
+        }
+
+        self.alpha = (self.accumulator.as_secs_f64() / self.timestep.as_secs_f64()) as f32;
+        steps
+    }
+
+    /// Returns how far between the last and the next fixed update the current frame is,
+    /// between 0 and 1
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    /// Clears the accumulated time
+    pub fn reset(&mut self) {
+        self.accumulator = Duration::ZERO;
+        self.alpha = 0.0;
+    }
+}
+
+impl Default for FixedTimestep {
+    /// 60 updates per second
+    fn default() -> Self {
+        Self::new(60.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn steps_and_alpha() {
+        let mut timestep = FixedTimestep::new(10.0);
+        assert_eq!(timestep.timestep, millis(100));
+
+        assert_eq!(timestep.advance(millis(50)), 0);
+        assert!((timestep.alpha() - 0.5).abs() < 1e-6);
+        assert_eq!(timestep.advance(millis(200)), 2);
+        assert!((timestep.alpha() - 0.5).abs() < 1e-6);
+        assert_eq!(timestep.advance(millis(75)), 1);
+        assert!((timestep.alpha() - 0.25).abs() < 1e-6);
+
+        timestep.reset();
+        assert_eq!(timestep.alpha(), 0.0);
+        assert_eq!(timestep.advance(millis(99)), 0);
+    }
+
+    #[test]
+    fn max_steps_drops_time() {
+        let mut timestep = FixedTimestep::new(10.0).with_max_steps(3);
+
+        assert_eq!(timestep.advance(Duration::from_secs(1)), 3);
+        assert_eq!(timestep.alpha(), 0.0);
+        // only the part of a step is kept from the dropped time
+        assert_eq!(timestep.advance(millis(1050)), 3);
+        assert!((timestep.alpha() - 0.5).abs() < 1e-6);
+        assert_eq!(timestep.advance(millis(50)), 1);
+    }
+
+    #[cfg(any(feature = "window", feature = "headless"))]
+    #[test]
+    fn fixed_updates_run_before_the_update_loop() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        struct Recorder(Rc<RefCell<Vec<&'static str>>>);
+        impl crate::Signal for Recorder {
+            fn fixed_update(&mut self, _engine: &mut crate::Engine) {
+                self.0.borrow_mut().push("signal");
+            }
+            fn pre_update(&mut self, _engine: &mut crate::Engine) {
+                self.0.borrow_mut().push("pre_update");
+            }
+        }
+
+        let mut engine = crate::utils::headless_test_engine();
+        engine.time = crate::utils::time::Time::manual();
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        engine
+            .signals
+            .add_signal("recorder", Box::new(Recorder(calls.clone())));
+        let fixed_calls = calls.clone();
+        engine.fixed_update(10.0, move |_| fixed_calls.borrow_mut().push("closure"));
+        let update_calls = calls.clone();
+        engine.update_loop = Some(Box::new(move |_| update_calls.borrow_mut().push("update")));
+
+        engine.step().unwrap();
+        assert_eq!(*calls.borrow(), ["pre_update", "update"]);
+
+        calls.borrow_mut().clear();
+        engine.time.advance(millis(250));
+        engine.step().unwrap();
+        assert_eq!(
+            *calls.borrow(),
+            [
+                "signal",
+                "closure",
+                "signal",
+                "closure",
+                "pre_update",
+                "update"
+            ]
+        );
+        assert!((engine.interpolation_alpha() - 0.5).abs() < 1e-6);
+    }
+}
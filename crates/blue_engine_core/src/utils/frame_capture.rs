@@ -0,0 +1,563 @@
+/*
+ * Blue Engine by Elham Aryanpur
+ *
+ * The license is same as the one on the root.
+*/
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+/// A frame copied from the GPU, as tightly packed RGBA8 pixels
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedFrame {
+    /// The width in pixels
+    pub width: u32,
+    /// The height in pixels
+    pub height: u32,
+    /// The pixels, row by row from the top, 4 bytes per pixel
+    pub data: Vec<u8>,
+}
+
+impl CapturedFrame {
+    /// Creates a frame from data copied out of a texture, removing the row padding
+    /// and converting BGRA formats to RGBA
+    pub fn from_padded(
+        data: &[u8],
+        width: u32,
+        height: u32,
+        padded_bytes_per_row: u32,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        let bytes_per_row = width as usize * 4;
+        let mut pixels = Vec::with_capacity(bytes_per_row * height as usize);
+        for row in data
+            .chunks(padded_bytes_per_row as usize)
+            .take(height as usize)
+        {
+            pixels.extend_from_slice(&row[..bytes_per_row]);
+        }
+
+        if matches!(
+            format,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+        ) {
+            pixels
+                .chunks_exact_mut(4)
+                .for_each(|pixel| pixel.swap(0, 2));
+        }
+
+        Self {
+            width,
+            height,
+            data: pixels,
+        }
+    }
+
+    /// Converts the frame to an image
+    pub fn to_image(&self) -> Option<image::RgbaImage> {
+        image::RgbaImage::from_raw(self.width, self.height, self.data.clone())
+    }
+
+    /// Saves the frame as a PNG file
+    pub fn save_png(&self, path: impl AsRef<std::path::Path>) -> Result<(), crate::error::Error> {
+        image::save_buffer_with_format(
+            path,
+            &self.data,
+            self.width,
+            self.height,
+            image::ExtendedColorType::Rgba8,
+            image::ImageFormat::Png,
+        )
+        .map_err(|error| crate::error::Error::Custom(format!("Failed to save the image: {error}")))
+    }
+}
+
+/// Returns the bytes per row of a texture copy, padded to the alignment wgpu requires
+pub fn padded_bytes_per_row(width: u32, bytes_per_pixel: u32) -> u32 {
+    let unpadded = width * bytes_per_pixel;
+    let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    unpadded.div_ceil(alignment) * alignment
+}
+
+/// The copy of a texture on its way to the CPU
+#[derive(Debug)]
+struct CaptureCopy {
+    buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: u32,
+    format: wgpu::TextureFormat,
+}
+
+#[derive(Debug, Default)]
+struct CaptureState {
+    copy: Option<CaptureCopy>,
+    mapped: Option<Result<(), wgpu::BufferAsyncError>>,
+    error: Option<String>,
+}
+
+/// A handle to a frame that is being copied from the GPU.
+///
+/// The copy does not block the rendering. Check it with [`FrameCapture::try_take`] every
+/// frame, or await it within a coroutine:
+/// ```
+/// # use blue_engine_core::Engine;
+/// # #[cfg(any(feature = "window", feature = "headless"))]
+/// # fn capture(engine: &mut Engine) {
+/// let capture = engine.renderer.capture_frame();
+/// engine.spawn(async move {
+///     if let Ok(frame) = capture.await {
+///         let _ = frame.save_png("screenshot.png");
+///     }
+/// });
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct FrameCapture {
+    device: wgpu::Device,
+    state: Arc<Mutex<CaptureState>>,
+}
+
+impl FrameCapture {
+    pub(crate) fn new(device: wgpu::Device) -> Self {
+        Self {
+            device,
+            state: Arc::new(Mutex::new(CaptureState::default())),
+        }
+    }
+
+    /// Returns a second handle to the same capture
+    pub(crate) fn share(&self) -> Self {
+        Self {
+            device: self.device.clone(),
+            state: self.state.clone(),
+        }
+    }
+
+    /// Records the copy of the texture into the encoder. The copy is read once
+    /// [`FrameCapture::map`] is called after the encoder is submitted.
+    pub(crate) fn encode(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+    ) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+
+        if !texture.usage().contains(wgpu::TextureUsages::COPY_SRC) {
+            state.error = Some("The texture does not support being copied from".to_string());
+            return;
+        }
+        // only the formats `CapturedFrame` can turn into RGBA8 pixels
+        if !matches!(
+            texture.format(),
+            wgpu::TextureFormat::Rgba8Unorm
+                | wgpu::TextureFormat::Rgba8UnormSrgb
+                | wgpu::TextureFormat::Bgra8Unorm
+                | wgpu::TextureFormat::Bgra8UnormSrgb
+        ) {
+            state.error = Some(format!(
+                "Capturing textures of format {:?} is not supported",
+                texture.format()
+            ));
+            return;
+        }
+
+        let (width, height) = (texture.width(), texture.height());
+        let padded_bytes_per_row = padded_bytes_per_row(width, 4);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Frame Capture Buffer"),
+            size: (padded_bytes_per_row * height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        state.copy = Some(CaptureCopy {
+            buffer,
+            width,
+            height,
+            padded_bytes_per_row,
+            format: texture.format(),
+        });
+    }
+
+    /// Starts reading the copy back to the CPU, after the encoder was submitted
+    pub(crate) fn map(&self) {
+        let buffer = match self.state.lock() {
+            Ok(state) => state.copy.as_ref().map(|copy| copy.buffer.clone()),
+            Err(_) => None,
+        };
+        // the lock is released first, as the callback can run right away on errors
+        if let Some(buffer) = buffer {
+            let shared = self.state.clone();
+            buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |result| {
+                    if let Ok(mut state) = shared.lock() {
+                        state.mapped = Some(result);
+                    }
+                });
+        }
+    }
+
+    /// Returns the frame once the copy is done, without waiting.
+    /// Returns `None` while the copy is still in progress, and after the frame was taken.
+    pub fn try_take(&mut self) -> Option<Result<CapturedFrame, crate::error::Error>> {
+        let _ = self.device.poll(wgpu::PollType::Poll);
+
+        let mut state = self.state.lock().ok()?;
+        if let Some(error) = state.error.take() {
+            return Some(Err(crate::error::Error::Custom(error)));
+        }
+
+        match state.mapped.take()? {
+            Ok(()) => {
+                let copy = state.copy.take()?;
+                let frame = CapturedFrame::from_padded(
+                    &copy.buffer.slice(..).get_mapped_range(),
+                    copy.width,
+                    copy.height,
+                    copy.padded_bytes_per_row,
+                    copy.format,
+                );
+                copy.buffer.unmap();
+                Some(Ok(frame))
+            }
+            Err(error) => Some(Err(crate::error::Error::Custom(error.to_string()))),
+        }
+    }
+}
+
+impl Future for FrameCapture {
+    type Output = Result<CapturedFrame, crate::error::Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.try_take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                // the GPU does not wake us up, so ask to be polled again
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Captures every rendered frame to a numbered sequence of PNG files,
+/// such as `frame_000000.png`, `frame_000001.png` and so on.
+///
+/// The files are written by a worker thread, which finishes the frames that are left
+/// once the sequence is dropped.
+#[derive(Debug)]
+pub struct CaptureSequence {
+    /// The directory the images are saved to
+    pub directory: PathBuf,
+    /// The start of the file names
+    pub prefix: String,
+    next_index: u64,
+    in_flight: VecDeque<(u64, FrameCapture)>,
+    sender: Option<Sender<(PathBuf, CapturedFrame)>>,
+    saver: Option<std::thread::JoinHandle<()>>,
+}
+
+impl CaptureSequence {
+    /// Creates a sequence, creating the directory if it does not exist
+    pub fn new(
+        directory: impl Into<PathBuf>,
+        prefix: impl AsRef<str>,
+    ) -> Result<Self, crate::error::Error> {
+        let directory = directory.into();
+        std::fs::create_dir_all(&directory)?;
+
+        let (sender, receiver) = std::sync::mpsc::channel::<(PathBuf, CapturedFrame)>();
+        let saver = std::thread::Builder::new()
+            .name("Frame Capture Saver".to_string())
+            .spawn(move || {
+                for (path, frame) in receiver {
+                    if let Err(error) = frame.save_png(&path) {
+                        log::error!("Could not save the frame {}: {error}", path.display());
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            directory,
+            prefix: prefix.as_ref().to_string(),
+            next_index: 0,
+            in_flight: VecDeque::new(),
+            sender: Some(sender),
+            saver: Some(saver),
+        })
+    }
+
+    /// Returns the amount of frames captured so far
+    pub fn frame_count(&self) -> u64 {
+        self.next_index
+    }
+
+    /// Adds the capture of the current frame to the sequence
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub(crate) fn push(&mut self, capture: FrameCapture) {
+        self.in_flight.push_back((self.next_index, capture));
+        self.next_index += 1;
+    }
+
+    /// Sends the frames whose copy is done to the worker thread to be saved
+    pub(crate) fn save_ready(&mut self) {
+        while let Some((index, capture)) = self.in_flight.front_mut() {
+            let Some(result) = capture.try_take() else {
+                break;
+            };
+            let path = self
+                .directory
+                .join(format!("{}{:06}.png", self.prefix, index));
+            self.in_flight.pop_front();
+
+            match result {
+                Ok(frame) => {
+                    let sent = match self.sender.as_ref() {
+                        Some(sender) => sender.send((path, frame)).is_ok(),
+                        None => false,
+                    };
+                    if !sent {
+                        log::error!("Could not save the frame, the saving thread has stopped");
+                    }
+                }
+                Err(error) => log::error!("Could not capture the frame: {error}"),
+            }
+        }
+    }
+
+    /// Waits for the copies in flight, saves them and waits until all frames are written
+    pub(crate) fn finish(&mut self) {
+        if let Some((_, capture)) = self.in_flight.back() {
+            let _ = capture.device.poll(wgpu::PollType::wait_indefinitely());
+        }
+        self.save_ready();
+        if !self.in_flight.is_empty() {
+            log::error!(
+                "Could not save {} frames, their copies were not submitted",
+                self.in_flight.len()
+            );
+            self.in_flight.clear();
+        }
+        // the worker stops once the channel is closed
+        self.sender = None;
+        if let Some(Err(_)) = self.saver.take().map(|saver| saver.join()) {
+            log::error!("The thread saving the frames has panicked");
+        }
+    }
+}
+
+impl Drop for CaptureSequence {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn padded_rows() {
+        assert_eq!(padded_bytes_per_row(64, 4), 256);
+        assert_eq!(padded_bytes_per_row(65, 4), 512);
+        assert_eq!(padded_bytes_per_row(1000, 4), 4096);
+    }
+
+    #[test]
+    fn from_padded_removes_padding_and_swaps_bgra() {
+        // 3 pixels wide, so each row of 12 bytes is padded to 256
+        let (width, height) = (3, 2);
+        let padded = padded_bytes_per_row(width, 4);
+        let mut data = vec![0xAA; (padded * height) as usize];
+        for y in 0..height {
+            for x in 0..width {
+                let start = (y * padded + x * 4) as usize;
+                data[start..start + 4].copy_from_slice(&[x as u8, y as u8, 100, 255]);
+            }
+        }
+
+        let frame = CapturedFrame::from_padded(
+            &data,
+            width,
+            height,
+            padded,
+            wgpu::TextureFormat::Bgra8UnormSrgb,
+        );
+        assert_eq!(frame.data.len(), (width * height * 4) as usize);
+        assert_eq!(&frame.data[..4], &[100, 0, 0, 255]);
+        assert_eq!(&frame.data[12..16], &[100, 1, 0, 255]);
+        assert_eq!(&frame.data[20..24], &[100, 1, 2, 255]);
+
+        let frame = CapturedFrame::from_padded(
+            &data,
+            width,
+            height,
+            padded,
+            wgpu::TextureFormat::Rgba8Unorm,
+        );
+        assert_eq!(&frame.data[20..24], &[2, 1, 100, 255]);
+        assert!(!frame.data.contains(&0xAA));
+    }
+
+    #[cfg(any(feature = "window", feature = "headless"))]
+    fn test_texture(
+        engine: &crate::Engine,
+        format: wgpu::TextureFormat,
+        usage: wgpu::TextureUsages,
+    ) -> wgpu::Texture {
+        engine
+            .renderer
+            .device
+            .create_texture(&wgpu::TextureDescriptor {
+                label: None,
+                size: wgpu::Extent3d {
+                    width: 4,
+                    height: 4,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage,
+                view_formats: &[],
+            })
+    }
+
+    #[test]
+    #[cfg(any(feature = "window", feature = "headless"))]
+    fn capture_frame_matches_headless_output() {
+        let mut engine = crate::utils::headless_test_engine();
+        crate::primitive_shapes::triangle(
+            "triangle",
+            crate::ObjectSettings::default(),
+            &mut engine.renderer,
+            &mut engine.objects,
+        )
+        .unwrap();
+
+        let mut capture = engine.renderer.capture_frame();
+        assert!(capture.try_take().is_none());
+        engine.step().unwrap();
+
+        let frame = capture
+            .try_take()
+            .expect("the copy is done after the frame")
+            .unwrap();
+        assert_eq!((frame.width, frame.height), (32, 32));
+        assert_eq!(frame.data, engine.renderer.headless_texture_data);
+        // the frame is only returned once
+        assert!(capture.try_take().is_none());
+    }
+
+    #[test]
+    #[cfg(any(feature = "window", feature = "headless"))]
+    fn capture_texture_errors() {
+        let engine = crate::utils::headless_test_engine();
+
+        let texture = test_texture(
+            &engine,
+            wgpu::TextureFormat::Rgba8Unorm,
+            wgpu::TextureUsages::TEXTURE_BINDING,
+        );
+        let mut capture = engine.renderer.capture_texture(&texture);
+        assert!(matches!(capture.try_take(), Some(Err(_))));
+
+        let texture = test_texture(
+            &engine,
+            wgpu::TextureFormat::Rgba16Float,
+            wgpu::TextureUsages::COPY_SRC,
+        );
+        let mut capture = engine.renderer.capture_texture(&texture);
+        assert!(matches!(capture.try_take(), Some(Err(_))));
+    }
+
+    #[test]
+    #[cfg(any(feature = "window", feature = "headless"))]
+    fn capture_sequence_names_files() {
+        let directory =
+            std::env::temp_dir().join(format!("frame_capture_sequence_{}", std::process::id()));
+        let mut engine = crate::utils::headless_test_engine();
+
+        engine
+            .renderer
+            .start_capture_sequence(&directory, "frame_")
+            .unwrap();
+        engine.step().unwrap();
+        engine.step().unwrap();
+        engine.renderer.stop_capture_sequence();
+
+        let mut files = std::fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort();
+        assert_eq!(files, ["frame_000000.png", "frame_000001.png"]);
+
+        let image = image::open(directory.join("frame_000001.png")).unwrap();
+        assert_eq!((image.width(), image.height()), (32, 32));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    #[cfg(any(feature = "window", feature = "headless"))]
+    fn replaced_capture_sequence_saves_every_frame() {
+        let directory = |name: &str| {
+            std::env::temp_dir().join(format!("frame_capture_{name}_{}", std::process::id()))
+        };
+        let mut engine = crate::utils::headless_test_engine();
+
+        engine
+            .renderer
+            .start_capture_sequence(directory("replaced"), "frame_")
+            .unwrap();
+        for _ in 0..3 {
+            engine.step().unwrap();
+        }
+        // the first sequence is dropped without stopping it
+        engine
+            .renderer
+            .start_capture_sequence(directory("replacement"), "frame_")
+            .unwrap();
+        engine.step().unwrap();
+        drop(engine);
+
+        for (name, frames) in [("replaced", 3), ("replacement", 1)] {
+            let files = std::fs::read_dir(directory(name)).unwrap().count();
+            assert_eq!(files, frames);
+            std::fs::remove_dir_all(directory(name)).unwrap();
+        }
+    }
+}
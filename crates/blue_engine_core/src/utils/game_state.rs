@@ -0,0 +1,405 @@
+/*
+ * Blue Engine by Elham Aryanpur
+ *
+ * The license is same as the one on the root.
+*/
+
+use crate::{CameraContainer, Engine, ObjectStorage, Signal};
+use std::any::Any;
+use std::collections::HashSet;
+
+/// A screen of the game, such as a main menu, a level or a pause overlay, kept on the
+/// [`StateStack`] of the engine. Each state owns its objects, cameras and the signals
+/// added while it is on top, which are put aside while another state is on top of it.
+///
+/// All hooks are optional.
+pub trait GameState: Any {
+    /// Runs when the state is pushed, once its empty objects and cameras are active
+    fn enter(&mut self, _engine: &mut Engine) {}
+    /// Runs when the state is popped or replaced, before its objects, cameras and signals
+    /// are dropped
+    fn exit(&mut self, _engine: &mut Engine) {}
+    /// Runs when another state is pushed on top of this one, before its objects, cameras
+    /// and signals are put aside
+    fn pause(&mut self, _engine: &mut Engine) {}
+    /// Runs when the state on top is popped and this one is active again
+    fn resume(&mut self, _engine: &mut Engine) {}
+    /// Runs every frame while the state is on top, right before the update loop
+    fn update(&mut self, _engine: &mut Engine) {}
+    /// Whether the objects of the state keep rendering underneath the states on top of it,
+    /// such as a level under its pause menu
+    fn render_when_paused(&self) -> bool {
+        false
+    }
+}
+
+/// The objects, cameras and own signals of a state that is not on top
+struct StateLayer {
+    objects: ObjectStorage,
+    camera: CameraContainer,
+    signals: Vec<(String, Box<dyn Signal>)>,
+}
+
+struct StateEntry {
+    /// taken out while one of its hooks runs
+    state: Option<Box<dyn GameState>>,
+    /// set while the state is not on top
+    layer: Option<StateLayer>,
+    /// the signals that existed when the state became active, the rest are its own
+    inherited_signals: HashSet<String>,
+}
+
+enum StateTransition {
+    Push(Box<dyn GameState>),
+    Pop,
+    Replace(Box<dyn GameState>),
+    Clear,
+}
+
+/// A stack of [`GameState`]s, where the state on top is active. The transitions are
+/// applied at the start of the next frame.
+///
+/// The objects and cameras in the engine are always the ones of the state on top. The
+/// ones from before the first state was pushed are put aside as well, and come back once
+/// the stack is empty again. The signals from before the first state stay active for
+/// every state.
+/// ```ignore
+/// struct Level;
+/// impl GameState for Level {
+///     fn enter(&mut self, engine: &mut Engine) {
+///         // add the objects of the level to `engine.objects`
+///     }
+///     fn render_when_paused(&self) -> bool {
+///         true
+///     }
+/// }
+///
+/// struct PauseMenu;
+/// impl GameState for PauseMenu {}
+///
+/// engine.states.push(Level);
+/// // later, the level keeps rendering under the menu
+/// engine.states.push(PauseMenu);
+/// ```
+#[derive(Default)]
+pub struct StateStack {
+    states: Vec<StateEntry>,
+    /// the objects and cameras from before the first state
+    base: Option<StateLayer>,
+    pending: Vec<StateTransition>,
+}
+
+impl StateStack {
+    /// Creates an empty state stack
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pauses the state on top, and pushes the new state on top of it
+    pub fn push(&mut self, state: impl GameState) {
+        self.pending.push(StateTransition::Push(Box::new(state)));
+    }
+
+    /// Exits the state on top, and resumes the one below it
+    pub fn pop(&mut self) {
+        self.pending.push(StateTransition::Pop);
+    }
+
+    /// Exits the state on top, and pushes the new state in its place without resuming
+    /// the one below it
+    pub fn replace(&mut self, state: impl GameState) {
+        self.pending.push(StateTransition::Replace(Box::new(state)));
+    }
+
+    /// Exits all states, from the top down
+    pub fn clear(&mut self) {
+        self.pending.push(StateTransition::Clear);
+    }
+
+    /// Returns the amount of states on the stack
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Returns true if there are no states on the stack
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    /// Returns true if transitions are waiting for the next frame
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Gets the state on top if it is of the type
+    pub fn top<T: GameState>(&mut self) -> Option<&mut T> {
+        let state: &mut dyn Any = self.states.last_mut()?.state.as_mut()?.as_mut();
+        state.downcast_mut::<T>()
+    }
+
+    /// Gets the highest state of the type on the stack
+    pub fn get<T: GameState>(&mut self) -> Option<&mut T> {
+        self.states.iter_mut().rev().find_map(|entry| {
+            let state: &mut dyn Any = entry.state.as_mut()?.as_mut();
+            state.downcast_mut::<T>()
+        })
+    }
+
+    /// Returns the object and camera layers to render, starting with the paused states that
+    /// render underneath, and ending with the active objects and cameras
+    pub(crate) fn render_layers<'a>(
+        &'a self,
+        objects: &'a ObjectStorage,
+        camera: &'a CameraContainer,
+    ) -> Vec<(&'a ObjectStorage, &'a CameraContainer)> {
+        let mut layers = Vec::new();
+        for entry in self.states.iter().rev().skip(1) {
+            match (&entry.state, &entry.layer) {
+                (Some(state), Some(layer)) if state.render_when_paused() => {
+                    layers.push((&layer.objects, &layer.camera))
+                }
+                _ => break,
+            }
+        }
+        layers.reverse();
+        layers.push((objects, camera));
+        layers
+    }
+
+    /// Returns the cameras of the states that are put aside
+    pub(crate) fn paused_cameras_mut(&mut self) -> impl Iterator<Item = &mut CameraContainer> {
+        self.states
+            .iter_mut()
+            .filter_map(|entry| entry.layer.as_mut())
+            .chain(self.base.as_mut())
+            .map(|layer| &mut layer.camera)
+    }
+
+    /// Returns the objects of the states that are put aside
+    pub(crate) fn paused_objects_mut(&mut self) -> impl Iterator<Item = &mut ObjectStorage> {
+        self.states
+            .iter_mut()
+            .filter_map(|entry| entry.layer.as_mut())
+            .chain(self.base.as_mut())
+            .map(|layer| &mut layer.objects)
+    }
+}
+
+impl std::fmt::Debug for StateStack {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StateStack")
+            .field("len", &self.states.len())
+            .field("pending", &self.pending.len())
+            .finish()
+    }
+}
+
+impl Engine {
+    /// Applies the state transitions requested since the last frame
+    pub(crate) fn apply_state_transitions(&mut self) {
+        while !self.states.pending.is_empty() {
+            for transition in std::mem::take(&mut self.states.pending) {
+                match transition {
+                    StateTransition::Push(state) => self.push_state(state),
+                    StateTransition::Pop => {
+                        if self.exit_state() {
+                            self.restore_state_layer(true);
+                        }
+                    }
+                    StateTransition::Replace(state) => {
+                        if self.exit_state() {
+                            self.take_state_layer(false);
+                            self.enter_state(state);
+                        } else {
+                            self.push_state(state);
+                        }
+                    }
+                    StateTransition::Clear => {
+                        while self.exit_state() {
+                            self.restore_state_layer(false);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Runs the update hook of the state on top
+    pub(crate) fn update_states(&mut self) {
+        if let Some(index) = self.states.states.len().checked_sub(1) {
+            self.run_state_hook(index, |state, engine| state.update(engine));
+        }
+    }
+
+    fn run_state_hook(&mut self, index: usize, hook: impl FnOnce(&mut dyn GameState, &mut Engine)) {
+        let Some(mut state) = self.states.states[index].state.take() else {
+            return;
+        };
+        hook(state.as_mut(), self);
+        self.states.states[index].state = Some(state);
+    }
+
+    /// Pauses the state on top and puts its layer aside, then pushes the new state
+    fn push_state(&mut self, state: Box<dyn GameState>) {
+        if let Some(index) = self.states.states.len().checked_sub(1) {
+            self.run_state_hook(index, |state, engine| state.pause(engine));
+        }
+        let layer = self.take_state_layer(!self.states.states.is_empty());
+        match self.states.states.last_mut() {
+            Some(entry) => entry.layer = Some(layer),
+            None => self.states.base = Some(layer),
+        }
+        self.enter_state(state);
+    }
+
+    /// Pushes the state with empty objects and cameras, which must be active already
+    fn enter_state(&mut self, state: Box<dyn GameState>) {
+        self.apply_signal_changes();
+        self.states.states.push(StateEntry {
+            state: Some(state),
+            layer: None,
+            inherited_signals: self.signals.events.iter().map(|i| i.0.clone()).collect(),
+        });
+        self.run_state_hook(self.states.states.len() - 1, |state, engine| {
+            state.enter(engine)
+        });
+    }
+
+    /// Exits and removes the state on top along with its own signals. Returns false if
+    /// there are no states
+    fn exit_state(&mut self) -> bool {
+        let Some(index) = self.states.states.len().checked_sub(1) else {
+            return false;
+        };
+        self.run_state_hook(index, |state, engine| state.exit(engine));
+        if let Some(entry) = self.states.states.pop() {
+            self.apply_signal_changes();
+            let (inherited, own): (Vec<_>, Vec<_>) = std::mem::take(&mut self.signals.events)
+                .into_iter()
+                .partition(|i| entry.inherited_signals.contains(&i.0));
+            self.signals.events = inherited;
+
+            // the signals of the state are gone for good, as if the engine shut down
+            self.signals.running += 1;
+            for (_, mut signal) in own {
+                signal.shutdown(self);
+            }
+            self.signals.running -= 1;
+            self.apply_signal_changes();
+        }
+        true
+    }
+
+    /// Puts the active objects and cameras aside, leaving empty ones in their place. The
+    /// signals the state on top added are put aside too if `with_signals` is set
+    fn take_state_layer(&mut self, with_signals: bool) -> StateLayer {
+        let size = (self.renderer.config.width, self.renderer.config.height);
+        let mut camera = CameraContainer::new(size, &mut self.renderer);
+        camera.update_view_projection(&mut self.renderer);
+
+        self.apply_signal_changes();
+        let mut signals = Vec::new();
+        if let Some(entry) = self.states.states.last().filter(|_| with_signals) {
+            let (own, inherited) = std::mem::take(&mut self.signals.events)
+                .into_iter()
+                .partition(|i| !entry.inherited_signals.contains(&i.0));
+            self.signals.events = inherited;
+            signals = own;
+        }
+
+        StateLayer {
+            objects: std::mem::take(&mut self.objects),
+            camera: std::mem::replace(&mut self.camera, camera),
+            signals,
+        }
+    }
+
+    /// Brings back the objects, cameras and signals of the state that is on top again, or the
+    /// ones from before the first state
+    fn restore_state_layer(&mut self, resume: bool) {
+        let layer = match self.states.states.last_mut() {
+            Some(entry) => entry.layer.take(),
+            None => self.states.base.take(),
+        };
+        let Some(layer) = layer else {
+            return;
+        };
+
+        self.objects = layer.objects;
+        self.camera = layer.camera;
+        self.apply_signal_changes();
+        // the restored signals belong to the state again, the others are inherited
+        let inherited = self.signals.events.iter().map(|i| i.0.clone()).collect();
+        for (key, signal) in layer.signals {
+            self.signals.insert(key, signal);
+        }
+
+        if let Some(index) = self.states.states.len().checked_sub(1) {
+            self.states.states[index].inherited_signals = inherited;
+            if resume {
+                self.run_state_hook(index, |state, engine| state.resume(engine));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    struct Tracked(Rc<Cell<u32>>);
+    impl Signal for Tracked {
+        fn shutdown(&mut self, _engine: &mut Engine) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    struct Level(Rc<Cell<u32>>);
+    impl GameState for Level {
+        fn enter(&mut self, engine: &mut Engine) {
+            engine
+                .signals
+                .add_signal("level", Box::new(Tracked(self.0.clone())));
+        }
+    }
+
+    #[test]
+    fn exit_shuts_down_own_signals() {
+        let mut engine = crate::utils::headless_test_engine();
+
+        let global = Rc::new(Cell::new(0));
+        let level = Rc::new(Cell::new(0));
+        engine
+            .signals
+            .add_signal("global", Box::new(Tracked(global.clone())));
+        engine.states.push(Level(level.clone()));
+        engine.step().unwrap();
+        assert!(engine.signals.events.iter().any(|i| i.0 == "level"));
+
+        engine.states.pop();
+        engine.step().unwrap();
+        assert_eq!(level.get(), 1);
+        assert_eq!(global.get(), 0);
+        assert!(!engine.signals.events.iter().any(|i| i.0 == "level"));
+        assert!(engine.signals.events.iter().any(|i| i.0 == "global"));
+    }
+
+    #[test]
+    fn shutdown_runs_each_hook_once() {
+        let mut engine = crate::utils::headless_test_engine();
+
+        let global = Rc::new(Cell::new(0));
+        let level = Rc::new(Cell::new(0));
+        engine
+            .signals
+            .add_signal("global", Box::new(Tracked(global.clone())));
+        engine.states.push(Level(level.clone()));
+        engine.step().unwrap();
+
+        engine.shutdown();
+        assert_eq!(level.get(), 1);
+        assert_eq!(global.get(), 1);
+    }
+}
@@ -0,0 +1,789 @@
+/*
+ * Blue Engine by Elham Aryanpur
+ *
+ * The license is same as the one on the root.
+*/
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// The id of a connected gamepad, given by the backend
+pub type GamepadId = usize;
+
+/// Buttons of a gamepad, following the layout of a standard controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum GamepadButton {
+    /// Bottom button of the action pad, e.g. A on Xbox and Cross on PlayStation
+    South,
+    /// Right button of the action pad, e.g. B on Xbox and Circle on PlayStation
+    East,
+    /// Top button of the action pad, e.g. Y on Xbox and Triangle on PlayStation
+    North,
+    /// Left button of the action pad, e.g. X on Xbox and Square on PlayStation
+    West,
+    /// Left bumper
+    LeftTrigger,
+    /// Left trigger
+    LeftTrigger2,
+    /// Right bumper
+    RightTrigger,
+    /// Right trigger
+    RightTrigger2,
+    /// Select, back or share button
+    Select,
+    /// Start or options button
+    Start,
+    /// The vendor button, e.g. the Xbox or PlayStation button
+    Mode,
+    /// Pressing the left stick
+    LeftThumb,
+    /// Pressing the right stick
+    RightThumb,
+    /// D-Pad up
+    DPadUp,
+    /// D-Pad down
+    DPadDown,
+    /// D-Pad left
+    DPadLeft,
+    /// D-Pad right
+    DPadRight,
+}
+
+/// Axes of a gamepad, with values between -1 and +1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum GamepadAxis {
+    /// Horizontal movement of the left stick
+    LeftStickX,
+    /// Vertical movement of the left stick
+    LeftStickY,
+    /// Left analog trigger, on gamepads that report it as an axis
+    LeftZ,
+    /// Horizontal movement of the right stick
+    RightStickX,
+    /// Vertical movement of the right stick
+    RightStickY,
+    /// Right analog trigger, on gamepads that report it as an axis
+    RightZ,
+}
+
+/// Events produced by a gamepad backend
+#[derive(Debug, Clone, PartialEq)]
+pub enum GamepadEvent {
+    /// A gamepad has been connected
+    Connected {
+        /// The id of the gamepad
+        id: GamepadId,
+        /// The name reported by the gamepad
+        name: String,
+    },
+    /// A gamepad has been disconnected
+    Disconnected {
+        /// The id of the gamepad
+        id: GamepadId,
+    },
+    /// A button went from "not pressed" to "pressed"
+    ButtonPressed {
+        /// The id of the gamepad
+        id: GamepadId,
+        /// The button that was pressed
+        button: GamepadButton,
+    },
+    /// A button went from "pressed" to "not pressed"
+    ButtonReleased {
+        /// The id of the gamepad
+        id: GamepadId,
+        /// The button that was released
+        button: GamepadButton,
+    },
+    /// The value of an axis has changed
+    AxisChanged {
+        /// The id of the gamepad
+        id: GamepadId,
+        /// The axis that changed
+        axis: GamepadAxis,
+        /// The raw value of the axis, between -1 and +1
+        value: f32,
+    },
+}
+
+/// A request to vibrate a gamepad
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RumbleRequest {
+    /// The id of the gamepad
+    pub id: GamepadId,
+    /// Strength of the low frequency motor, between 0 and 1
+    pub strong: f32,
+    /// Strength of the high frequency motor, between 0 and 1
+    pub weak: f32,
+    /// How long the vibration should last
+    pub duration: std::time::Duration,
+}
+
+/// A source of gamepad events. The engine uses [`GilrsBackend`] when the `gamepad` feature
+/// is enabled, and [`MockGamepadBackend`] can be used to drive the engine with synthetic events.
+pub trait GamepadBackend: Send {
+    /// Returns the next pending event, if any
+    fn next_event(&mut self) -> Option<GamepadEvent>;
+
+    /// Vibrates the gamepad according to the request
+    fn rumble(&mut self, request: RumbleRequest) -> Result<(), crate::error::Error>;
+}
+
+/// The state of a single connected gamepad
+#[derive(Debug, Clone, Default)]
+pub struct GamepadState {
+    /// The name reported by the gamepad
+    pub name: String,
+    buttons_held: HashSet<GamepadButton>,
+    buttons_pressed: HashSet<GamepadButton>,
+    buttons_released: HashSet<GamepadButton>,
+    axes: HashMap<GamepadAxis, f32>,
+}
+
+/// Holds the state of all gamepads, updated every frame from the backend.
+pub struct Gamepads {
+    backend: Option<Box<dyn GamepadBackend>>,
+    gamepads: HashMap<GamepadId, GamepadState>,
+    events: Vec<GamepadEvent>,
+    /// Axis values with an absolute value below this are reported as `0.0`.
+    /// The rest of the range is rescaled so it still reaches `1.0`.
+    pub deadzone: f32,
+}
+impl std::fmt::Debug for Gamepads {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Gamepads")
+            .field("has_backend", &self.backend.is_some())
+            .field("gamepads", &self.gamepads)
+            .field("events", &self.events)
+            .field("deadzone", &self.deadzone)
+            .finish()
+    }
+}
+
+impl Gamepads {
+    /// Creates the gamepad state using the platform backend if the `gamepad` feature is
+    /// enabled and the platform supports it, otherwise no backend is set.
+    pub fn new() -> Self {
+        #[cfg(feature = "gamepad")]
+        let backend = match GilrsBackend::new() {
+            Ok(backend) => Some(Box::new(backend) as Box<dyn GamepadBackend>),
+            Err(e) => {
+                log::warn!("Gamepad support is not available: {e}");
+                None
+            }
+        };
+        #[cfg(not(feature = "gamepad"))]
+        let backend = None;
+
+        Self {
+            backend,
+            gamepads: HashMap::new(),
+            events: Vec::new(),
+            deadzone: 0.1,
+        }
+    }
+
+    /// Creates the gamepad state with a custom backend
+    pub fn with_backend(backend: Box<dyn GamepadBackend>) -> Self {
+        Self {
+            backend: Some(backend),
+            ..Self::empty()
+        }
+    }
+
+    fn empty() -> Self {
+        Self {
+            backend: None,
+            gamepads: HashMap::new(),
+            events: Vec::new(),
+            deadzone: 0.1,
+        }
+    }
+
+    /// Replaces the backend. The state of all known gamepads is cleared.
+    pub fn set_backend(&mut self, backend: Box<dyn GamepadBackend>) {
+        self.backend = Some(backend);
+        self.gamepads.clear();
+        self.events.clear();
+    }
+
+    /// Starts a new step and processes all pending events of the backend.
+    ///
+    /// This is called by the engine every frame.
+    pub fn update(&mut self) {
+        self.events.clear();
+        for gamepad in self.gamepads.values_mut() {
+            gamepad.buttons_pressed.clear();
+            gamepad.buttons_released.clear();
+        }
+
+        let Some(backend) = self.backend.as_mut() else {
+            return;
+        };
+        while let Some(event) = backend.next_event() {
+            match &event {
+                GamepadEvent::Connected { id, name } => {
+                    self.gamepads.insert(
+                        *id,
+                        GamepadState {
+                            name: name.clone(),
+                            ..Default::default()
+                        },
+                    );
+                }
+                GamepadEvent::Disconnected { id } => {
+                    if self.gamepads.remove(id).is_none() {
+                        continue;
+                    }
+                }
+                GamepadEvent::ButtonPressed { id, button } => {
+                    // events of gamepads that never connected are ignored
+                    let Some(gamepad) = self.gamepads.get_mut(id) else {
+                        continue;
+                    };
+                    if gamepad.buttons_held.insert(*button) {
+                        gamepad.buttons_pressed.insert(*button);
+                    }
+                }
+                GamepadEvent::ButtonReleased { id, button } => {
+                    // events of gamepads that never connected are ignored
+                    let Some(gamepad) = self.gamepads.get_mut(id) else {
+                        continue;
+                    };
+                    if gamepad.buttons_held.remove(button) {
+                        gamepad.buttons_released.insert(*button);
+                    }
+                }
+                GamepadEvent::AxisChanged { id, axis, value } => {
+                    // events of gamepads that never connected are ignored
+                    let Some(gamepad) = self.gamepads.get_mut(id) else {
+                        continue;
+                    };
+                    // NaN is clamped to itself, so it is treated as the center instead
+                    let value = if value.is_nan() {
+                        0.0
+                    } else {
+                        value.clamp(-1.0, 1.0)
+                    };
+                    gamepad.axes.insert(*axis, value);
+                }
+            }
+            self.events.push(event);
+        }
+    }
+
+    /// Returns the events received during the last step, in order
+    pub fn events(&self) -> &[GamepadEvent] {
+        &self.events
+    }
+
+    /// Returns the ids of all connected gamepads
+    pub fn connected(&self) -> impl Iterator<Item = GamepadId> + '_ {
+        self.gamepads.keys().copied()
+    }
+
+    /// Returns the state of a connected gamepad
+    pub fn get(&self, id: GamepadId) -> Option<&GamepadState> {
+        self.gamepads.get(&id)
+    }
+
+    /// Returns true when the button goes from "not pressed" to "pressed".
+    pub fn button_pressed(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads
+            .get(&id)
+            .is_some_and(|i| i.buttons_pressed.contains(&button))
+    }
+
+    /// Returns true while the button remains "pressed".
+    pub fn button_held(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads
+            .get(&id)
+            .is_some_and(|i| i.buttons_held.contains(&button))
+    }
+
+    /// Returns true when the button goes from "pressed" to "not pressed".
+    pub fn button_released(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads
+            .get(&id)
+            .is_some_and(|i| i.buttons_released.contains(&button))
+    }
+
+    /// Returns true when the button was pressed on any of the gamepads
+    pub fn any_button_pressed(&self, button: GamepadButton) -> bool {
+        self.gamepads
+            .values()
+            .any(|i| i.buttons_pressed.contains(&button))
+    }
+
+    /// Returns the value of the axis with the deadzone applied
+    pub fn axis(&self, id: GamepadId, axis: GamepadAxis) -> f32 {
+        self.apply_deadzone(self.axis_raw(id, axis))
+    }
+
+    /// Returns the value of the axis as reported by the gamepad
+    pub fn axis_raw(&self, id: GamepadId, axis: GamepadAxis) -> f32 {
+        self.gamepads
+            .get(&id)
+            .and_then(|i| i.axes.get(&axis).copied())
+            .unwrap_or(0.0)
+    }
+
+    fn apply_deadzone(&self, value: f32) -> f32 {
+        let deadzone = if self.deadzone.is_nan() {
+            0.0
+        } else {
+            self.deadzone.clamp(0.0, 0.99)
+        };
+        if value.abs() <= deadzone {
+            0.0
+        } else {
+            value.signum() * (value.abs() - deadzone) / (1.0 - deadzone)
+        }
+    }
+
+    /// Vibrates the gamepad with the given motor strengths, between 0 and 1, for the duration
+    pub fn rumble(
+        &mut self,
+        id: GamepadId,
+        strong: f32,
+        weak: f32,
+        duration: std::time::Duration,
+    ) -> Result<(), crate::error::Error> {
+        match self.backend.as_mut() {
+            Some(backend) => backend.rumble(RumbleRequest {
+                id,
+                strong: strong.clamp(0.0, 1.0),
+                weak: weak.clamp(0.0, 1.0),
+                duration,
+            }),
+            None => Err(crate::error::Error::Custom(
+                "No gamepad backend is available".to_string(),
+            )),
+        }
+    }
+}
+
+impl Default for Gamepads {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Shared state of the [`MockGamepadBackend`]
+#[derive(Debug, Default)]
+pub struct MockGamepadState {
+    /// Events waiting to be received by the engine
+    pub pending_events: VecDeque<GamepadEvent>,
+    /// All rumble requests that have been made
+    pub rumble_requests: Vec<RumbleRequest>,
+}
+
+/// A gamepad backend driven by synthetic events.
+///
+/// The backend is cheap to clone and all clones share the same state, so one can be given
+/// to the engine while another one is kept around to push events and inspect rumble requests.
+#[derive(Debug, Clone, Default)]
+pub struct MockGamepadBackend {
+    /// The shared state of the backend
+    pub state: Arc<Mutex<MockGamepadState>>,
+}
+
+impl MockGamepadBackend {
+    /// Creates a new mock backend with no events
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues an event that will be received on the next update
+    pub fn push_event(&self, event: GamepadEvent) {
+        if let Ok(mut state) = self.state.lock() {
+            state.pending_events.push_back(event);
+        }
+    }
+
+    /// Returns and clears the rumble requests made so far
+    pub fn take_rumble_requests(&self) -> Vec<RumbleRequest> {
+        match self.state.lock() {
+            Ok(mut state) => std::mem::take(&mut state.rumble_requests),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+impl GamepadBackend for MockGamepadBackend {
+    fn next_event(&mut self) -> Option<GamepadEvent> {
+        self.state
+            .lock()
+            .ok()
+            .and_then(|mut state| state.pending_events.pop_front())
+    }
+
+    fn rumble(&mut self, request: RumbleRequest) -> Result<(), crate::error::Error> {
+        if let Ok(mut state) = self.state.lock() {
+            state.rumble_requests.push(request);
+        }
+        Ok(())
+    }
+}
+
+/// The platform gamepad backend, using [gilrs](https://docs.rs/gilrs)
+#[cfg(feature = "gamepad")]
+pub struct GilrsBackend {
+    gilrs: gilrs::Gilrs,
+    ids: HashMap<GamepadId, gilrs::GamepadId>,
+    rumble_effects: HashMap<GamepadId, gilrs::ff::Effect>,
+    /// Events created by the backend itself, returned before the ones of gilrs
+    pending_events: VecDeque<GamepadEvent>,
+}
+
+#[cfg(feature = "gamepad")]
+impl GilrsBackend {
+    /// Starts the platform gamepad backend
+    pub fn new() -> Result<Self, crate::error::Error> {
+        let gilrs = gilrs::Gilrs::new().map_err(|e| crate::error::Error::Custom(e.to_string()))?;
+        let mut backend = Self {
+            gilrs,
+            ids: HashMap::new(),
+            rumble_effects: HashMap::new(),
+            pending_events: VecDeque::new(),
+        };
+        // gilrs does not send events for the gamepads that are connected at startup
+        let connected = backend
+            .gilrs
+            .gamepads()
+            .map(|(id, gamepad)| (id, gamepad.name().to_string()))
+            .collect::<Vec<_>>();
+        for (id, name) in connected {
+            backend.ids.insert(usize::from(id), id);
+            backend.pending_events.push_back(GamepadEvent::Connected {
+                id: usize::from(id),
+                name,
+            });
+        }
+        Ok(backend)
+    }
+
+    fn convert_button(button: gilrs::Button) -> Option<GamepadButton> {
+        use gilrs::Button;
+        Some(match button {
+            Button::South => GamepadButton::South,
+            Button::East => GamepadButton::East,
+            Button::North => GamepadButton::North,
+            Button::West => GamepadButton::West,
+            Button::LeftTrigger => GamepadButton::LeftTrigger,
+            Button::LeftTrigger2 => GamepadButton::LeftTrigger2,
+            Button::RightTrigger => GamepadButton::RightTrigger,
+            Button::RightTrigger2 => GamepadButton::RightTrigger2,
+            Button::Select => GamepadButton::Select,
+            Button::Start => GamepadButton::Start,
+            Button::Mode => GamepadButton::Mode,
+            Button::LeftThumb => GamepadButton::LeftThumb,
+            Button::RightThumb => GamepadButton::RightThumb,
+            Button::DPadUp => GamepadButton::DPadUp,
+            Button::DPadDown => GamepadButton::DPadDown,
+            Button::DPadLeft => GamepadButton::DPadLeft,
+            Button::DPadRight => GamepadButton::DPadRight,
+            _ => return None,
+        })
+    }
+
+    fn convert_axis(axis: gilrs::Axis) -> Option<GamepadAxis> {
+        use gilrs::Axis;
+        Some(match axis {
+            Axis::LeftStickX => GamepadAxis::LeftStickX,
+            Axis::LeftStickY => GamepadAxis::LeftStickY,
+            Axis::LeftZ => GamepadAxis::LeftZ,
+            Axis::RightStickX => GamepadAxis::RightStickX,
+            Axis::RightStickY => GamepadAxis::RightStickY,
+            Axis::RightZ => GamepadAxis::RightZ,
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(feature = "gamepad")]
+impl GamepadBackend for GilrsBackend {
+    fn next_event(&mut self) -> Option<GamepadEvent> {
+        if let Some(event) = self.pending_events.pop_front() {
+            return Some(event);
+        }
+        // skip over the events that have no equivalent in the engine
+        while let Some(gilrs::Event { id, event, .. }) = self.gilrs.next_event() {
+            let engine_id = usize::from(id);
+            let converted = match event {
+                gilrs::EventType::Connected => {
+                    self.ids.insert(engine_id, id);
+                    Some(GamepadEvent::Connected {
+                        id: engine_id,
+                        name: self.gilrs.gamepad(id).name().to_string(),
+                    })
+                }
+                gilrs::EventType::Disconnected => {
+                    self.ids.remove(&engine_id);
+                    self.rumble_effects.remove(&engine_id);
+                    Some(GamepadEvent::Disconnected { id: engine_id })
+                }
+                gilrs::EventType::ButtonPressed(button, _) => {
+                    Self::convert_button(button).map(|button| GamepadEvent::ButtonPressed {
+                        id: engine_id,
+                        button,
+                    })
+                }
+                gilrs::EventType::ButtonReleased(button, _) => {
+                    Self::convert_button(button).map(|button| GamepadEvent::ButtonReleased {
+                        id: engine_id,
+                        button,
+                    })
+                }
+                gilrs::EventType::AxisChanged(axis, value, _) => {
+                    Self::convert_axis(axis).map(|axis| GamepadEvent::AxisChanged {
+                        id: engine_id,
+                        axis,
+                        value,
+                    })
+                }
+                _ => None,
+            };
+
+            if converted.is_some() {
+                return converted;
+            }
+        }
+        None
+    }
+
+    fn rumble(&mut self, request: RumbleRequest) -> Result<(), crate::error::Error> {
+        use gilrs::ff::{BaseEffect, BaseEffectType, EffectBuilder, Replay, Ticks};
+
+        let Some(id) = self.ids.get(&request.id).copied() else {
+            return Err(crate::error::Error::Custom(format!(
+                "Gamepad {} is not connected",
+                request.id
+            )));
+        };
+        let play_for = Ticks::from_ms(request.duration.as_millis().min(u32::MAX as u128) as u32);
+        let scheduling = Replay {
+            play_for,
+            ..Default::default()
+        };
+
+        let effect = EffectBuilder::new()
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Strong {
+                    magnitude: (request.strong * u16::MAX as f32) as u16,
+                },
+                scheduling,
+                ..Default::default()
+            })
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Weak {
+                    magnitude: (request.weak * u16::MAX as f32) as u16,
+                },
+                scheduling,
+                ..Default::default()
+            })
+            .repeat(gilrs::ff::Repeat::For(play_for))
+            .gamepads(&[id])
+            .finish(&mut self.gilrs)
+            .map_err(|e| crate::error::Error::Custom(e.to_string()))?;
+        effect
+            .play()
+            .map_err(|e| crate::error::Error::Custom(e.to_string()))?;
+
+        // the effect stops once dropped, so keep the latest one of each gamepad alive
+        self.rumble_effects.insert(request.id, effect);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the gamepads and a handle to their backend to push events with
+    fn gamepads() -> (Gamepads, MockGamepadBackend) {
+        let backend = MockGamepadBackend::new();
+        (Gamepads::with_backend(Box::new(backend.clone())), backend)
+    }
+
+    fn push(backend: &MockGamepadBackend, events: impl IntoIterator<Item = GamepadEvent>) {
+        events
+            .into_iter()
+            .for_each(|event| backend.push_event(event));
+    }
+
+    fn connected(id: GamepadId) -> GamepadEvent {
+        GamepadEvent::Connected {
+            id,
+            name: format!("Pad {id}"),
+        }
+    }
+
+    fn axis(value: f32) -> GamepadEvent {
+        GamepadEvent::AxisChanged {
+            id: 0,
+            axis: GamepadAxis::LeftStickX,
+            value,
+        }
+    }
+
+    #[test]
+    fn connect_and_disconnect() {
+        let (mut pads, backend) = gamepads();
+        push(&backend, [connected(0), connected(1)]);
+
+        pads.update();
+        let mut ids = pads.connected().collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, vec![0, 1]);
+        assert_eq!(pads.get(1).map(|i| i.name.as_str()), Some("Pad 1"));
+        assert_eq!(pads.events().len(), 2);
+
+        push(&backend, [GamepadEvent::Disconnected { id: 0 }]);
+        pads.update();
+        assert_eq!(pads.connected().collect::<Vec<_>>(), vec![1]);
+        assert!(pads.get(0).is_none());
+        assert_eq!(pads.events(), &[GamepadEvent::Disconnected { id: 0 }]);
+
+        pads.update();
+        assert!(pads.events().is_empty());
+    }
+
+    #[test]
+    fn unknown_ids_are_ignored() {
+        let (mut pads, backend) = gamepads();
+        push(
+            &backend,
+            [
+                GamepadEvent::ButtonPressed {
+                    id: 3,
+                    button: GamepadButton::South,
+                },
+                GamepadEvent::AxisChanged {
+                    id: 3,
+                    axis: GamepadAxis::LeftStickX,
+                    value: 1.0,
+                },
+                GamepadEvent::Disconnected { id: 3 },
+            ],
+        );
+
+        pads.update();
+        assert_eq!(pads.connected().count(), 0);
+        assert!(pads.events().is_empty());
+        assert!(!pads.button_held(3, GamepadButton::South));
+    }
+
+    #[test]
+    fn button_state() {
+        let (mut pads, backend) = gamepads();
+        push(
+            &backend,
+            [
+                connected(0),
+                GamepadEvent::ButtonPressed {
+                    id: 0,
+                    button: GamepadButton::South,
+                },
+            ],
+        );
+
+        pads.update();
+        assert!(pads.button_pressed(0, GamepadButton::South));
+        assert!(pads.button_held(0, GamepadButton::South));
+        assert!(pads.any_button_pressed(GamepadButton::South));
+
+        pads.update();
+        assert!(!pads.button_pressed(0, GamepadButton::South));
+        assert!(pads.button_held(0, GamepadButton::South));
+
+        push(
+            &backend,
+            [GamepadEvent::ButtonReleased {
+                id: 0,
+                button: GamepadButton::South,
+            }],
+        );
+        pads.update();
+        assert!(pads.button_released(0, GamepadButton::South));
+        assert!(!pads.button_held(0, GamepadButton::South));
+    }
+
+    #[test]
+    fn axis_deadzone() {
+        let (mut pads, backend) = gamepads();
+        pads.deadzone = 0.1;
+
+        push(&backend, [connected(0), axis(0.05)]);
+        pads.update();
+        assert_eq!(pads.axis_raw(0, GamepadAxis::LeftStickX), 0.05);
+        assert_eq!(pads.axis(0, GamepadAxis::LeftStickX), 0.0);
+
+        push(&backend, [axis(-0.55)]);
+        pads.update();
+        assert!((pads.axis(0, GamepadAxis::LeftStickX) + 0.5).abs() < 1e-6);
+
+        push(&backend, [axis(2.0)]);
+        pads.update();
+        assert_eq!(pads.axis_raw(0, GamepadAxis::LeftStickX), 1.0);
+        assert_eq!(pads.axis(0, GamepadAxis::LeftStickX), 1.0);
+        assert_eq!(pads.axis(0, GamepadAxis::RightStickY), 0.0);
+    }
+
+    #[test]
+    fn nan_is_centered() {
+        let (mut pads, backend) = gamepads();
+        push(&backend, [connected(0), axis(f32::NAN)]);
+
+        pads.update();
+        assert_eq!(pads.axis_raw(0, GamepadAxis::LeftStickX), 0.0);
+        assert_eq!(pads.axis(0, GamepadAxis::LeftStickX), 0.0);
+
+        push(&backend, [axis(0.5)]);
+        pads.update();
+        pads.deadzone = f32::NAN;
+        assert_eq!(pads.axis(0, GamepadAxis::LeftStickX), 0.5);
+    }
+
+    #[test]
+    fn rumble() {
+        let (mut pads, backend) = gamepads();
+        let duration = std::time::Duration::from_millis(200);
+
+        pads.rumble(0, 2.0, -1.0, duration).unwrap();
+        pads.rumble(1, 0.25, 0.75, duration).unwrap();
+        assert_eq!(
+            backend.take_rumble_requests(),
+            [
+                RumbleRequest {
+                    id: 0,
+                    strong: 1.0,
+                    weak: 0.0,
+                    duration,
+                },
+                RumbleRequest {
+                    id: 1,
+                    strong: 0.25,
+                    weak: 0.75,
+                    duration,
+                },
+            ]
+        );
+        assert!(backend.take_rumble_requests().is_empty());
+
+        let mut pads = Gamepads::empty();
+        assert!(pads.rumble(0, 1.0, 1.0, duration).is_err());
+    }
+
+    #[cfg(any(feature = "window", feature = "headless"))]
+    #[test]
+    fn engine_updates_gamepads() {
+        let mut engine = crate::utils::headless_test_engine();
+        let backend = MockGamepadBackend::new();
+        engine.gamepads.set_backend(Box::new(backend.clone()));
+
+        push(&backend, [connected(0), axis(0.5)]);
+        engine.step().unwrap();
+        assert_eq!(engine.gamepads.axis_raw(0, GamepadAxis::LeftStickX), 0.5);
+    }
+}
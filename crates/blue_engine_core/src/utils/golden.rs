@@ -0,0 +1,363 @@
+/*
+ * Blue Engine by Elham Aryanpur
+ *
+ * The license is same as the one on the root.
+*/
+
+use crate::{Engine, EngineSettings};
+use std::path::PathBuf;
+
+/// Set this environment variable to `1` to replace the reference images with the new output
+pub const UPDATE_GOLDEN_ENV: &str = "BLUE_ENGINE_UPDATE_GOLDEN";
+
+/// The result of comparing two images
+#[derive(Debug, Clone)]
+pub struct ImageComparison {
+    /// The amount of pixels that differ more than the tolerance
+    pub differing_pixels: u64,
+    /// The amount of pixels compared
+    pub total_pixels: u64,
+    /// The largest perceptual difference found, between 0 and 1
+    pub max_difference: f32,
+    /// The reference image in grayscale, with the differing pixels in red
+    pub diff_image: image::RgbaImage,
+}
+
+impl ImageComparison {
+    /// Returns the fraction of the pixels that differ, between 0 and 1
+    pub fn differing_fraction(&self) -> f32 {
+        if self.total_pixels == 0 {
+            0.0
+        } else {
+            self.differing_pixels as f32 / self.total_pixels as f32
+        }
+    }
+}
+
+/// Blends the pixel over white and converts it to the YIQ color space
+fn to_yiq(pixel: &image::Rgba<u8>) -> (f32, f32, f32) {
+    let alpha = pixel[3] as f32 / 255.0;
+    let blend = |channel: u8| 255.0 + (channel as f32 - 255.0) * alpha;
+    let (r, g, b) = (blend(pixel[0]), blend(pixel[1]), blend(pixel[2]));
+
+    (
+        r * 0.298_895_3 + g * 0.586_622_5 + b * 0.114_482_23,
+        r * 0.595_977_97 - g * 0.274_176_1 - b * 0.321_801_9,
+        r * 0.211_470_17 - g * 0.522_617_1 + b * 0.311_146_94,
+    )
+}
+
+/// Returns the perceptual difference of two pixels between 0 and 1, based on the
+/// difference of their brightness and color as the eye sees them
+pub fn perceptual_difference(a: &image::Rgba<u8>, b: &image::Rgba<u8>) -> f32 {
+    // the largest possible value of the weighted distance below
+    const MAX_DELTA: f32 = 35215.0;
+
+    let (a, b) = (to_yiq(a), to_yiq(b));
+    let (y, i, q) = (a.0 - b.0, a.1 - b.1, a.2 - b.2);
+    (0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q) / MAX_DELTA
+}
+
+/// Compares two images of the same size. Pixels with a perceptual difference above
+/// the tolerance are counted as differing.
+pub fn compare_images(
+    reference: &image::RgbaImage,
+    actual: &image::RgbaImage,
+    tolerance: f32,
+) -> Result<ImageComparison, crate::error::Error> {
+    if reference.dimensions() != actual.dimensions() {
+        return Err(crate::error::Error::Custom(format!(
+            "The image sizes do not match: expected {:?} but got {:?}",
+            reference.dimensions(),
+            actual.dimensions()
+        )));
+    }
+
+    let mut diff_image = image::RgbaImage::new(reference.width(), reference.height());
+    let mut differing_pixels = 0;
+    let mut max_difference = 0f32;
+    for ((expected, found), diff) in reference
+        .pixels()
+        .zip(actual.pixels())
+        .zip(diff_image.pixels_mut())
+    {
+        let difference = perceptual_difference(expected, found);
+        max_difference = max_difference.max(difference);
+
+        *diff = if difference > tolerance {
+            differing_pixels += 1;
+            image::Rgba([255, 0, 0, 255])
+        } else {
+            // faded so the differing pixels stand out
+            let gray = (to_yiq(expected).0 * 0.1 + 229.5) as u8;
+            image::Rgba([gray, gray, gray, 255])
+        };
+    }
+
+    Ok(ImageComparison {
+        differing_pixels,
+        total_pixels: reference.width() as u64 * reference.height() as u64,
+        max_difference,
+        diff_image,
+    })
+}
+
+/// The outcome of a golden image check
+#[derive(Debug, Clone)]
+pub enum GoldenResult {
+    /// The output matches the reference image within the tolerance
+    Matched(ImageComparison),
+    /// Updating was requested with [`UPDATE_GOLDEN_ENV`], so the output was saved as the
+    /// reference image
+    Created(PathBuf),
+    /// The output does not match the reference image. The output and the diff image are saved
+    Mismatch {
+        /// The details of the comparison
+        comparison: ImageComparison,
+        /// Where the output was saved
+        actual_path: PathBuf,
+        /// Where the diff image was saved
+        diff_path: PathBuf,
+    },
+}
+
+/// Renders scenes in headless mode and compares them against reference images, to catch
+/// rendering regressions on machines without a GPU.
+///
+/// The engine runs on the software adapter by default, such as llvmpipe or lavapipe, so the
+/// output is the same across machines.
+/// ```no_run
+/// # use blue_engine_core::{ObjectSettings, golden::GoldenTest, primitive_shapes::triangle};
+/// GoldenTest::new("tests/golden").assert_matches("triangle", |engine| {
+///     triangle("triangle", ObjectSettings::default(), &mut engine.renderer, &mut engine.objects)?;
+///     Ok(())
+/// });
+/// ```
+#[derive(Debug, Clone)]
+pub struct GoldenTest {
+    /// The directory of the reference images
+    pub reference_dir: PathBuf,
+    /// The directory the outputs and diff images of failed checks are saved to
+    pub output_dir: PathBuf,
+    /// The settings of the engine that renders the scene. It always runs headless,
+    /// with RGBA8 output
+    pub settings: EngineSettings,
+    /// The amount of frames to render before the output is compared
+    pub frames: u64,
+    /// The perceptual difference between 0 and 1 above which a pixel counts as differing,
+    /// see [`perceptual_difference`]
+    pub tolerance: f32,
+    /// The fraction of the pixels between 0 and 1 that can differ for the check to pass
+    pub max_differing_fraction: f32,
+}
+
+impl GoldenTest {
+    /// Creates a harness with the reference images in the given directory. Failed outputs
+    /// are saved to the `failures` directory within it.
+    pub fn new(reference_dir: impl Into<PathBuf>) -> Self {
+        let reference_dir = reference_dir.into();
+        Self {
+            output_dir: reference_dir.join("failures"),
+            reference_dir,
+            settings: EngineSettings {
+                width: 256,
+                height: 256,
+                force_fallback_adapter: true,
+                ..Default::default()
+            },
+            frames: 1,
+            tolerance: 0.01,
+            max_differing_fraction: 0.001,
+        }
+    }
+
+    /// Sets the size of the output
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.settings.width = width;
+        self.settings.height = height;
+        self
+    }
+
+    /// Sets the pixel tolerance and the fraction of the pixels that can differ
+    pub fn with_tolerance(mut self, tolerance: f32, max_differing_fraction: f32) -> Self {
+        self.tolerance = tolerance;
+        self.max_differing_fraction = max_differing_fraction;
+        self
+    }
+
+    /// Boots the engine, lets the scene function set it up, and returns the last frame
+    pub fn render(
+        &self,
+        scene: impl FnOnce(&mut Engine) -> Result<(), crate::error::Error>,
+    ) -> Result<image::RgbaImage, crate::error::Error> {
+        let mut settings = self.settings.clone();
+        settings.run_mode = crate::RunMode::Headless;
+        settings.headless_output = crate::utils::headless_output::HeadlessOutputFormat::Rgba8;
+
+        let mut engine = Engine::new_config(settings)?;
+        scene(&mut engine)?;
+        engine.run_frames(self.frames.max(1), |_| {})?;
+
+        image::RgbaImage::from_raw(
+            engine.renderer.config.width,
+            engine.renderer.config.height,
+            std::mem::take(&mut engine.renderer.headless_texture_data),
+        )
+        .ok_or_else(|| crate::error::Error::Custom("The frame was not rendered".to_string()))
+    }
+
+    /// Renders the scene and compares it against the reference image of the given name.
+    /// A missing reference image is an error, unless [`UPDATE_GOLDEN_ENV`] is set to `1`
+    pub fn check(
+        &self,
+        name: impl AsRef<str>,
+        scene: impl FnOnce(&mut Engine) -> Result<(), crate::error::Error>,
+    ) -> Result<GoldenResult, crate::error::Error> {
+        let name = name.as_ref();
+        let actual = self.render(scene)?;
+        let reference_path = self.reference_dir.join(format!("{name}.png"));
+
+        if update_requested() {
+            std::fs::create_dir_all(&self.reference_dir)?;
+            save_image(&actual, &reference_path)?;
+            return Ok(GoldenResult::Created(reference_path));
+        }
+        if !reference_path.exists() {
+            return Err(crate::error::Error::Custom(format!(
+                "The reference image {} does not exist, set {UPDATE_GOLDEN_ENV}=1 to create it",
+                reference_path.display()
+            )));
+        }
+
+        let reference = image::open(&reference_path)?.to_rgba8();
+        let comparison = compare_images(&reference, &actual, self.tolerance)?;
+        if comparison.differing_fraction() <= self.max_differing_fraction {
+            return Ok(GoldenResult::Matched(comparison));
+        }
+
+        std::fs::create_dir_all(&self.output_dir)?;
+        let actual_path = self.output_dir.join(format!("{name}.actual.png"));
+        let diff_path = self.output_dir.join(format!("{name}.diff.png"));
+        save_image(&actual, &actual_path)?;
+        save_image(&comparison.diff_image, &diff_path)?;
+
+        Ok(GoldenResult::Mismatch {
+            comparison,
+            actual_path,
+            diff_path,
+        })
+    }
+
+    /// Like [`GoldenTest::check`], but panics if the output does not match, for use in tests.
+    /// Updating the reference image panics as well, so the next run checks against it
+    pub fn assert_matches(
+        &self,
+        name: impl AsRef<str>,
+        scene: impl FnOnce(&mut Engine) -> Result<(), crate::error::Error>,
+    ) {
+        let name = name.as_ref();
+        match self.check(name, scene) {
+            Ok(GoldenResult::Matched(_)) => {}
+            Ok(GoldenResult::Created(path)) => panic!(
+                "The golden image \"{name}\" was saved to {}, run again without {UPDATE_GOLDEN_ENV} to check it",
+                path.display()
+            ),
+            Ok(GoldenResult::Mismatch {
+                comparison,
+                diff_path,
+                ..
+            }) => panic!(
+                "The golden image \"{name}\" does not match: {} of {} pixels differ, see {}",
+                comparison.differing_pixels,
+                comparison.total_pixels,
+                diff_path.display()
+            ),
+            Err(error) => panic!("The golden image \"{name}\" could not be checked: {error}"),
+        }
+    }
+}
+
+/// Returns true if the reference images should be replaced, see [`UPDATE_GOLDEN_ENV`]
+fn update_requested() -> bool {
+    std::env::var(UPDATE_GOLDEN_ENV).is_ok_and(|i| i == "1")
+}
+
+fn save_image(image: &image::RgbaImage, path: &std::path::Path) -> Result<(), crate::error::Error> {
+    image
+        .save_with_format(path, image::ImageFormat::Png)
+        .map_err(|error| crate::error::Error::Custom(format!("Failed to save the image: {error}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn perceptual_differences() {
+        let pixel = |r, g, b, a| image::Rgba([r, g, b, a]);
+        let black = pixel(0, 0, 0, 255);
+        let white = pixel(255, 255, 255, 255);
+
+        assert_eq!(perceptual_difference(&black, &black), 0.0);
+        assert!(perceptual_difference(&black, &white) > 0.9);
+        assert!(perceptual_difference(&black, &white) <= 1.0);
+        // the eye is less sensitive to blue than to green
+        assert!(
+            perceptual_difference(&black, &pixel(0, 0, 40, 255))
+                < perceptual_difference(&black, &pixel(0, 40, 0, 255))
+        );
+        // transparent pixels are seen over white
+        assert!(perceptual_difference(&white, &pixel(0, 0, 0, 0)) < 1e-6);
+    }
+
+    #[test]
+    fn compare_with_tolerance() {
+        let reference = image::RgbaImage::from_pixel(4, 4, image::Rgba([100, 100, 100, 255]));
+        let mut actual = reference.clone();
+        actual.put_pixel(0, 0, image::Rgba([102, 100, 100, 255]));
+        actual.put_pixel(1, 0, image::Rgba([200, 100, 100, 255]));
+
+        let comparison = compare_images(&reference, &actual, 0.01).unwrap();
+        assert_eq!(comparison.differing_pixels, 1);
+        assert_eq!(comparison.total_pixels, 16);
+        assert_eq!(
+            comparison.diff_image.get_pixel(1, 0),
+            &image::Rgba([255, 0, 0, 255])
+        );
+        assert_ne!(
+            comparison.diff_image.get_pixel(0, 0),
+            &image::Rgba([255, 0, 0, 255])
+        );
+
+        let smaller = image::RgbaImage::new(2, 2);
+        assert!(compare_images(&reference, &smaller, 0.01).is_err());
+    }
+
+    fn golden() -> GoldenTest {
+        GoldenTest::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden")).with_size(64, 64)
+    }
+
+    #[test]
+    fn triangle() {
+        golden().assert_matches("triangle", |engine| {
+            engine.renderer.clear_color = wgpu::Color::BLACK;
+            crate::primitive_shapes::triangle(
+                "triangle",
+                crate::ObjectSettings::default(),
+                &mut engine.renderer,
+                &mut engine.objects,
+            )?;
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn missing_reference() {
+        if update_requested() {
+            return;
+        }
+        let result = golden().check("missing", |_| Ok(()));
+        assert!(result.is_err());
+        assert!(!golden().reference_dir.join("missing.png").exists());
+    }
+}
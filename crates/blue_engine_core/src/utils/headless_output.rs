@@ -0,0 +1,315 @@
+/*
+ * Blue Engine by Elham Aryanpur
+ *
+ * The license is same as the one on the root.
+*/
+
+/// The data that is copied to `Renderer::headless_texture_data` every headless frame
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum HeadlessOutputFormat {
+    /// 8 bits per channel color in sRGB, 4 bytes per pixel
+    #[default]
+    Rgba8,
+    /// 16 bit float per channel linear color, 8 bytes per pixel. Useful for HDR output
+    Rgba16Float,
+    /// The depth buffer as 32 bit floats between 0 and 1, 4 bytes per pixel
+    Depth,
+}
+
+impl HeadlessOutputFormat {
+    /// Returns the size of a single pixel of the output in bytes
+    pub fn bytes_per_pixel(&self) -> u32 {
+        match self {
+            Self::Rgba8 | Self::Depth => 4,
+            Self::Rgba16Float => 8,
+        }
+    }
+
+    /// Returns the format of the color target the scene is rendered to
+    pub fn color_format(&self) -> wgpu::TextureFormat {
+        match self {
+            Self::Rgba8 | Self::Depth => wgpu::TextureFormat::Rgba8UnormSrgb,
+            Self::Rgba16Float => wgpu::TextureFormat::Rgba16Float,
+        }
+    }
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    let value = value.clamp(0.0, 1.0);
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Converts the output to 8 bit sRGB color, tightly packed. Depth is shown in grayscale
+pub fn to_rgba8(data: &[u8], format: HeadlessOutputFormat) -> Vec<u8> {
+    match format {
+        HeadlessOutputFormat::Rgba8 => data.to_vec(),
+        HeadlessOutputFormat::Rgba16Float => data
+            .chunks_exact(8)
+            .flat_map(|pixel| {
+                let channel = |index: usize| {
+                    half::f16::from_le_bytes([pixel[index * 2], pixel[index * 2 + 1]]).to_f32()
+                };
+                [
+                    (linear_to_srgb(channel(0)) * 255.0).round() as u8,
+                    (linear_to_srgb(channel(1)) * 255.0).round() as u8,
+                    (linear_to_srgb(channel(2)) * 255.0).round() as u8,
+                    (channel(3).clamp(0.0, 1.0) * 255.0).round() as u8,
+                ]
+            })
+            .collect(),
+        HeadlessOutputFormat::Depth => data
+            .chunks_exact(4)
+            .flat_map(|pixel| {
+                let depth = f32::from_le_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+                let value = (depth.clamp(0.0, 1.0) * 255.0).round() as u8;
+                [value, value, value, 255]
+            })
+            .collect(),
+    }
+}
+
+/// Converts the output to 32 bit float linear color, tightly packed. Depth is stored in
+/// the color channels
+pub fn to_rgba32f(data: &[u8], format: HeadlessOutputFormat) -> Vec<f32> {
+    match format {
+        HeadlessOutputFormat::Rgba8 => data
+            .chunks_exact(4)
+            .flat_map(|pixel| {
+                [
+                    srgb_to_linear(pixel[0] as f32 / 255.0),
+                    srgb_to_linear(pixel[1] as f32 / 255.0),
+                    srgb_to_linear(pixel[2] as f32 / 255.0),
+                    pixel[3] as f32 / 255.0,
+                ]
+            })
+            .collect(),
+        HeadlessOutputFormat::Rgba16Float => data
+            .chunks_exact(2)
+            .map(|channel| half::f16::from_le_bytes([channel[0], channel[1]]).to_f32())
+            .collect(),
+        HeadlessOutputFormat::Depth => data
+            .chunks_exact(4)
+            .flat_map(|pixel| {
+                let depth = f32::from_le_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+                [depth, depth, depth, 1.0]
+            })
+            .collect(),
+    }
+}
+
+/// Writes the output as a PNG file
+pub fn write_png(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    format: HeadlessOutputFormat,
+    path: impl AsRef<std::path::Path>,
+) -> Result<(), crate::error::Error> {
+    image::save_buffer_with_format(
+        path,
+        &to_rgba8(data, format),
+        width,
+        height,
+        image::ExtendedColorType::Rgba8,
+        image::ImageFormat::Png,
+    )
+    .map_err(|error| crate::error::Error::Custom(format!("Failed to save the image: {error}")))
+}
+
+/// Writes the output as an OpenEXR file, keeping the full float precision
+pub fn write_exr(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    format: HeadlessOutputFormat,
+    path: impl AsRef<std::path::Path>,
+) -> Result<(), crate::error::Error> {
+    let image = image::Rgba32FImage::from_raw(width, height, to_rgba32f(data, format)).ok_or_else(
+        || crate::error::Error::Custom("The output does not match its size".to_string()),
+    )?;
+    image::DynamicImage::ImageRgba32F(image)
+        .save_with_format(path, image::ImageFormat::OpenExr)
+        .map_err(|error| crate::error::Error::Custom(format!("Failed to save the image: {error}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgba8_conversions() {
+        let data = [0, 128, 255, 255];
+        assert_eq!(to_rgba8(&data, HeadlessOutputFormat::Rgba8), data);
+
+        let linear = to_rgba32f(&data, HeadlessOutputFormat::Rgba8);
+        assert_eq!(linear[0], 0.0);
+        assert!((linear[1] - 0.2158).abs() < 1e-3);
+        assert_eq!(&linear[2..], &[1.0, 1.0]);
+    }
+
+    #[test]
+    fn rgba16_float_conversions() {
+        let data = [0.0f32, 0.2158, 1.0, 0.5]
+            .iter()
+            .flat_map(|channel| half::f16::from_f32(*channel).to_le_bytes())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            to_rgba8(&data, HeadlessOutputFormat::Rgba16Float),
+            [0, 128, 255, 128]
+        );
+        let linear = to_rgba32f(&data, HeadlessOutputFormat::Rgba16Float);
+        assert_eq!(linear[0], 0.0);
+        assert!((linear[1] - 0.2158).abs() < 1e-3);
+        assert_eq!(&linear[2..], &[1.0, 0.5]);
+    }
+
+    #[test]
+    fn depth_conversions() {
+        let data = [0.0f32, 0.5, 1.0]
+            .iter()
+            .flat_map(|depth| depth.to_le_bytes())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            to_rgba8(&data, HeadlessOutputFormat::Depth),
+            [0, 0, 0, 255, 128, 128, 128, 255, 255, 255, 255, 255]
+        );
+        assert_eq!(
+            &to_rgba32f(&data, HeadlessOutputFormat::Depth)[4..],
+            &[0.5, 0.5, 0.5, 1.0, 1.0, 1.0, 1.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn write_exr_round_trip() {
+        let path = std::env::temp_dir().join(format!("headless_output_{}.exr", std::process::id()));
+        let data = [0.25f32, 0.5, 1.0, 1.0, 2.0, 0.0, 0.0, 1.0]
+            .iter()
+            .flat_map(|channel| half::f16::from_f32(*channel).to_le_bytes())
+            .collect::<Vec<_>>();
+
+        write_exr(&data, 2, 1, HeadlessOutputFormat::Rgba16Float, &path).unwrap();
+        let image = image::open(&path).unwrap().into_rgba32f();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(image.dimensions(), (2, 1));
+        // values above 1 are kept
+        assert_eq!(
+            image.as_raw().as_slice(),
+            &[0.25, 0.5, 1.0, 1.0, 2.0, 0.0, 0.0, 1.0]
+        );
+
+        assert!(write_exr(&data, 3, 1, HeadlessOutputFormat::Rgba16Float, &path).is_err());
+    }
+
+    /// A width whose rows need padding
+    #[cfg(any(feature = "window", feature = "headless"))]
+    fn settings(format: HeadlessOutputFormat) -> crate::EngineSettings {
+        crate::EngineSettings {
+            width: 1000,
+            height: 16,
+            run_mode: crate::RunMode::Headless,
+            headless_output: format,
+            backends: crate::Backends::all(),
+            ..Default::default()
+        }
+    }
+
+    /// Renders a triangle on a gray background
+    #[cfg(any(feature = "window", feature = "headless"))]
+    fn render(format: HeadlessOutputFormat) -> crate::Engine {
+        let mut engine = crate::Engine::new_config(settings(format)).expect("a headless engine");
+        engine.renderer.set_clear_color(0.5, 0.5, 0.5, 1.0);
+        crate::primitive_shapes::triangle(
+            "triangle",
+            crate::ObjectSettings::default(),
+            &mut engine.renderer,
+            &mut engine.objects,
+        )
+        .unwrap();
+        engine.step().unwrap();
+
+        assert_eq!(engine.renderer.headless_output_format(), format);
+        assert_eq!(
+            engine.renderer.headless_texture_data.len(),
+            (1000 * 16 * format.bytes_per_pixel()) as usize
+        );
+        engine
+    }
+
+    /// Returns the pixel at the position as linear color
+    #[cfg(any(feature = "window", feature = "headless"))]
+    fn pixel(engine: &crate::Engine, x: usize, y: usize) -> [f32; 4] {
+        let rgba = to_rgba32f(
+            &engine.renderer.headless_texture_data,
+            engine.renderer.headless_output_format(),
+        );
+        let start = (y * 1000 + x) * 4;
+        [
+            rgba[start],
+            rgba[start + 1],
+            rgba[start + 2],
+            rgba[start + 3],
+        ]
+    }
+
+    #[test]
+    #[cfg(any(feature = "window", feature = "headless"))]
+    fn render_rgba8() {
+        let engine = render(HeadlessOutputFormat::Rgba8);
+        // the background is the clear color in sRGB, in every row
+        assert_eq!(
+            &engine.renderer.headless_texture_data[..4],
+            &[188, 188, 188, 255]
+        );
+        let last = engine.renderer.headless_texture_data.len() - 4;
+        assert_eq!(
+            &engine.renderer.headless_texture_data[last..],
+            &[188, 188, 188, 255]
+        );
+        assert_ne!(pixel(&engine, 500, 8), pixel(&engine, 0, 0));
+    }
+
+    #[test]
+    #[cfg(any(feature = "window", feature = "headless"))]
+    fn render_rgba16_float() {
+        let engine = render(HeadlessOutputFormat::Rgba16Float);
+        assert_eq!(pixel(&engine, 0, 0), [0.5, 0.5, 0.5, 1.0]);
+        assert_eq!(pixel(&engine, 999, 15), [0.5, 0.5, 0.5, 1.0]);
+        assert_ne!(pixel(&engine, 500, 8), pixel(&engine, 0, 0));
+    }
+
+    #[test]
+    #[cfg(any(feature = "window", feature = "headless"))]
+    fn render_depth() {
+        let supported = crate::utils::headless_test_engine()
+            .renderer
+            .adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(wgpu::DownlevelFlags::DEPTH_TEXTURE_AND_BUFFER_COPIES);
+        if !supported {
+            // such as OpenGL ES, which can not copy the depth buffer
+            assert!(crate::Engine::new_config(settings(HeadlessOutputFormat::Depth)).is_err());
+            return;
+        }
+
+        let engine = render(HeadlessOutputFormat::Depth);
+        // the depth is cleared to the far plane, and the triangle is closer
+        assert_eq!(pixel(&engine, 0, 0), [1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(pixel(&engine, 999, 15), [1.0, 1.0, 1.0, 1.0]);
+        assert!(pixel(&engine, 500, 8)[0] < 1.0);
+    }
+}
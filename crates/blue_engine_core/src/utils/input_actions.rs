@@ -0,0 +1,532 @@
+/*
+ * Blue Engine by Elham Aryanpur
+ *
+ * The license is same as the one on the root.
+*/
+
+use crate::utils::winit_input_helper::WinitInputHelper;
+use std::collections::HashMap;
+use winit::event::MouseButton;
+use winit::keyboard::KeyCode;
+
+/// Modifier keys that must be held for a binding to trigger.
+///
+/// Modifiers are not exclusive, meaning a binding with only `shift` set will
+/// still trigger if control is held as well.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct InputModifiers {
+    /// Any of the shift keys
+    pub shift: bool,
+    /// Any of the control keys
+    pub control: bool,
+    /// Any of the alt keys
+    pub alt: bool,
+}
+impl InputModifiers {
+    /// No modifiers required
+    pub const NONE: Self = Self {
+        shift: false,
+        control: false,
+        alt: false,
+    };
+
+    /// Checks if the required modifiers are currently held
+    pub fn held(&self, input: &WinitInputHelper) -> bool {
+        (!self.shift || input.held_shift())
+            && (!self.control || input.held_control())
+            && (!self.alt || input.held_alt())
+    }
+}
+
+/// A physical input that can trigger a named action
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type")]
+pub enum ActionBinding {
+    /// A keyboard key, using physical key positions
+    Key {
+        /// The key to listen to
+        key: KeyCode,
+        /// Modifiers that must be held as well
+        #[serde(default)]
+        modifiers: InputModifiers,
+    },
+    /// A mouse button
+    Mouse {
+        /// The button to listen to
+        button: MouseButton,
+        /// Modifiers that must be held as well
+        #[serde(default)]
+        modifiers: InputModifiers,
+    },
+    /// Triggers while the mouse wheel is scrolled up
+    ScrollUp,
+    /// Triggers while the mouse wheel is scrolled down
+    ScrollDown,
+}
+impl ActionBinding {
+    /// Checks if the binding is active during the current step
+    pub fn held(&self, input: &WinitInputHelper) -> bool {
+        match self {
+            Self::Key { key, modifiers } => input.key_held(*key) && modifiers.held(input),
+            Self::Mouse { button, modifiers } => input.mouse_held(*button) && modifiers.held(input),
+            Self::ScrollUp => input.scroll_diff().1 > 0.0,
+            Self::ScrollDown => input.scroll_diff().1 < 0.0,
+        }
+    }
+
+    /// Checks if the binding went from "not pressed" to "pressed" during the current step
+    pub fn pressed(&self, input: &WinitInputHelper) -> bool {
+        match self {
+            Self::Key { key, modifiers } => input.key_pressed(*key) && modifiers.held(input),
+            Self::Mouse { button, modifiers } => {
+                input.mouse_pressed(*button) && modifiers.held(input)
+            }
+            Self::ScrollUp | Self::ScrollDown => self.held(input),
+        }
+    }
+}
+
+/// A physical input that drives a named axis, with values usually between -1 and +1
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type")]
+pub enum AxisBinding {
+    /// A pair of keys, where `negative` gives -1 and `positive` gives +1
+    Keys {
+        /// The key that moves the axis towards -1
+        negative: KeyCode,
+        /// The key that moves the axis towards +1
+        positive: KeyCode,
+        /// Modifiers that must be held as well
+        #[serde(default)]
+        modifiers: InputModifiers,
+    },
+    /// Horizontal mouse scroll, multiplied by the scale
+    ScrollX {
+        /// Multiplier of the raw value
+        scale: f32,
+    },
+    /// Vertical mouse scroll, multiplied by the scale
+    ScrollY {
+        /// Multiplier of the raw value
+        scale: f32,
+    },
+    /// Horizontal mouse movement, multiplied by the scale. Uses raw device motion
+    MouseDeltaX {
+        /// Multiplier of the raw value
+        scale: f32,
+    },
+    /// Vertical mouse movement, multiplied by the scale. Uses raw device motion
+    MouseDeltaY {
+        /// Multiplier of the raw value
+        scale: f32,
+    },
+}
+impl AxisBinding {
+    /// Returns the value of the binding during the current step
+    pub fn value(&self, input: &WinitInputHelper) -> f32 {
+        match self {
+            Self::Keys {
+                negative,
+                positive,
+                modifiers,
+            } => {
+                if !modifiers.held(input) {
+                    return 0.0;
+                }
+                let mut value = 0.0;
+                if input.key_held(*negative) {
+                    value -= 1.0;
+                }
+                if input.key_held(*positive) {
+                    value += 1.0;
+                }
+                value
+            }
+            Self::ScrollX { scale } => input.scroll_diff().0 * scale,
+            Self::ScrollY { scale } => input.scroll_diff().1 * scale,
+            Self::MouseDeltaX { scale } => input.mouse_diff().0 * scale,
+            Self::MouseDeltaY { scale } => input.mouse_diff().1 * scale,
+        }
+    }
+}
+
+/// The bindings of all actions and axes. This is the part of [`InputActions`] that is
+/// saved to and loaded from config files.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct InputBindings {
+    /// Named actions and the inputs that trigger them
+    pub actions: HashMap<String, Vec<ActionBinding>>,
+    /// Named axes and the inputs that drive them
+    pub axes: HashMap<String, Vec<AxisBinding>>,
+}
+
+/// The state of a single action during the current step
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ActionState {
+    /// The action went from "not pressed" to "pressed"
+    pub pressed: bool,
+    /// The action is currently pressed
+    pub held: bool,
+    /// The action went from "pressed" to "not pressed"
+    pub released: bool,
+}
+
+/// Maps named actions and axes to keys, mouse buttons, scroll and mouse movement.
+///
+/// The engine updates the state every frame before the update loop runs, so game code
+/// can query `engine.actions.pressed("jump")` or `engine.actions.axis("move_x")`
+/// instead of checking raw keys. The bindings can be changed at any time, and can be
+/// saved to and loaded from a TOML file.
+#[derive(Debug, Clone, Default)]
+pub struct InputActions {
+    /// The bindings of all actions and axes
+    pub bindings: InputBindings,
+    action_state: HashMap<String, ActionState>,
+    axis_state: HashMap<String, f32>,
+}
+
+impl InputActions {
+    /// Creates an empty action map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an action map from existing bindings
+    pub fn from_bindings(bindings: InputBindings) -> Self {
+        Self {
+            bindings,
+            ..Default::default()
+        }
+    }
+
+    /// Adds a binding to an action, creating the action if it does not exist
+    pub fn bind_action(&mut self, name: impl AsRef<str>, binding: ActionBinding) {
+        self.bindings
+            .actions
+            .entry(name.as_ref().to_string())
+            .or_default()
+            .push(binding);
+    }
+
+    /// Replaces all bindings of an action
+    pub fn rebind_action(&mut self, name: impl AsRef<str>, bindings: Vec<ActionBinding>) {
+        self.bindings
+            .actions
+            .insert(name.as_ref().to_string(), bindings);
+    }
+
+    /// Removes an action and all of its bindings
+    pub fn remove_action(&mut self, name: impl AsRef<str>) {
+        self.bindings.actions.remove(name.as_ref());
+        self.action_state.remove(name.as_ref());
+    }
+
+    /// Adds a binding to an axis, creating the axis if it does not exist
+    pub fn bind_axis(&mut self, name: impl AsRef<str>, binding: AxisBinding) {
+        self.bindings
+            .axes
+            .entry(name.as_ref().to_string())
+            .or_default()
+            .push(binding);
+    }
+
+    /// Replaces all bindings of an axis
+    pub fn rebind_axis(&mut self, name: impl AsRef<str>, bindings: Vec<AxisBinding>) {
+        self.bindings
+            .axes
+            .insert(name.as_ref().to_string(), bindings);
+    }
+
+    /// Removes an axis and all of its bindings
+    pub fn remove_axis(&mut self, name: impl AsRef<str>) {
+        self.bindings.axes.remove(name.as_ref());
+        self.axis_state.remove(name.as_ref());
+    }
+
+    /// Updates the state of all actions and axes from the input helper.
+    ///
+    /// This is called by the engine every frame and should not be needed
+    /// unless you are driving the input helper yourself.
+    pub fn update(&mut self, input: &WinitInputHelper) {
+        self.action_state
+            .retain(|name, _| self.bindings.actions.contains_key(name));
+        for (name, bindings) in self.bindings.actions.iter() {
+            let held = bindings.iter().any(|i| i.held(input));
+            let pressed_now = bindings.iter().any(|i| i.pressed(input));
+
+            let state = self.action_state.entry(name.clone()).or_default();
+            let was_held = state.held;
+            *state = ActionState {
+                pressed: pressed_now || (held && !was_held),
+                held: held || pressed_now,
+                released: !held && was_held,
+            };
+        }
+
+        self.axis_state.clear();
+        for (name, bindings) in self.bindings.axes.iter() {
+            let value = bindings.iter().map(|i| i.value(input)).sum::<f32>();
+            self.axis_state.insert(name.clone(), value);
+        }
+    }
+
+    /// Returns the state of the action during the current step
+    pub fn state(&self, name: impl AsRef<str>) -> ActionState {
+        self.action_state
+            .get(name.as_ref())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns true when the action goes from "not pressed" to "pressed".
+    pub fn pressed(&self, name: impl AsRef<str>) -> bool {
+        self.state(name).pressed
+    }
+
+    /// Returns true while the action remains "pressed".
+    pub fn held(&self, name: impl AsRef<str>) -> bool {
+        self.state(name).held
+    }
+
+    /// Returns true when the action goes from "pressed" to "not pressed".
+    pub fn released(&self, name: impl AsRef<str>) -> bool {
+        self.state(name).released
+    }
+
+    /// Returns the value of the axis, which is the sum of all of its bindings.
+    /// Returns `0.0` if the axis does not exist.
+    pub fn axis(&self, name: impl AsRef<str>) -> f32 {
+        self.axis_state.get(name.as_ref()).copied().unwrap_or(0.0)
+    }
+
+    /// Serializes the bindings to a TOML string
+    pub fn to_toml(&self) -> Result<String, crate::error::Error> {
+        Ok(toml::to_string_pretty(&self.bindings)?)
+    }
+
+    /// Replaces the bindings with ones parsed from a TOML string
+    pub fn load_toml(&mut self, source: impl AsRef<str>) -> Result<(), crate::error::Error> {
+        self.bindings = toml::from_str(source.as_ref())?;
+        self.action_state.clear();
+        self.axis_state.clear();
+        Ok(())
+    }
+
+    /// Saves the bindings to a TOML file
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), crate::error::Error> {
+        std::fs::write(path, self.to_toml()?)?;
+        Ok(())
+    }
+
+    /// Replaces the bindings with ones loaded from a TOML file
+    pub fn load(&mut self, path: impl AsRef<std::path::Path>) -> Result<(), crate::error::Error> {
+        let source = std::fs::read_to_string(path)?;
+        self.load_toml(source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::input_event::InputEvent;
+    use crate::utils::synthetic_input::keyboard_event;
+    use winit::event::{ElementState, MouseScrollDelta};
+
+    /// Runs one input step with the events, and updates the actions from it
+    fn step(input: &mut WinitInputHelper, actions: &mut InputActions, events: &[InputEvent]) {
+        input.step();
+        for event in events {
+            input.process_input_event(event);
+        }
+        input.end_step();
+        actions.update(input);
+    }
+
+    fn key(key: KeyCode) -> ActionBinding {
+        ActionBinding::Key {
+            key,
+            modifiers: InputModifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn actions() {
+        let mut input = WinitInputHelper::new();
+        let mut actions = InputActions::new();
+        actions.bind_action("jump", key(KeyCode::Space));
+        actions.bind_action(
+            "jump",
+            ActionBinding::Mouse {
+                button: MouseButton::Right,
+                modifiers: InputModifiers::NONE,
+            },
+        );
+        actions.bind_action(
+            "sprint",
+            ActionBinding::Key {
+                key: KeyCode::KeyW,
+                modifiers: InputModifiers {
+                    shift: true,
+                    ..Default::default()
+                },
+            },
+        );
+
+        step(
+            &mut input,
+            &mut actions,
+            &[
+                keyboard_event(KeyCode::Space, ElementState::Pressed),
+                keyboard_event(KeyCode::KeyW, ElementState::Pressed),
+            ],
+        );
+        assert_eq!(
+            actions.state("jump"),
+            ActionState {
+                pressed: true,
+                held: true,
+                released: false,
+            }
+        );
+        // the modifier is not held
+        assert!(!actions.held("sprint"));
+
+        step(
+            &mut input,
+            &mut actions,
+            &[keyboard_event(KeyCode::ShiftLeft, ElementState::Pressed)],
+        );
+        assert!(!actions.pressed("jump"));
+        assert!(actions.held("jump"));
+        assert!(actions.pressed("sprint"));
+
+        // the other binding keeps the action held
+        step(
+            &mut input,
+            &mut actions,
+            &[
+                InputEvent::MouseInput {
+                    button: MouseButton::Right,
+                    state: ElementState::Pressed,
+                },
+                keyboard_event(KeyCode::Space, ElementState::Released),
+            ],
+        );
+        assert!(actions.held("jump"));
+        assert!(!actions.released("jump"));
+
+        step(
+            &mut input,
+            &mut actions,
+            &[InputEvent::MouseInput {
+                button: MouseButton::Right,
+                state: ElementState::Released,
+            }],
+        );
+        assert!(actions.released("jump"));
+        assert!(!actions.held("jump"));
+
+        actions.remove_action("jump");
+        assert_eq!(actions.state("jump"), ActionState::default());
+        assert_eq!(actions.state("unknown"), ActionState::default());
+    }
+
+    #[test]
+    fn axes() {
+        let mut input = WinitInputHelper::new();
+        let mut actions = InputActions::new();
+        actions.bind_axis(
+            "move_x",
+            AxisBinding::Keys {
+                negative: KeyCode::KeyA,
+                positive: KeyCode::KeyD,
+                modifiers: InputModifiers::NONE,
+            },
+        );
+        actions.bind_axis(
+            "move_x",
+            AxisBinding::Keys {
+                negative: KeyCode::ArrowLeft,
+                positive: KeyCode::ArrowRight,
+                modifiers: InputModifiers::NONE,
+            },
+        );
+        actions.bind_axis("zoom", AxisBinding::ScrollY { scale: 0.5 });
+        actions.bind_axis("look_x", AxisBinding::MouseDeltaX { scale: 2.0 });
+
+        step(
+            &mut input,
+            &mut actions,
+            &[
+                keyboard_event(KeyCode::KeyD, ElementState::Pressed),
+                keyboard_event(KeyCode::ArrowRight, ElementState::Pressed),
+                InputEvent::MouseWheel {
+                    delta: MouseScrollDelta::LineDelta(0.0, 3.0),
+                },
+                InputEvent::MouseMotion { x: 4.0, y: 1.0 },
+            ],
+        );
+        // the bindings of an axis add up
+        assert_eq!(actions.axis("move_x"), 2.0);
+        assert_eq!(actions.axis("zoom"), 1.5);
+        assert_eq!(actions.axis("look_x"), 8.0);
+
+        step(
+            &mut input,
+            &mut actions,
+            &[keyboard_event(KeyCode::KeyA, ElementState::Pressed)],
+        );
+        // opposite keys cancel out
+        assert_eq!(actions.axis("move_x"), 1.0);
+        assert_eq!(actions.axis("zoom"), 0.0);
+        assert_eq!(actions.axis("look_x"), 0.0);
+
+        actions.rebind_axis("move_x", Vec::new());
+        step(&mut input, &mut actions, &[]);
+        assert_eq!(actions.axis("move_x"), 0.0);
+        assert_eq!(actions.axis("unknown"), 0.0);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut actions = InputActions::new();
+        actions.bind_action("jump", key(KeyCode::Space));
+        actions.bind_action(
+            "fire",
+            ActionBinding::Mouse {
+                button: MouseButton::Left,
+                modifiers: InputModifiers {
+                    control: true,
+                    ..Default::default()
+                },
+            },
+        );
+        actions.bind_action("zoom_in", ActionBinding::ScrollUp);
+        actions.bind_axis(
+            "move_y",
+            AxisBinding::Keys {
+                negative: KeyCode::KeyS,
+                positive: KeyCode::KeyW,
+                modifiers: InputModifiers::NONE,
+            },
+        );
+        actions.bind_axis("look_y", AxisBinding::MouseDeltaY { scale: -0.1 });
+
+        let toml = actions.to_toml().unwrap();
+        let mut loaded = InputActions::new();
+        loaded.bind_action("old", key(KeyCode::Escape));
+        loaded.load_toml(&toml).unwrap();
+        assert_eq!(loaded.bindings, actions.bindings);
+
+        let path = std::env::temp_dir().join(format!("input_actions_{}.toml", std::process::id()));
+        actions.save(&path).unwrap();
+        let mut loaded = InputActions::new();
+        loaded.load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.bindings, actions.bindings);
+
+        assert!(loaded.load_toml("actions = 1").is_err());
+    }
+}
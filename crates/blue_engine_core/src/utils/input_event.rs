@@ -0,0 +1,181 @@
+/*
+ * Blue Engine by Elham Aryanpur
+ *
+ * The license is same as the one on the root.
+*/
+
+use std::path::PathBuf;
+use winit::event::{
+    DeviceEvent, ElementState, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent,
+};
+use winit::keyboard::{Key, PhysicalKey};
+
+/// The input events understood by the input helper.
+///
+/// These are a serializable subset of the winit window and device events, so they can be
+/// recorded, replayed and injected without a real window.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type")]
+pub enum InputEvent {
+    /// A key was pressed or released
+    Keyboard {
+        /// The key position on the keyboard
+        physical_key: PhysicalKey,
+        /// The key after the keyboard layout is applied
+        #[serde(with = "logical_key")]
+        logical_key: Key,
+        /// Pressed or released
+        state: ElementState,
+    },
+    /// The cursor moved within the window, in pixels
+    CursorMoved {
+        /// The horizontal position
+        x: f64,
+        /// The vertical position
+        y: f64,
+    },
+    /// A mouse button was pressed or released
+    MouseInput {
+        /// The mouse button
+        button: MouseButton,
+        /// Pressed or released
+        state: ElementState,
+    },
+    /// The mouse wheel was scrolled
+    MouseWheel {
+        /// The amount scrolled
+        delta: MouseScrollDelta,
+    },
+    /// Raw mouse movement, as reported by the device
+    MouseMotion {
+        /// The horizontal movement
+        x: f64,
+        /// The vertical movement
+        y: f64,
+    },
+    /// A finger touched, moved on or left the touch screen
+    Touch {
+        /// Unique identifier of the finger
+        id: u64,
+        /// The phase of the touch
+        phase: TouchPhase,
+        /// The horizontal position in pixels
+        x: f64,
+        /// The vertical position in pixels
+        y: f64,
+        /// Normalized pressure between 0 and 1, if the platform supports it
+        force: Option<f64>,
+    },
+    /// The window gained or lost focus
+    Focused {
+        /// Whether the window is focused
+        focused: bool,
+    },
+    /// The window was resized
+    Resized {
+        /// The new width
+        width: u32,
+        /// The new height
+        height: u32,
+    },
+    /// The scale factor of the window changed
+    ScaleFactorChanged {
+        /// The new scale factor
+        scale_factor: f64,
+    },
+    /// A file was dropped on the window
+    DroppedFile {
+        /// Path of the file
+        path: PathBuf,
+    },
+    /// The OS requested the window to close
+    CloseRequested,
+    /// The window was destroyed
+    Destroyed,
+}
+
+impl InputEvent {
+    /// Converts a window event. Returns `None` for events that do not affect the input
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        Some(match event {
+            WindowEvent::KeyboardInput { event, .. } => Self::Keyboard {
+                physical_key: event.physical_key,
+                logical_key: event.logical_key.clone(),
+                state: event.state,
+            },
+            WindowEvent::CursorMoved { position, .. } => Self::CursorMoved {
+                x: position.x,
+                y: position.y,
+            },
+            WindowEvent::MouseInput { state, button, .. } => Self::MouseInput {
+                button: *button,
+                state: *state,
+            },
+            WindowEvent::MouseWheel { delta, .. } => Self::MouseWheel { delta: *delta },
+            WindowEvent::Touch(touch) => Self::Touch {
+                id: touch.id,
+                phase: touch.phase,
+                x: touch.location.x,
+                y: touch.location.y,
+                force: touch.force.map(|force| force.normalized()),
+            },
+            WindowEvent::Focused(focused) => Self::Focused { focused: *focused },
+            WindowEvent::Resized(size) => Self::Resized {
+                width: size.width,
+                height: size.height,
+            },
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => Self::ScaleFactorChanged {
+                scale_factor: *scale_factor,
+            },
+            WindowEvent::DroppedFile(path) => Self::DroppedFile { path: path.clone() },
+            WindowEvent::CloseRequested => Self::CloseRequested,
+            WindowEvent::Destroyed => Self::Destroyed,
+            _ => return None,
+        })
+    }
+
+    /// Converts a device event. Returns `None` for events that do not affect the input
+    pub fn from_device_event(event: &DeviceEvent) -> Option<Self> {
+        match event {
+            DeviceEvent::MouseMotion { delta } => Some(Self::MouseMotion {
+                x: delta.0,
+                y: delta.1,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Serializes [`Key`], storing the dead key without a character by name, as TOML has no
+/// value for the `None` within it
+mod logical_key {
+    use serde::{Deserialize, Serialize};
+    use winit::keyboard::Key;
+
+    #[derive(Serialize, Deserialize)]
+    enum LogicalKey {
+        DeadWithoutCharacter,
+        #[serde(untagged)]
+        Key(Key),
+    }
+
+    pub(crate) fn serialize<S: serde::Serializer>(
+        key: &Key,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match key {
+            Key::Dead(None) => LogicalKey::DeadWithoutCharacter,
+            key => LogicalKey::Key(key.clone()),
+        }
+        .serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Key, D::Error> {
+        Ok(match LogicalKey::deserialize(deserializer)? {
+            LogicalKey::DeadWithoutCharacter => Key::Dead(None),
+            LogicalKey::Key(key) => key,
+        })
+    }
+}
@@ -0,0 +1,330 @@
+/*
+ * Blue Engine by Elham Aryanpur
+ *
+ * The license is same as the one on the root.
+*/
+
+use super::input_event::InputEvent;
+use std::time::Duration;
+
+/// A single input event with the frame it happened on
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RecordedEvent {
+    /// The frame the event was received on, counted from the start of the recording
+    pub frame: u64,
+    /// Seconds since the start of the recording
+    pub time: f64,
+    /// The event itself
+    pub event: InputEvent,
+}
+
+/// A recorded stream of input events, which can be saved to and loaded from a TOML file
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct InputRecording {
+    /// The amount of frames the recording lasted
+    pub frames: u64,
+    /// The recorded events, in order
+    pub events: Vec<RecordedEvent>,
+}
+
+impl InputRecording {
+    /// Serializes the recording to a TOML string
+    pub fn to_toml(&self) -> Result<String, crate::error::Error> {
+        Ok(toml::to_string(self)?)
+    }
+
+    /// Parses a recording from a TOML string
+    pub fn from_toml(source: impl AsRef<str>) -> Result<Self, crate::error::Error> {
+        Ok(toml::from_str(source.as_ref())?)
+    }
+
+    /// Saves the recording to a TOML file
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), crate::error::Error> {
+        std::fs::write(path, self.to_toml()?)?;
+        Ok(())
+    }
+
+    /// Loads a recording from a TOML file
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, crate::error::Error> {
+        Self::from_toml(std::fs::read_to_string(path)?)
+    }
+}
+
+/// Records the input events the engine receives
+#[derive(Debug, Clone)]
+pub struct InputRecorder {
+    recording: InputRecording,
+    start: Duration,
+}
+
+impl InputRecorder {
+    /// Starts a new recording at the given time, which the engine takes from
+    /// `Time::real_elapsed`
+    pub fn new(start: Duration) -> Self {
+        Self {
+            recording: InputRecording::default(),
+            start,
+        }
+    }
+
+    /// Adds an event to the current frame, received at the given time of the same clock as
+    /// the start of the recording
+    pub fn record(&mut self, event: InputEvent, now: Duration) {
+        self.recording.events.push(RecordedEvent {
+            frame: self.recording.frames,
+            time: now.saturating_sub(self.start).as_secs_f64(),
+            event,
+        });
+    }
+
+    /// Marks the end of the current frame
+    pub fn next_frame(&mut self) {
+        self.recording.frames += 1;
+    }
+
+    /// Returns the recording so far
+    pub fn recording(&self) -> &InputRecording {
+        &self.recording
+    }
+
+    /// Stops recording and returns the result
+    pub fn finish(self) -> InputRecording {
+        self.recording
+    }
+}
+
+impl Default for InputRecorder {
+    /// Starts a new recording at zero
+    fn default() -> Self {
+        Self::new(Duration::ZERO)
+    }
+}
+
+/// Plays back a recording frame by frame.
+///
+/// The playback is tied to frames and not to wall-clock time, so replaying the same
+/// recording always gives the same input on the same frames.
+#[derive(Debug, Clone)]
+pub struct InputReplay {
+    recording: InputRecording,
+    cursor: usize,
+    frame: u64,
+}
+
+impl InputReplay {
+    /// Starts playing back the recording from its first frame
+    pub fn new(recording: InputRecording) -> Self {
+        Self {
+            recording,
+            cursor: 0,
+            frame: 0,
+        }
+    }
+
+    /// Returns the events of the current frame and moves on to the next frame
+    pub fn next_frame(&mut self) -> &[RecordedEvent] {
+        let start = self.cursor;
+        while self
+            .recording
+            .events
+            .get(self.cursor)
+            .is_some_and(|i| i.frame <= self.frame)
+        {
+            self.cursor += 1;
+        }
+        self.frame += 1;
+
+        &self.recording.events[start..self.cursor]
+    }
+
+    /// Returns the frame that will be played next
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Returns true once all frames of the recording have been played
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.recording.frames && self.cursor >= self.recording.events.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Engine;
+    use crate::utils::touch::{Gesture, TouchPhase};
+    use std::time::Duration;
+    use winit::event::{ElementState, MouseButton, MouseScrollDelta};
+    use winit::keyboard::{Key, KeyCode, NamedKey, NativeKey, NativeKeyCode, PhysicalKey};
+
+    fn manual_engine() -> Engine {
+        let mut engine = crate::utils::headless_test_engine();
+        engine.time = crate::utils::time::Time::manual();
+        engine
+    }
+
+    /// Steps the engine by frames of 16ms, and returns the gestures with their frame
+    fn run(engine: &mut Engine, frames: u64) -> Vec<(u64, Gesture)> {
+        let mut gestures = Vec::new();
+        for frame in 0..frames {
+            engine.time.advance(Duration::from_millis(16));
+            engine.step().unwrap();
+            gestures.extend(engine.simple_input.gestures().iter().map(|i| (frame, *i)));
+        }
+        gestures
+    }
+
+    #[test]
+    fn replay_recognizes_the_same_gestures() {
+        let mut engine = manual_engine();
+        engine.start_input_recording();
+        let input = &mut engine.synthetic_input;
+        input.touch(1, TouchPhase::Started, 5.0, 5.0);
+        input.push_at(
+            3,
+            crate::utils::input_event::InputEvent::Touch {
+                id: 1,
+                phase: TouchPhase::Ended,
+                x: 5.0,
+                y: 5.0,
+                force: None,
+            },
+        );
+        input.push_at(
+            10,
+            crate::utils::input_event::InputEvent::Touch {
+                id: 2,
+                phase: TouchPhase::Started,
+                x: 20.0,
+                y: 20.0,
+                force: None,
+            },
+        );
+        input.push_at(
+            50,
+            crate::utils::input_event::InputEvent::Touch {
+                id: 2,
+                phase: TouchPhase::Ended,
+                x: 20.0,
+                y: 20.0,
+                force: None,
+            },
+        );
+
+        let mut recorded = run(&mut engine, 20);
+        // the wall clock does not matter, only the clock of the engine
+        std::thread::sleep(Duration::from_millis(300));
+        recorded.extend(
+            run(&mut engine, 40)
+                .into_iter()
+                .map(|(frame, gesture)| (frame + 20, gesture)),
+        );
+        let recording = engine.stop_input_recording().unwrap();
+        assert_eq!(
+            recorded.iter().map(|i| i.1).collect::<Vec<_>>(),
+            [
+                Gesture::Tap {
+                    position: (5.0, 5.0)
+                },
+                Gesture::LongPress {
+                    position: (20.0, 20.0)
+                },
+            ]
+        );
+
+        let mut engine = manual_engine();
+        engine.replay_input(recording);
+        assert_eq!(run(&mut engine, 60), recorded);
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        let keys = [
+            (PhysicalKey::Code(KeyCode::KeyW), Key::Character("w".into())),
+            (
+                PhysicalKey::Code(KeyCode::Escape),
+                Key::Named(NamedKey::Escape),
+            ),
+            (
+                PhysicalKey::Unidentified(NativeKeyCode::Xkb(42)),
+                Key::Unidentified(NativeKey::Unidentified),
+            ),
+            (PhysicalKey::Code(KeyCode::Quote), Key::Dead(None)),
+            (PhysicalKey::Code(KeyCode::Backquote), Key::Dead(Some('`'))),
+        ];
+        let mut events = keys
+            .into_iter()
+            .map(|(physical_key, logical_key)| InputEvent::Keyboard {
+                physical_key,
+                logical_key,
+                state: ElementState::Pressed,
+            })
+            .collect::<Vec<_>>();
+        events.extend([
+            InputEvent::CursorMoved { x: 10.5, y: 20.0 },
+            InputEvent::MouseInput {
+                button: MouseButton::Other(7),
+                state: ElementState::Released,
+            },
+            InputEvent::MouseWheel {
+                delta: MouseScrollDelta::LineDelta(0.0, -1.0),
+            },
+            InputEvent::MouseWheel {
+                delta: MouseScrollDelta::PixelDelta((1.0, 2.0).into()),
+            },
+            InputEvent::MouseMotion { x: -3.0, y: 4.0 },
+            InputEvent::Touch {
+                id: 1,
+                phase: TouchPhase::Moved,
+                x: 5.0,
+                y: 6.0,
+                force: None,
+            },
+            InputEvent::Touch {
+                id: 2,
+                phase: TouchPhase::Cancelled,
+                x: 7.0,
+                y: 8.0,
+                force: Some(0.5),
+            },
+        ]);
+
+        let mut recorder = InputRecorder::new(Duration::from_secs(1));
+        for (index, event) in events.into_iter().enumerate() {
+            recorder.record(event, Duration::from_millis(1000 + 250 * index as u64));
+            recorder.next_frame();
+        }
+        let recording = recorder.finish();
+        assert_eq!(recording.events[2].time, 0.5);
+
+        let toml = recording.to_toml().unwrap();
+        assert_eq!(InputRecording::from_toml(toml).unwrap(), recording);
+    }
+
+    #[test]
+    fn recording_uses_the_engine_clock() {
+        let mut engine = manual_engine();
+        run(&mut engine, 2);
+        engine.start_input_recording();
+        engine
+            .synthetic_input
+            .push_at(2, InputEvent::CursorMoved { x: 1.0, y: 2.0 });
+        run(&mut engine, 4);
+        // the wall clock does not matter
+        std::thread::sleep(Duration::from_millis(50));
+        engine
+            .synthetic_input
+            .push(InputEvent::MouseMotion { x: 1.0, y: 1.0 });
+        run(&mut engine, 1);
+
+        let recording = engine.stop_input_recording().unwrap();
+        assert_eq!(
+            recording
+                .events
+                .iter()
+                .map(|i| (i.frame, i.time))
+                .collect::<Vec<_>>(),
+            [(2, 0.048), (4, 0.08)]
+        );
+    }
+}
@@ -1,10 +1,74 @@
+/// picking the adapter to render with
+pub mod adapter_selection;
 /// The camera utilities
 pub mod camera;
+/// components attached to entities and the systems that run on them
+pub mod components;
 /// Input wrapping
-#[cfg(all(feature = "window", not(feature = "headless")))]
+#[cfg(any(feature = "window", feature = "headless"))]
 mod current_input;
 /// default resources used in the engine
 pub mod default_resources;
+/// recovering from the loss of the device
+#[cfg(any(feature = "window", feature = "headless"))]
+pub mod device_recovery;
+/// typed events sent between signals
+pub mod event_bus;
+/// fixed-timestep update scheduling
+pub mod fixed_timestep;
+/// copying rendered frames back to the CPU
+pub mod frame_capture;
+/// a stack of game states, such as menus, levels and pause screens
+#[cfg(any(feature = "window", feature = "headless"))]
+pub mod game_state;
+/// gamepad input with swappable backends
+pub mod gamepad;
+/// golden image visual regression tests
+#[cfg(any(feature = "window", feature = "headless"))]
+pub mod golden;
+/// formats and image export of the headless output
+#[cfg(any(feature = "window", feature = "headless"))]
+pub mod headless_output;
+/// named actions and axes mapped to inputs
+#[cfg(any(feature = "window", feature = "headless"))]
+pub mod input_actions;
+/// serializable input events
+#[cfg(any(feature = "window", feature = "headless"))]
+pub mod input_event;
+/// recording and playback of input events
+#[cfg(any(feature = "window", feature = "headless"))]
+pub mod input_recording;
+/// rendering the scene at a resolution independent of the output
+#[cfg(any(feature = "window", feature = "headless"))]
+pub mod render_resolution;
+/// shared state keyed by its type
+pub mod resources;
+/// timers, scheduled callbacks and coroutines
+#[cfg(any(feature = "window", feature = "headless"))]
+pub mod scheduler;
+/// loading the engine settings from files, environment variables and arguments
+pub mod settings;
+/// input injected by code, for tests and headless mode
+#[cfg(any(feature = "window", feature = "headless"))]
+pub mod synthetic_input;
+/// frame timing and game time
+pub mod time;
+/// touch tracking and gesture recognition
+#[cfg(any(feature = "window", feature = "headless"))]
+pub mod touch;
 /// input helper
-#[cfg(all(feature = "window", not(feature = "headless")))]
+#[cfg(any(feature = "window", feature = "headless"))]
 pub mod winit_input_helper;
+
+/// Creates a small engine in the headless run mode, for the tests that step frames
+#[cfg(all(test, any(feature = "window", feature = "headless")))]
+pub(crate) fn headless_test_engine() -> crate::Engine {
+    crate::Engine::new_config(crate::EngineSettings {
+        width: 32,
+        height: 32,
+        run_mode: crate::RunMode::Headless,
+        backends: crate::Backends::all(),
+        ..Default::default()
+    })
+    .expect("a headless engine")
+}
@@ -0,0 +1,618 @@
+/*
+ * Blue Engine by Elham Aryanpur
+ *
+ * The license is same as the one on the root.
+*/
+
+use crate::{Renderer, WindowSize};
+
+/// Draws the scene texture over the whole output
+const UPSCALE_SHADER: &str = r#"
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // a triangle that covers the screen
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+@group(0) @binding(0)
+var scene: texture_2d<f32>;
+@group(0) @binding(1)
+var scene_sampler: sampler;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(scene, scene_sampler, in.uv);
+}
+
+override sharpness: f32 = 0.0;
+
+@fragment
+fn fs_sharpened(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(scene));
+    let center = textureSample(scene, scene_sampler, in.uv);
+    let up = textureSample(scene, scene_sampler, in.uv - vec2<f32>(0.0, texel.y)).rgb;
+    let down = textureSample(scene, scene_sampler, in.uv + vec2<f32>(0.0, texel.y)).rgb;
+    let left = textureSample(scene, scene_sampler, in.uv - vec2<f32>(texel.x, 0.0)).rgb;
+    let right = textureSample(scene, scene_sampler, in.uv + vec2<f32>(texel.x, 0.0)).rgb;
+
+    // push the color away from the blurred neighbourhood, without leaving its range
+    let blurred = (up + down + left + right) * 0.25;
+    let sharpened = center.rgb + (center.rgb - blurred) * sharpness * 2.0;
+    let low = min(center.rgb, min(min(up, down), min(left, right)));
+    let high = max(center.rgb, max(max(up, down), max(left, right)));
+    return vec4<f32>(clamp(sharpened, low, high), center.a);
+}
+"#;
+
+/// How the scene is scaled from the render resolution to the output
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum UpscaleFilter {
+    /// Bilinear filtering, which gets blurry the more the scene is scaled up
+    #[default]
+    Bilinear,
+    /// Bilinear filtering followed by sharpening of the given strength, from `0.0` to `1.0`
+    Sharpened(f32),
+}
+
+/// Settings of the automatic render scale, which renders at a lower resolution while the
+/// frames take longer than the target, and at a higher one while they are faster.
+///
+/// The frame time is the real time between frames, so the target should be slower than
+/// VSync or the fps cap, otherwise waiting for them reads as a slow frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DynamicResolution {
+    /// The frame time to reach, in seconds
+    pub target_frame_time: f32,
+    /// The lowest render scale
+    pub min_scale: f32,
+    /// The highest render scale
+    pub max_scale: f32,
+    /// How much the render scale changes in one adjustment
+    pub step: f32,
+    /// The amount of frames whose time is averaged before each adjustment
+    pub sample_frames: u32,
+}
+
+impl DynamicResolution {
+    /// Creates the settings that target the frames per second
+    pub fn new(target_fps: f32) -> Self {
+        Self {
+            target_frame_time: 1.0 / target_fps.max(1.0),
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for DynamicResolution {
+    fn default() -> Self {
+        Self {
+            target_frame_time: 1.0 / 60.0,
+            min_scale: 0.5,
+            max_scale: 1.0,
+            step: 0.1,
+            sample_frames: 30,
+        }
+    }
+}
+
+/// The automatic render scale along with the frame times measured since the last adjustment
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DynamicScaler {
+    settings: DynamicResolution,
+    frame_time: f32,
+    frames: u32,
+}
+
+/// The pass that scales the scene to the output, which only depends on the render target
+/// format and the upscale filter and so outlives the targets of each render resolution
+#[derive(Debug)]
+pub(crate) struct UpscalePipeline {
+    format: wgpu::TextureFormat,
+    filter: UpscaleFilter,
+    pipeline: wgpu::RenderPipeline,
+    sampler: wgpu::Sampler,
+}
+
+impl UpscalePipeline {
+    /// Creates the pass for the render target format and upscale filter of the renderer
+    fn new(renderer: &Renderer) -> Self {
+        let format = renderer.render_target_format();
+        let filter = renderer.upscale_filter;
+
+        let shader = renderer
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Upscale Shader"),
+                source: wgpu::ShaderSource::Wgsl(UPSCALE_SHADER.into()),
+            });
+        let (entry_point, constants) = match filter {
+            UpscaleFilter::Bilinear => ("fs_main", Vec::new()),
+            UpscaleFilter::Sharpened(sharpness) => (
+                "fs_sharpened",
+                vec![("sharpness", sharpness.clamp(0.0, 1.0) as f64)],
+            ),
+        };
+        let layout = renderer
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Upscale Pipeline Layout"),
+                bind_group_layouts: &[&renderer.texture_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let pipeline = renderer
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Upscale Pipeline"),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    buffers: &[],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some(entry_point),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: wgpu::PipelineCompilationOptions {
+                        constants: &constants,
+                        ..Default::default()
+                    },
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+                cache: None,
+            });
+
+        let sampler = renderer.device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Scaled Scene Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            format,
+            filter,
+            pipeline,
+            sampler,
+        }
+    }
+}
+
+/// The offscreen color, depth and MSAA targets the scene is rendered to at the render
+/// resolution, and the pass that scales them to the output
+#[derive(Debug)]
+pub(crate) struct ScaledTarget {
+    pub(crate) size: WindowSize,
+    pub(crate) color_view: wgpu::TextureView,
+    pub(crate) depth_buffer: (wgpu::Texture, wgpu::TextureView, wgpu::Sampler),
+    pub(crate) msaa_target: Option<wgpu::TextureView>,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl ScaledTarget {
+    /// Creates the targets of the size, in the render target format and MSAA of the renderer,
+    /// which are scaled to the output with the upscale pass
+    fn new(renderer: &Renderer, upscale: &UpscalePipeline, size: WindowSize) -> Self {
+        let size = (size.0.max(1), size.1.max(1));
+
+        let color = renderer.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Scaled Scene Target"),
+            size: wgpu::Extent3d {
+                width: size.0,
+                height: size.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: upscale.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let color_view = color.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut config = renderer.config.clone();
+        config.width = size.0;
+        config.height = size.1;
+        let depth_buffer = Renderer::build_depth_buffer(
+            "Scaled Scene Depth Buffer",
+            &renderer.device,
+            &config,
+            renderer.sample_count,
+        );
+        let msaa_target = renderer.build_msaa_target("Scaled Scene MSAA Target", size.0, size.1);
+
+        let bind_group = renderer
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Scaled Scene Bind Group"),
+                layout: &renderer.texture_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&color_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&upscale.sampler),
+                    },
+                ],
+            });
+
+        Self {
+            size,
+            color_view,
+            depth_buffer,
+            msaa_target,
+            bind_group,
+            pipeline: upscale.pipeline.clone(),
+        }
+    }
+
+    /// Records the pass that scales the scene to the output view
+    pub(crate) fn encode_upscale(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Upscale pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+                depth_slice: None,
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+impl Renderer {
+    /// Returns the resolution the scene is rendered at, which is the output size unless it
+    /// was changed with [`Renderer::set_render_resolution`] or [`Renderer::set_render_scale`]
+    pub fn render_resolution(&self) -> WindowSize {
+        self.scaled_target
+            .as_ref()
+            .map(|target| target.size)
+            .unwrap_or((self.config.width, self.config.height))
+    }
+
+    /// Renders the scene at the resolution, independent of the window or output size, and
+    /// scales it to the output with the upscale filter. `None` follows the render scale
+    /// again. Turns off the dynamic resolution. The scissor rect is in pixels of the render
+    /// resolution.
+    ///
+    /// The frame hooks of the signals, such as user interfaces, still draw at the output size.
+    pub fn set_render_resolution(
+        &mut self,
+        resolution: Option<WindowSize>,
+    ) -> Result<(), crate::error::Error> {
+        if resolution.is_some() {
+            self.check_render_scaling()?;
+        }
+        self.render_resolution =
+            resolution.map(|resolution| (resolution.0.max(1), resolution.1.max(1)));
+        self.dynamic_resolution = None;
+        self.rebuild_scaled_target();
+        Ok(())
+    }
+
+    /// Returns the render scale, the size of the render resolution relative to the output
+    pub fn render_scale(&self) -> f32 {
+        self.render_scale
+    }
+
+    /// Renders the scene at the scale of the output size, such as `0.5` for half the width
+    /// and height, and scales it to the output with the upscale filter. The render
+    /// resolution follows the output as it is resized. Replaces the resolution set with
+    /// [`Renderer::set_render_resolution`]. The scale is kept between `0.1` and `2.0`, and
+    /// infinite and NaN scales are an error.
+    pub fn set_render_scale(&mut self, scale: f32) -> Result<(), crate::error::Error> {
+        if !scale.is_finite() {
+            return Err(crate::error::Error::Custom(format!(
+                "Invalid render scale {scale}"
+            )));
+        }
+        let scale = scale.clamp(0.1, 2.0);
+        if scale != 1.0 {
+            self.check_render_scaling()?;
+        }
+        self.render_scale = scale;
+        self.render_resolution = None;
+        self.rebuild_scaled_target();
+        Ok(())
+    }
+
+    /// Returns the filter that scales the scene to the output
+    pub fn upscale_filter(&self) -> UpscaleFilter {
+        self.upscale_filter
+    }
+
+    /// Sets the filter that scales the scene from the render resolution to the output
+    pub fn set_upscale_filter(&mut self, filter: UpscaleFilter) {
+        self.upscale_filter = filter;
+        // the targets keep their size, only the pass changes
+        if self.scaled_target.is_none() {
+            return;
+        }
+        self.update_upscale_pipeline();
+        if let (Some(target), Some(upscale)) =
+            (self.scaled_target.as_mut(), self.upscale_pipeline.as_ref())
+        {
+            target.pipeline = upscale.pipeline.clone();
+        }
+    }
+
+    /// Returns the settings of the automatic render scale, if it is on
+    pub fn dynamic_resolution(&self) -> Option<&DynamicResolution> {
+        self.dynamic_resolution
+            .as_ref()
+            .map(|scaler| &scaler.settings)
+    }
+
+    /// Turns on the automatic render scale, or turns it off with `None` and keeps the current
+    /// render scale. The scale starts at the highest one of the range.
+    /// ```ignore
+    /// // keep the frames at 60 fps, going down to half the resolution at most
+    /// engine.renderer.set_dynamic_resolution(Some(DynamicResolution {
+    ///     min_scale: 0.5,
+    ///     ..DynamicResolution::new(60.0)
+    /// }))?;
+    /// engine.renderer.set_upscale_filter(UpscaleFilter::Sharpened(0.5));
+    /// ```
+    pub fn set_dynamic_resolution(
+        &mut self,
+        settings: Option<DynamicResolution>,
+    ) -> Result<(), crate::error::Error> {
+        self.dynamic_resolution = None;
+        if let Some(settings) = settings {
+            // the scale can go below the highest one at any frame
+            self.check_render_scaling()?;
+            if !(settings.min_scale > 0.0 && settings.min_scale <= settings.max_scale) {
+                return Err(crate::error::Error::Custom(format!(
+                    "Invalid render scale range {} to {}",
+                    settings.min_scale, settings.max_scale
+                )));
+            }
+            self.set_render_scale(settings.max_scale)?;
+            self.dynamic_resolution = Some(DynamicScaler {
+                settings,
+                frame_time: 0.0,
+                frames: 0,
+            });
+        }
+        Ok(())
+    }
+
+    /// Measures the time of the last frame, and adjusts the render scale once enough frames
+    /// were measured
+    pub(crate) fn update_dynamic_resolution(&mut self, frame_time: std::time::Duration) {
+        let Some(scaler) = self.dynamic_resolution.as_mut() else {
+            return;
+        };
+        // the first frame, or a manual clock that did not move
+        if frame_time.is_zero() {
+            return;
+        }
+        scaler.frame_time += frame_time.as_secs_f32();
+        scaler.frames += 1;
+        if scaler.frames < scaler.settings.sample_frames.max(1) {
+            return;
+        }
+
+        let settings = scaler.settings;
+        let average = scaler.frame_time / scaler.frames as f32;
+        scaler.frame_time = 0.0;
+        scaler.frames = 0;
+
+        // a margin around the target keeps the scale from flipping every adjustment
+        let scale = if average > settings.target_frame_time * 1.05 {
+            self.render_scale - settings.step
+        } else if average < settings.target_frame_time * 0.85 {
+            self.render_scale + settings.step
+        } else {
+            return;
+        };
+        let scale = scale.clamp(settings.min_scale, settings.max_scale);
+        if (scale - self.render_scale).abs() > f32::EPSILON {
+            self.render_scale = scale;
+            self.rebuild_scaled_target();
+        }
+    }
+
+    /// Rebuilds the targets of the render resolution, or removes them when the scene is
+    /// rendered at the output size
+    pub(crate) fn rebuild_scaled_target(&mut self) {
+        let output = (self.config.width, self.config.height);
+        let resolution = self.render_resolution.unwrap_or_else(|| {
+            (
+                ((output.0 as f32 * self.render_scale).round() as u32).max(1),
+                ((output.1 as f32 * self.render_scale).round() as u32).max(1),
+            )
+        });
+
+        // rendering at the output size does not need the extra pass
+        if resolution == output {
+            self.scaled_target = None;
+            return;
+        }
+        self.update_upscale_pipeline();
+        self.scaled_target = self
+            .upscale_pipeline
+            .as_ref()
+            .map(|upscale| ScaledTarget::new(self, upscale, resolution));
+    }
+
+    /// Builds the upscale pass again if the render target format or the upscale filter changed
+    /// since it was built
+    fn update_upscale_pipeline(&mut self) {
+        let format = self.render_target_format();
+        let filter = self.upscale_filter;
+        if !self
+            .upscale_pipeline
+            .as_ref()
+            .is_some_and(|upscale| upscale.format == format && upscale.filter == filter)
+        {
+            self.upscale_pipeline = Some(UpscalePipeline::new(self));
+        }
+    }
+
+    /// Checks that the scene can be rendered at another resolution than the output
+    fn check_render_scaling(&self) -> Result<(), crate::error::Error> {
+        if self.run_mode == crate::RunMode::Headless
+            && self.headless_output == crate::utils::headless_output::HeadlessOutputFormat::Depth
+        {
+            return Err(crate::error::Error::Custom(
+                "The depth output needs the scene to be rendered at the output size".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dynamic_resolution_settings() {
+        let mut engine = crate::utils::headless_test_engine();
+        for (min_scale, max_scale) in [(f32::NAN, 1.0), (0.5, f32::NAN), (0.0, 1.0), (1.0, 0.5)] {
+            let settings = DynamicResolution {
+                min_scale,
+                max_scale,
+                ..Default::default()
+            };
+            assert!(
+                engine
+                    .renderer
+                    .set_dynamic_resolution(Some(settings))
+                    .is_err()
+            );
+        }
+        assert!(engine.renderer.dynamic_resolution().is_none());
+
+        // the depth output can not be scaled, even when the scale starts at the output size
+        engine.renderer.headless_output =
+            crate::utils::headless_output::HeadlessOutputFormat::Depth;
+        assert!(
+            engine
+                .renderer
+                .set_dynamic_resolution(Some(DynamicResolution::default()))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn dynamic_resolution_follows_frame_time() {
+        let mut engine = crate::utils::headless_test_engine();
+        let renderer = &mut engine.renderer;
+        renderer
+            .set_dynamic_resolution(Some(DynamicResolution {
+                step: 0.25,
+                sample_frames: 4,
+                ..DynamicResolution::new(60.0)
+            }))
+            .unwrap();
+        assert_eq!(renderer.render_scale(), 1.0);
+
+        let frames = |renderer: &mut crate::Renderer, milliseconds: u64, amount: u32| {
+            for _ in 0..amount {
+                renderer.update_dynamic_resolution(std::time::Duration::from_millis(milliseconds));
+            }
+            renderer.render_scale()
+        };
+
+        // slow frames lower the scale once enough of them are measured, down to the lowest
+        assert_eq!(frames(renderer, 40, 3), 1.0);
+        assert_eq!(frames(renderer, 40, 1), 0.75);
+        assert_eq!(renderer.render_resolution(), (24, 24));
+        assert_eq!(frames(renderer, 40, 4), 0.5);
+        assert_eq!(frames(renderer, 40, 8), 0.5);
+
+        // frames close to the target keep it
+        assert_eq!(frames(renderer, 16, 4), 0.5);
+        // frames that did not take any time are not measured
+        assert_eq!(frames(renderer, 0, 8), 0.5);
+
+        // fast frames raise it again, up to the highest
+        assert_eq!(frames(renderer, 5, 4), 0.75);
+        assert_eq!(frames(renderer, 5, 4), 1.0);
+        assert_eq!(frames(renderer, 5, 8), 1.0);
+        assert!(renderer.scaled_target.is_none());
+    }
+
+    #[test]
+    fn non_finite_render_scale() {
+        let mut engine = crate::utils::headless_test_engine();
+        for scale in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            assert!(engine.renderer.set_render_scale(scale).is_err());
+        }
+        assert_eq!(engine.renderer.render_scale(), 1.0);
+        assert!(engine.renderer.scaled_target.is_none());
+
+        // the finite ones are kept in range
+        engine.renderer.set_render_scale(100.0).unwrap();
+        assert_eq!(engine.renderer.render_scale(), 2.0);
+        assert_eq!(engine.renderer.render_resolution(), (64, 64));
+    }
+
+    #[test]
+    fn upscale_pipeline_is_kept_across_sizes() {
+        let mut engine = crate::utils::headless_test_engine();
+        engine.renderer.set_render_scale(0.5).unwrap();
+        let pipeline = engine
+            .renderer
+            .scaled_target
+            .as_ref()
+            .unwrap()
+            .pipeline
+            .clone();
+        assert_eq!(engine.renderer.render_resolution(), (16, 16));
+
+        engine.renderer.set_render_scale(0.25).unwrap();
+        engine.renderer.set_render_scale(1.0).unwrap();
+        assert!(engine.renderer.scaled_target.is_none());
+        engine.renderer.set_render_scale(0.75).unwrap();
+        let target = engine.renderer.scaled_target.as_ref().unwrap();
+        assert_eq!(target.size, (24, 24));
+        assert_eq!(target.pipeline, pipeline);
+
+        engine
+            .renderer
+            .set_upscale_filter(UpscaleFilter::Sharpened(0.5));
+        let target = engine.renderer.scaled_target.as_ref().unwrap();
+        assert_eq!(target.size, (24, 24));
+        assert_ne!(target.pipeline, pipeline);
+        engine.step().unwrap();
+    }
+}
@@ -0,0 +1,135 @@
+/*
+ * Blue Engine by Elham Aryanpur
+ *
+ * The license is same as the one on the root.
+*/
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+/// Shared state keyed by its type, so signals and the update loop can reach it without
+/// knowing each other. There is at most one value of each type.
+/// ```
+/// # use blue_engine_core::Resources;
+/// struct Score(u32);
+///
+/// let mut resources = Resources::new();
+/// resources.insert(Score(0));
+/// if let Some(score) = resources.get_mut::<Score>() {
+///     score.0 += 10;
+/// }
+/// assert_eq!(resources.get::<Score>().map(|i| i.0), Some(10));
+/// ```
+#[derive(Default)]
+pub struct Resources {
+    values: HashMap<TypeId, Box<dyn Any>>,
+}
+
+impl Resources {
+    /// Creates an empty resource map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the value, returning the previous value of the same type if any
+    pub fn insert<T: 'static>(&mut self, value: T) -> Option<T> {
+        self.values
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|previous| previous.downcast::<T>().ok())
+            .map(|previous| *previous)
+    }
+
+    /// Gets the value of the type
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.values
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref::<T>())
+    }
+
+    /// Gets the value of the type mutably
+    pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.values
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_mut::<T>())
+    }
+
+    /// Gets the value of the type, inserting the one the function returns if there is none
+    pub fn get_or_insert_with<T: 'static>(&mut self, default: impl FnOnce() -> T) -> &mut T {
+        #[allow(clippy::expect_used)]
+        self.values
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(default()))
+            .downcast_mut::<T>()
+            .expect("The resource is stored under the id of its own type")
+    }
+
+    /// Removes the value of the type and returns it
+    pub fn remove<T: 'static>(&mut self) -> Option<T> {
+        self.values
+            .remove(&TypeId::of::<T>())
+            .and_then(|value| value.downcast::<T>().ok())
+            .map(|value| *value)
+    }
+
+    /// Checks if there is a value of the type
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.values.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Returns the amount of values
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns true if there are no values
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Removes all values
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+}
+
+impl std::fmt::Debug for Resources {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Resources")
+            .field("len", &self.values.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Score(u32);
+
+    #[test]
+    fn typed_values() {
+        let mut resources = Resources::new();
+        assert_eq!(resources.insert(Score(1)), None);
+        assert_eq!(resources.insert(Score(2)), Some(Score(1)));
+        resources.insert(String::from("level"));
+        assert_eq!(resources.len(), 2);
+
+        resources.get_mut::<Score>().unwrap().0 += 1;
+        assert_eq!(resources.get::<Score>(), Some(&Score(3)));
+        assert_eq!(resources.get::<String>().map(|i| i.as_str()), Some("level"));
+        assert_eq!(resources.get::<u32>(), None);
+
+        // an existing value is not replaced
+        assert_eq!(resources.get_or_insert_with(|| Score(10)), &Score(3));
+        assert_eq!(*resources.get_or_insert_with(|| 5u32), 5);
+
+        assert_eq!(resources.remove::<Score>(), Some(Score(3)));
+        assert_eq!(resources.remove::<Score>(), None);
+        assert!(!resources.contains::<Score>());
+        assert!(resources.contains::<String>());
+
+        resources.clear();
+        assert!(resources.is_empty());
+    }
+}
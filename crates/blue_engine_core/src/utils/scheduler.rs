@@ -0,0 +1,631 @@
+/*
+ * Blue Engine by Elham Aryanpur
+ *
+ * The license is same as the one on the root.
+*/
+
+use crate::Engine;
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// Identifies a timer or a coroutine, used to cancel it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScheduleId(u64);
+
+type Callback = Box<dyn 'static + FnMut(&mut Engine)>;
+type EngineCallback = Box<dyn 'static + FnOnce(&mut Engine)>;
+type EngineFunction<R> = Box<dyn 'static + FnOnce(&mut Engine) -> R>;
+
+struct Timer {
+    id: ScheduleId,
+    fire_at: Duration,
+    interval: Option<Duration>,
+    // taken out while the callback runs
+    callback: Option<Callback>,
+}
+
+struct Task {
+    id: ScheduleId,
+    // taken out while the coroutine is polled
+    future: Option<Pin<Box<dyn 'static + Future<Output = ()>>>>,
+}
+
+thread_local! {
+    static GAME_TIME: Cell<Duration> = const { Cell::new(Duration::ZERO) };
+    static FRAME: Cell<u64> = const { Cell::new(0) };
+    static ENGINE_QUEUE: RefCell<Vec<EngineCallback>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Runs timers, callbacks and coroutines against the game time of the engine.
+///
+/// Everything is run once per frame, after the fixed updates and before the update loop.
+/// Since the game time is used, timers and coroutines wait while the time is paused and
+/// follow the time scale. Anything scheduled while running starts on the next frame, and
+/// anything cancelled or cleared while running does not run anymore.
+///
+/// Coroutines are plain futures that are polled every frame:
+/// ```
+/// # use blue_engine_core::{Engine, wait_seconds, with_engine};
+/// # fn spawn(engine: &mut Engine) {
+/// engine.spawn(async {
+///     wait_seconds(2.0).await;
+///     with_engine(|engine| engine.time.pause()).await;
+/// });
+/// # }
+/// ```
+#[derive(Default)]
+pub struct Scheduler {
+    next_id: u64,
+    timers: Vec<Timer>,
+    next_frame: Vec<(ScheduleId, EngineCallback)>,
+    tasks: Vec<Task>,
+    now: Duration,
+}
+
+impl Scheduler {
+    /// Creates an empty scheduler
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn new_id(&mut self) -> ScheduleId {
+        self.next_id += 1;
+        ScheduleId(self.next_id)
+    }
+
+    /// Runs the callback once after the given amount of seconds. Seconds too long for a
+    /// [`Duration`], such as infinity, never pass
+    pub fn after(
+        &mut self,
+        seconds: f32,
+        callback: impl 'static + FnOnce(&mut Engine),
+    ) -> ScheduleId {
+        let mut callback = Some(callback);
+        self.add_timer(
+            duration_from_seconds(seconds),
+            None,
+            Box::new(move |engine| {
+                if let Some(callback) = callback.take() {
+                    callback(engine);
+                }
+            }),
+        )
+    }
+
+    /// Runs the callback every time the given amount of seconds passes, until cancelled
+    pub fn every(
+        &mut self,
+        seconds: f32,
+        callback: impl 'static + FnMut(&mut Engine),
+    ) -> ScheduleId {
+        let interval = duration_from_seconds(seconds);
+        self.add_timer(interval, Some(interval), Box::new(callback))
+    }
+
+    fn add_timer(
+        &mut self,
+        delay: Duration,
+        interval: Option<Duration>,
+        callback: Callback,
+    ) -> ScheduleId {
+        let id = self.new_id();
+        self.timers.push(Timer {
+            id,
+            fire_at: self.now.saturating_add(delay),
+            interval,
+            callback: Some(callback),
+        });
+        id
+    }
+
+    /// Runs the callback once on the next frame
+    pub fn next_frame(&mut self, callback: impl 'static + FnOnce(&mut Engine)) {
+        let id = self.new_id();
+        self.next_frame.push((id, Box::new(callback)));
+    }
+
+    /// Starts a coroutine, which is polled once every frame until it finishes
+    pub fn spawn(&mut self, future: impl 'static + Future<Output = ()>) -> ScheduleId {
+        let id = self.new_id();
+        self.tasks.push(Task {
+            id,
+            future: Some(Box::pin(future)),
+        });
+        id
+    }
+
+    /// Stops a timer or a coroutine
+    pub fn cancel(&mut self, id: ScheduleId) {
+        self.timers.retain(|i| i.id != id);
+        self.tasks.retain(|i| i.id != id);
+    }
+
+    /// Returns true if the timer or the coroutine is still waiting to run
+    pub fn is_scheduled(&self, id: ScheduleId) -> bool {
+        self.timers.iter().any(|i| i.id == id) || self.tasks.iter().any(|i| i.id == id)
+    }
+
+    /// Returns the amount of timers and coroutines that are waiting to run
+    pub fn len(&self) -> usize {
+        self.timers.len() + self.tasks.len() + self.next_frame.len()
+    }
+
+    /// Returns true if nothing is waiting to run
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all timers, callbacks and coroutines
+    pub fn clear(&mut self) {
+        self.timers.clear();
+        self.next_frame.clear();
+        self.tasks.clear();
+    }
+
+    fn timer(&mut self, id: ScheduleId) -> Option<&mut Timer> {
+        self.timers.iter_mut().find(|i| i.id == id)
+    }
+
+    fn task(&mut self, id: ScheduleId) -> Option<&mut Task> {
+        self.tasks.iter_mut().find(|i| i.id == id)
+    }
+
+    /// Runs the timers, callbacks and coroutines of the engine that are due this frame
+    pub(crate) fn update(engine: &mut Engine) {
+        let now = engine.time.elapsed();
+        engine.scheduler.now = now;
+        GAME_TIME.with(|i| i.set(now));
+        FRAME.with(|i| i.set(engine.time.frame_count()));
+
+        // what is scheduled while running waits for the next frame, and each one is looked up
+        // again before it runs, in case an earlier one cancelled it
+        let scheduler = &engine.scheduler;
+        let callbacks = scheduler.next_frame.iter().map(|i| i.0).collect::<Vec<_>>();
+        let due = scheduler
+            .timers
+            .iter()
+            .filter(|i| i.fire_at <= now)
+            .map(|i| i.id)
+            .collect::<Vec<_>>();
+        let mut pending = scheduler.tasks.iter().map(|i| i.id).collect::<Vec<_>>();
+
+        for id in callbacks {
+            let Some(index) = engine.scheduler.next_frame.iter().position(|i| i.0 == id) else {
+                continue;
+            };
+            let (_, callback) = engine.scheduler.next_frame.remove(index);
+            callback(engine);
+        }
+
+        for id in due {
+            let Some(mut callback) = engine.scheduler.timer(id).and_then(|i| i.callback.take())
+            else {
+                continue;
+            };
+            callback(engine);
+
+            let Some(timer) = engine.scheduler.timer(id) else {
+                continue;
+            };
+            match timer.interval {
+                Some(interval) => {
+                    timer.callback = Some(callback);
+                    timer.fire_at = timer.fire_at.saturating_add(interval);
+                    if timer.fire_at <= now {
+                        timer.fire_at = now.saturating_add(interval);
+                    }
+                }
+                None => engine.scheduler.cancel(id),
+            }
+        }
+
+        let mut context = Context::from_waker(std::task::Waker::noop());
+        while !pending.is_empty() {
+            let mut waiting_on_engine = Vec::new();
+            for id in pending {
+                let Some(mut future) = engine.scheduler.task(id).and_then(|i| i.future.take())
+                else {
+                    continue;
+                };
+                let finished = future.as_mut().poll(&mut context).is_ready();
+                if finished {
+                    engine.scheduler.cancel(id);
+                } else if let Some(task) = engine.scheduler.task(id) {
+                    task.future = Some(future);
+                }
+
+                let callbacks = ENGINE_QUEUE.with(|i| std::mem::take(&mut *i.borrow_mut()));
+                if !callbacks.is_empty() {
+                    // the task can continue within the same frame once the engine is done
+                    if !finished {
+                        waiting_on_engine.push(id);
+                    }
+                    for callback in callbacks {
+                        callback(engine);
+                    }
+                }
+            }
+            pending = waiting_on_engine;
+        }
+    }
+}
+
+/// Converts seconds to a duration, where negative and NaN seconds are zero, and seconds too
+/// long for a duration never pass
+fn duration_from_seconds(seconds: f32) -> Duration {
+    if seconds.is_nan() || seconds <= 0.0 {
+        return Duration::ZERO;
+    }
+    Duration::try_from_secs_f32(seconds).unwrap_or(Duration::MAX)
+}
+
+/// A future that finishes once the given amount of game time passed
+#[derive(Debug, Clone, Copy)]
+pub struct WaitTime {
+    duration: Duration,
+    until: Option<Duration>,
+}
+
+impl Future for WaitTime {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+        let now = GAME_TIME.with(|i| i.get());
+        let duration = self.duration;
+        let until = *self.until.get_or_insert(now.saturating_add(duration));
+        if now >= until {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Waits for the given amount of seconds of game time within a coroutine. Seconds too long
+/// for a [`Duration`], such as infinity, never pass
+pub fn wait_seconds(seconds: f32) -> WaitTime {
+    wait(duration_from_seconds(seconds))
+}
+
+/// Waits for the given duration of game time within a coroutine
+pub fn wait(duration: Duration) -> WaitTime {
+    WaitTime {
+        duration,
+        until: None,
+    }
+}
+
+/// A future that finishes once the given amount of frames passed
+#[derive(Debug, Clone, Copy)]
+pub struct WaitFrames {
+    frames: u64,
+    until: Option<u64>,
+}
+
+impl Future for WaitFrames {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+        let frame = FRAME.with(|i| i.get());
+        let frames = self.frames;
+        let until = *self.until.get_or_insert(frame + frames);
+        if frame >= until {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Waits for the given amount of frames within a coroutine
+pub fn wait_frames(frames: u64) -> WaitFrames {
+    WaitFrames {
+        frames,
+        until: None,
+    }
+}
+
+/// Waits until the next frame within a coroutine
+pub fn next_frame() -> WaitFrames {
+    wait_frames(1)
+}
+
+/// A future that runs a function on the engine and returns its result
+pub struct WithEngine<R> {
+    function: Option<EngineFunction<R>>,
+    result: Rc<RefCell<Option<R>>>,
+}
+
+impl<R: 'static> Future for WithEngine<R> {
+    type Output = R;
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<R> {
+        if let Some(result) = self.result.borrow_mut().take() {
+            return Poll::Ready(result);
+        }
+
+        if let Some(function) = self.function.take() {
+            let result = self.result.clone();
+            ENGINE_QUEUE.with(|i| {
+                i.borrow_mut().push(Box::new(move |engine| {
+                    *result.borrow_mut() = Some(function(engine));
+                }))
+            });
+        }
+        Poll::Pending
+    }
+}
+
+/// Gives the engine to the function within a coroutine. The function runs within the
+/// same frame, and the coroutine continues right after with its result.
+pub fn with_engine<R: 'static>(function: impl 'static + FnOnce(&mut Engine) -> R) -> WithEngine<R> {
+    WithEngine {
+        function: Some(Box::new(function)),
+        result: Rc::new(RefCell::new(None)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_while_running() {
+        let mut engine = crate::utils::headless_test_engine();
+        engine.time = crate::utils::time::Time::manual();
+
+        let finished = engine.scheduler.after(0.0, |_| {});
+        let own_id = Rc::new(Cell::new(None));
+        let id = own_id.clone();
+        let repeating = engine.scheduler.every(0.0, move |engine| {
+            // cancelling a timer that already finished does nothing
+            engine.scheduler.cancel(finished);
+            if engine.time.frame_count() == 3 {
+                engine.scheduler.cancel(id.get().unwrap());
+            }
+        });
+        own_id.set(Some(repeating));
+
+        for _ in 0..5 {
+            engine.step().unwrap();
+        }
+        assert!(!engine.scheduler.is_scheduled(repeating));
+        assert!(engine.scheduler.is_empty());
+    }
+
+    #[test]
+    fn cancel_from_another_callback() {
+        let (mut engine, ran) = manual_engine();
+        let ids = Rc::new(Cell::new(None));
+
+        let cancelled = ids.clone();
+        engine.scheduler.after(0.0, move |engine| {
+            let (timer, task) = cancelled.get().unwrap();
+            // both are due this frame, and are still scheduled until now
+            assert!(engine.scheduler.is_scheduled(timer));
+            assert!(engine.scheduler.is_scheduled(task));
+            assert_eq!(engine.scheduler.len(), 3);
+            engine.scheduler.cancel(timer);
+            engine.scheduler.cancel(task);
+        });
+        let counter = ran.clone();
+        let timer = engine
+            .scheduler
+            .after(0.0, move |_| counter.set(counter.get() + 1));
+        let counter = ran.clone();
+        let task = engine.spawn(async move { counter.set(counter.get() + 1) });
+        ids.set(Some((timer, task)));
+
+        engine.step().unwrap();
+        engine.step().unwrap();
+        assert_eq!(ran.get(), 0);
+        assert!(engine.scheduler.is_empty());
+    }
+
+    #[test]
+    fn clear_from_callback() {
+        let (mut engine, ran) = manual_engine();
+
+        let counter = ran.clone();
+        engine.scheduler.next_frame(move |engine| {
+            engine.scheduler.clear();
+            // scheduled after the clear, so it is kept
+            let counter = counter.clone();
+            engine
+                .scheduler
+                .after(0.0, move |_| counter.set(counter.get() + 10));
+        });
+        let counter = ran.clone();
+        engine
+            .scheduler
+            .next_frame(move |_| counter.set(counter.get() + 1));
+        let counter = ran.clone();
+        engine
+            .scheduler
+            .every(0.0, move |_| counter.set(counter.get() + 1));
+        let counter = ran.clone();
+        engine.spawn(async move {
+            loop {
+                counter.set(counter.get() + 1);
+                next_frame().await;
+            }
+        });
+
+        engine.step().unwrap();
+        assert_eq!(ran.get(), 0);
+        assert_eq!(engine.scheduler.len(), 1);
+
+        engine.step().unwrap();
+        engine.step().unwrap();
+        assert_eq!(ran.get(), 10);
+        assert!(engine.scheduler.is_empty());
+    }
+
+    #[test]
+    fn durations_out_of_range() {
+        let mut engine = crate::utils::headless_test_engine();
+        engine.time = crate::utils::time::Time::manual();
+
+        let never = engine.scheduler.after(f32::INFINITY, |_| panic!("ran"));
+        let too_long = engine.scheduler.every(1e30, |_| panic!("ran"));
+        let ran = Rc::new(Cell::new(0));
+        let counter = ran.clone();
+        engine
+            .scheduler
+            .after(f32::NAN, move |_| counter.set(counter.get() + 1));
+        engine.scheduler.spawn(async {
+            wait_seconds(f32::INFINITY).await;
+            panic!("finished");
+        });
+
+        for _ in 0..3 {
+            engine.time.advance(Duration::from_secs(3600));
+            engine.step().unwrap();
+        }
+        assert_eq!(ran.get(), 1);
+        assert!(engine.scheduler.is_scheduled(never));
+        assert!(engine.scheduler.is_scheduled(too_long));
+
+        engine.scheduler.cancel(ScheduleId(u64::MAX));
+        engine.scheduler.clear();
+        assert!(engine.scheduler.is_empty());
+    }
+
+    /// An engine with a manual clock, and a counter for the callbacks
+    fn manual_engine() -> (Engine, Rc<Cell<u32>>) {
+        let mut engine = crate::utils::headless_test_engine();
+        engine.time = crate::utils::time::Time::manual();
+        (engine, Rc::new(Cell::new(0)))
+    }
+
+    fn step_after(engine: &mut Engine, seconds: f32) {
+        engine.time.advance(Duration::from_secs_f32(seconds));
+        engine.step().unwrap();
+    }
+
+    #[test]
+    fn after_runs_once() {
+        let (mut engine, ran) = manual_engine();
+        let counter = ran.clone();
+        let id = engine
+            .scheduler
+            .after(1.0, move |_| counter.set(counter.get() + 1));
+
+        engine.step().unwrap();
+        step_after(&mut engine, 0.5);
+        assert_eq!(ran.get(), 0);
+        assert!(engine.scheduler.is_scheduled(id));
+
+        step_after(&mut engine, 0.5);
+        assert_eq!(ran.get(), 1);
+        step_after(&mut engine, 1.0);
+        assert_eq!(ran.get(), 1);
+        assert!(engine.scheduler.is_empty());
+    }
+
+    #[test]
+    fn every_repeats() {
+        let (mut engine, ran) = manual_engine();
+        let counter = ran.clone();
+        let id = engine
+            .scheduler
+            .every(1.0, move |_| counter.set(counter.get() + 1));
+
+        engine.step().unwrap();
+        for _ in 0..3 {
+            step_after(&mut engine, 1.0);
+        }
+        assert_eq!(ran.get(), 3);
+
+        // a long frame runs it once, instead of catching up
+        step_after(&mut engine, 2.5);
+        assert_eq!(ran.get(), 4);
+
+        engine.scheduler.cancel(id);
+        step_after(&mut engine, 1.0);
+        assert_eq!(ran.get(), 4);
+        assert!(engine.scheduler.is_empty());
+    }
+
+    #[test]
+    fn next_frame_runs_once() {
+        let (mut engine, ran) = manual_engine();
+        let counter = ran.clone();
+        engine
+            .scheduler
+            .next_frame(move |_| counter.set(counter.get() + 1));
+        assert_eq!(engine.scheduler.len(), 1);
+
+        engine.step().unwrap();
+        engine.step().unwrap();
+        assert_eq!(ran.get(), 1);
+        assert!(engine.scheduler.is_empty());
+    }
+
+    #[test]
+    fn coroutine_follows_game_time() {
+        let (mut engine, ran) = manual_engine();
+        let counter = ran.clone();
+        let id = engine.spawn(async move {
+            wait_seconds(2.0).await;
+            counter.set(counter.get() + 1);
+        });
+
+        engine.step().unwrap();
+        step_after(&mut engine, 1.0);
+
+        // the paused time does not count
+        engine.time.pause();
+        step_after(&mut engine, 5.0);
+        assert_eq!(ran.get(), 0);
+
+        // 0.5 seconds at double speed are the last second
+        engine.time.resume();
+        engine.time.set_time_scale(2.0);
+        step_after(&mut engine, 0.4);
+        assert_eq!(ran.get(), 0);
+        step_after(&mut engine, 0.1);
+        assert_eq!(ran.get(), 1);
+        assert!(!engine.scheduler.is_scheduled(id));
+    }
+
+    #[test]
+    fn coroutine_waits_frames() {
+        let (mut engine, ran) = manual_engine();
+        let counter = ran.clone();
+        engine.spawn(async move {
+            next_frame().await;
+            counter.set(counter.get() + 1);
+            wait_frames(2).await;
+            counter.set(counter.get() + 1);
+        });
+
+        let mut counts = Vec::new();
+        for _ in 0..4 {
+            engine.step().unwrap();
+            counts.push(ran.get());
+        }
+        assert_eq!(counts, [0, 1, 1, 2]);
+    }
+
+    #[test]
+    fn coroutine_with_engine() {
+        let (mut engine, ran) = manual_engine();
+        let counter = ran.clone();
+        engine.spawn(async move {
+            let frame = with_engine(|engine| engine.time.frame_count()).await;
+            counter.set(frame as u32);
+            with_engine(|engine| engine.time.pause()).await;
+        });
+
+        // both run within the first frame
+        engine.step().unwrap();
+        assert_eq!(ran.get(), 1);
+        assert!(engine.time.is_paused());
+        assert!(engine.scheduler.is_empty());
+    }
+}
@@ -0,0 +1,424 @@
+/*
+ * Blue Engine by Elham Aryanpur
+ *
+ * The license is same as the one on the root.
+*/
+
+use crate::{EngineSettings, error::Error};
+
+/// The settings that can be overridden by environment variables and command-line arguments
+const OPTIONS: &[&str] = &[
+    "width",
+    "height",
+    "resolution",
+    "title",
+    "run_mode",
+    "present_mode",
+    "backend",
+    "msaa",
+    "fullscreen",
+    "fps_cap",
+    "power_preference",
+    "adapter",
+];
+
+/// The prefix of the environment variables that override the settings
+pub const ENV_PREFIX: &str = "BLUE_ENGINE_";
+
+impl EngineSettings {
+    /// Parses the settings from a TOML string
+    pub fn from_toml(source: impl AsRef<str>) -> Result<Self, Error> {
+        Ok(toml::from_str(source.as_ref())?)
+    }
+
+    /// Serializes the settings to a TOML string
+    pub fn to_toml(&self) -> Result<String, Error> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    /// Parses the settings from a RON string
+    pub fn from_ron(source: impl AsRef<str>) -> Result<Self, Error> {
+        Ok(ron::from_str(source.as_ref())?)
+    }
+
+    /// Serializes the settings to a RON string
+    pub fn to_ron(&self) -> Result<String, Error> {
+        Ok(ron::ser::to_string_pretty(
+            self,
+            ron::ser::PrettyConfig::default(),
+        )?)
+    }
+
+    /// Loads the settings from a file, as RON if its extension is `ron`, and as TOML
+    /// otherwise
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, Error> {
+        let source = std::fs::read_to_string(path.as_ref())?;
+        if is_ron(path.as_ref()) {
+            Self::from_ron(source)
+        } else {
+            Self::from_toml(source)
+        }
+    }
+
+    /// Saves the settings to a file, as RON if its extension is `ron`, and as TOML otherwise
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), Error> {
+        let source = if is_ron(path.as_ref()) {
+            self.to_ron()?
+        } else {
+            self.to_toml()?
+        };
+        std::fs::write(path, source)?;
+        Ok(())
+    }
+
+    /// Applies the overrides from the environment variables, and then from the arguments
+    /// of the process, so the arguments win. See [`EngineSettings::apply_env`] and
+    /// [`EngineSettings::apply_args`].
+    /// ```no_run
+    /// # use blue_engine_core::EngineSettings;
+    /// let mut settings = EngineSettings::load("settings.toml").unwrap_or_default();
+    /// settings.apply_overrides().expect("Invalid settings");
+    /// ```
+    pub fn apply_overrides(&mut self) -> Result<(), Error> {
+        self.apply_env()?;
+        self.apply_args(std::env::args().skip(1))
+    }
+
+    /// Applies the `BLUE_ENGINE_*` environment variables, such as `BLUE_ENGINE_MSAA=4` or
+    /// `BLUE_ENGINE_PRESENT_MODE=fifo`. See [`EngineSettings::set_option`] for the names
+    pub fn apply_env(&mut self) -> Result<(), Error> {
+        for key in OPTIONS {
+            let name = format!("{ENV_PREFIX}{}", key.to_uppercase());
+            if let Ok(value) = std::env::var(name) {
+                self.set_option(key, &value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the command-line arguments such as `--resolution 1280x720`, `--msaa=4` or
+    /// `--fullscreen`. Dashes and underscores in names are the same. Arguments that are not
+    /// settings are skipped, so they can be shared with the arguments of the application.
+    /// See [`EngineSettings::set_option`] for the names
+    pub fn apply_args(
+        &mut self,
+        args: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Result<(), Error> {
+        let args = args
+            .into_iter()
+            .map(|i| i.as_ref().to_string())
+            .collect::<Vec<_>>();
+
+        let mut index = 0;
+        while index < args.len() {
+            let arg = &args[index];
+            index += 1;
+            let Some(arg) = arg.strip_prefix("--") else {
+                continue;
+            };
+            let (key, value) = match arg.split_once('=') {
+                Some((key, value)) => (key.replace('-', "_"), Some(value.to_string())),
+                None => (arg.replace('-', "_"), None),
+            };
+
+            match (key.as_str(), value) {
+                ("windowed", None) => {
+                    self.set_option("fullscreen", "false")?;
+                }
+                ("fullscreen", None) => {
+                    self.set_option("fullscreen", "true")?;
+                }
+                (key, Some(value)) => {
+                    self.set_option(key, &value)?;
+                }
+                (key, None) if OPTIONS.contains(&key) => {
+                    let Some(value) = args.get(index) else {
+                        return Err(Error::Custom(format!(
+                            "The `--{key}` argument needs a value"
+                        )));
+                    };
+                    index += 1;
+                    self.set_option(key, value)?;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets a setting from its text value. Returns false if there is no setting of the name.
+    ///
+    /// The names are `width`, `height`, `resolution` (such as `1280x720`), `title`,
+    /// `run_mode` (`windowed`, `headless` or `embedded`), `present_mode` (`vsync`,
+    /// `no_vsync`, `fifo`, `fifo_relaxed`, `immediate` or `mailbox`), `backend` (a comma
+    /// separated list such as `vulkan,gl`, or `all` or `primary`), `msaa` (the sample count),
+    /// `fullscreen`, `fps_cap` (a number or `none`), `power_preference` (`low`, `high` or
+    /// `none`) and `adapter` (a part of the adapter name).
+    pub fn set_option(&mut self, key: &str, value: &str) -> Result<bool, Error> {
+        let value = value.trim();
+        let invalid = || Error::Custom(format!("Invalid value `{value}` for the `{key}` setting"));
+        let number = || value.parse::<u32>().map_err(|_| invalid());
+
+        match key {
+            "width" => self.width = number()?,
+            "height" => self.height = number()?,
+            "resolution" => {
+                let (width, height) = value
+                    .split_once(['x', 'X'])
+                    .and_then(|(w, h)| Some((w.trim().parse().ok()?, h.trim().parse().ok()?)))
+                    .ok_or_else(invalid)?;
+                self.width = width;
+                self.height = height;
+            }
+            "title" => self.title = value.to_string(),
+            "run_mode" => {
+                self.run_mode = match normalize(value).as_str() {
+                    "windowed" => crate::RunMode::Windowed,
+                    "headless" => crate::RunMode::Headless,
+                    "embedded" => crate::RunMode::Embedded,
+                    _ => return Err(invalid()),
+                }
+            }
+            "present_mode" => {
+                self.present_mode = match normalize(value).as_str() {
+                    "vsync" | "auto_vsync" => wgpu::PresentMode::AutoVsync,
+                    "no_vsync" | "auto_no_vsync" => wgpu::PresentMode::AutoNoVsync,
+                    "fifo" => wgpu::PresentMode::Fifo,
+                    "fifo_relaxed" => wgpu::PresentMode::FifoRelaxed,
+                    "immediate" => wgpu::PresentMode::Immediate,
+                    "mailbox" => wgpu::PresentMode::Mailbox,
+                    _ => return Err(invalid()),
+                }
+            }
+            "backend" | "backends" => {
+                self.backends = backends_list::parse(value).ok_or_else(invalid)?;
+            }
+            "msaa" | "msaa_samples" => {
+                let samples = number()?;
+                if !samples.is_power_of_two() || samples > 16 {
+                    return Err(invalid());
+                }
+                self.msaa_samples = samples;
+            }
+            #[cfg(all(feature = "window", not(feature = "headless")))]
+            "fullscreen" => self.fullscreen = parse_bool(value).ok_or_else(invalid)?,
+            "fps_cap" => {
+                self.fps_cap = match normalize(value).as_str() {
+                    "none" | "off" | "0" => None,
+                    _ => Some(
+                        value
+                            .parse::<f32>()
+                            .ok()
+                            .filter(|i| {
+                                // the frame time of the cap has to fit in a duration
+                                i.is_finite()
+                                    && *i > 0.0
+                                    && std::time::Duration::try_from_secs_f32(1.0 / i).is_ok()
+                            })
+                            .ok_or_else(invalid)?,
+                    ),
+                }
+            }
+            "power_preference" => {
+                self.power_preference = match normalize(value).as_str() {
+                    "low" | "low_power" => wgpu::PowerPreference::LowPower,
+                    "high" | "high_performance" => wgpu::PowerPreference::HighPerformance,
+                    "none" => wgpu::PowerPreference::None,
+                    _ => return Err(invalid()),
+                }
+            }
+            "adapter" => {
+                self.adapter =
+                    crate::utils::adapter_selection::AdapterSelection::Name(value.to_string())
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+fn is_ron(path: &std::path::Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("ron"))
+}
+
+fn normalize(value: &str) -> String {
+    value.trim().to_lowercase().replace('-', "_")
+}
+
+#[cfg(all(feature = "window", not(feature = "headless")))]
+fn parse_bool(value: &str) -> Option<bool> {
+    match normalize(value).as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// Serializes [`wgpu::Backends`] as a comma separated list of backend names
+pub(crate) mod backends_list {
+    const NAMES: &[(wgpu::Backends, &str)] = &[
+        (wgpu::Backends::VULKAN, "vulkan"),
+        (wgpu::Backends::DX12, "dx12"),
+        (wgpu::Backends::METAL, "metal"),
+        (wgpu::Backends::GL, "gl"),
+        (wgpu::Backends::BROWSER_WEBGPU, "webgpu"),
+        (wgpu::Backends::NOOP, "noop"),
+    ];
+
+    /// Parses a comma separated list of backend names, or `all` or `primary`
+    pub(crate) fn parse(value: &str) -> Option<wgpu::Backends> {
+        let backends = match value.trim().to_lowercase().as_str() {
+            "all" => wgpu::Backends::all(),
+            "primary" => wgpu::Backends::PRIMARY,
+            "secondary" => wgpu::Backends::SECONDARY,
+            list => list
+                .split(',')
+                .map(|name| {
+                    NAMES
+                        .iter()
+                        .find(|i| i.1 == name.trim())
+                        .map(|i| i.0)
+                        .or_else(|| {
+                            let backend = wgpu::Backends::from_comma_list(name);
+                            (!backend.is_empty()).then_some(backend)
+                        })
+                })
+                .try_fold(wgpu::Backends::empty(), |backends, backend| {
+                    Some(backends | backend?)
+                })?,
+        };
+        (!backends.is_empty()).then_some(backends)
+    }
+
+    pub(crate) fn serialize<S: serde::Serializer>(
+        backends: &wgpu::Backends,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        if *backends == wgpu::Backends::all() {
+            return serializer.serialize_str("all");
+        }
+        let names = NAMES
+            .iter()
+            .filter(|i| backends.contains(i.0))
+            .map(|i| i.1)
+            .collect::<Vec<_>>();
+        serializer.serialize_str(&names.join(","))
+    }
+
+    pub(crate) fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<wgpu::Backends, D::Error> {
+        let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+        parse(&value)
+            .ok_or_else(|| serde::de::Error::custom(format!("Invalid backend list `{value}`")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let settings = EngineSettings {
+            title: "Round trip".to_string(),
+            backends: wgpu::Backends::VULKAN | wgpu::Backends::GL,
+            fps_cap: Some(30.0),
+            msaa_samples: 4,
+            ..Default::default()
+        };
+
+        let toml = settings.to_toml().unwrap();
+        let parsed = EngineSettings::from_toml(&toml).unwrap();
+        assert_eq!(parsed.to_toml().unwrap(), toml);
+        assert_eq!(parsed.title, "Round trip");
+        assert_eq!(parsed.backends, wgpu::Backends::VULKAN | wgpu::Backends::GL);
+
+        let ron = settings.to_ron().unwrap();
+        let parsed = EngineSettings::from_ron(&ron).unwrap();
+        assert_eq!(parsed.to_ron().unwrap(), ron);
+        assert_eq!(parsed.fps_cap, Some(30.0));
+        assert_eq!(parsed.msaa_samples, 4);
+
+        let defaults = EngineSettings::default().to_toml().unwrap();
+        assert_eq!(
+            EngineSettings::from_toml(&defaults)
+                .unwrap()
+                .to_toml()
+                .unwrap(),
+            defaults
+        );
+    }
+
+    #[test]
+    fn missing_fields_keep_defaults() {
+        let settings = EngineSettings::from_toml("width = 1280").unwrap();
+        assert_eq!(settings.width, 1280);
+        assert_eq!(settings.height, EngineSettings::default().height);
+    }
+
+    #[test]
+    fn args() {
+        let mut settings = EngineSettings::default();
+        settings
+            .apply_args([
+                "--resolution",
+                "1280x720",
+                "--msaa=4",
+                "--present-mode",
+                "fifo",
+                "--fps-cap=none",
+                "--backends=vulkan,gl",
+            ])
+            .unwrap();
+        assert_eq!((settings.width, settings.height), (1280, 720));
+        assert_eq!(settings.msaa_samples, 4);
+        assert_eq!(settings.present_mode, wgpu::PresentMode::Fifo);
+        assert_eq!(settings.fps_cap, None);
+        assert_eq!(
+            settings.backends,
+            wgpu::Backends::VULKAN | wgpu::Backends::GL
+        );
+    }
+
+    #[test]
+    fn unknown_args_are_skipped() {
+        let mut settings = EngineSettings::default();
+        settings
+            .apply_args(["game.bin", "--verbose", "--level=2", "-w", "--width", "640"])
+            .unwrap();
+        assert_eq!(settings.width, 640);
+    }
+
+    #[test]
+    fn invalid_values() {
+        let mut settings = EngineSettings::default();
+        assert!(settings.apply_args(["--msaa=3"]).is_err());
+        assert!(settings.apply_args(["--resolution=1280"]).is_err());
+        assert!(
+            settings
+                .apply_args(["--present-mode", "sometimes"])
+                .is_err()
+        );
+        assert!(settings.apply_args(["--fps-cap=-1"]).is_err());
+        assert!(settings.apply_args(["--fps-cap=1e-39"]).is_err());
+        assert!(settings.apply_args(["--fps-cap=inf"]).is_err());
+        assert!(settings.apply_args(["--backend=glide"]).is_err());
+        assert!(settings.apply_args(["--width"]).is_err());
+        assert_eq!(settings.msaa_samples, 1);
+    }
+
+    #[cfg(all(feature = "window", not(feature = "headless")))]
+    #[test]
+    fn fullscreen_flags() {
+        let mut settings = EngineSettings::default();
+        settings.apply_args(["--fullscreen"]).unwrap();
+        assert!(settings.fullscreen);
+        settings.apply_args(["--windowed"]).unwrap();
+        assert!(!settings.fullscreen);
+        settings.apply_args(["--fullscreen=yes"]).unwrap();
+        assert!(settings.fullscreen);
+        assert!(settings.apply_args(["--fullscreen=maybe"]).is_err());
+    }
+}
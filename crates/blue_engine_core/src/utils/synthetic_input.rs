@@ -0,0 +1,299 @@
+/*
+ * Blue Engine by Elham Aryanpur
+ *
+ * The license is same as the one on the root.
+*/
+
+use super::input_event::InputEvent;
+use std::collections::VecDeque;
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, TouchPhase};
+use winit::keyboard::{Key, KeyCode, NamedKey, NativeKey, NativeKeyCode, PhysicalKey};
+
+/// Input events injected by code instead of a real user.
+///
+/// Events are queued per frame, and the engine gives the events of each frame to
+/// `simple_input` right before the update loop runs, so the usual queries such as
+/// `engine.simple_input.key_pressed(KeyCode::Space)` work the same as with real input.
+/// This works in both windowed and headless mode.
+#[derive(Debug, Clone, Default)]
+pub struct SyntheticInput {
+    // index 0 is the next frame
+    frames: VecDeque<Vec<InputEvent>>,
+}
+
+impl SyntheticInput {
+    /// Creates an empty queue
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues an event for the next frame
+    pub fn push(&mut self, event: InputEvent) {
+        self.push_at(0, event);
+    }
+
+    /// Queues an event for the given amount of frames after the next frame
+    pub fn push_at(&mut self, frames_from_next: usize, event: InputEvent) {
+        if self.frames.len() <= frames_from_next {
+            self.frames.resize_with(frames_from_next + 1, Vec::new);
+        }
+        self.frames[frames_from_next].push(event);
+    }
+
+    /// Returns true if there are no more queued events
+    pub fn is_empty(&self) -> bool {
+        self.frames.iter().all(|i| i.is_empty())
+    }
+
+    /// Removes all queued events
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Takes the events of the next frame
+    pub fn next_frame(&mut self) -> Vec<InputEvent> {
+        self.frames.pop_front().unwrap_or_default()
+    }
+
+    /// Presses a key on the next frame, and keeps it held until released
+    pub fn press_key(&mut self, key: KeyCode) {
+        self.push(keyboard_event(key, ElementState::Pressed));
+    }
+
+    /// Releases a key on the next frame
+    pub fn release_key(&mut self, key: KeyCode) {
+        self.push(keyboard_event(key, ElementState::Released));
+    }
+
+    /// Presses a key on the next frame and releases it on the frame after
+    pub fn tap_key(&mut self, key: KeyCode) {
+        self.push(keyboard_event(key, ElementState::Pressed));
+        self.push_at(1, keyboard_event(key, ElementState::Released));
+    }
+
+    /// Types the text on the next frame, as if each character was pressed and released
+    pub fn type_text(&mut self, text: impl AsRef<str>) {
+        for character in text.as_ref().chars() {
+            for state in [ElementState::Pressed, ElementState::Released] {
+                self.push(InputEvent::Keyboard {
+                    physical_key: PhysicalKey::Unidentified(NativeKeyCode::Unidentified),
+                    logical_key: Key::Character(character.to_string().into()),
+                    state,
+                });
+            }
+        }
+    }
+
+    /// Moves the cursor to the position in pixels on the next frame
+    pub fn move_cursor(&mut self, x: f32, y: f32) {
+        self.push(InputEvent::CursorMoved {
+            x: x as f64,
+            y: y as f64,
+        });
+    }
+
+    /// Moves the mouse by the amount on the next frame, as a raw device movement
+    pub fn move_mouse(&mut self, delta_x: f32, delta_y: f32) {
+        self.push(InputEvent::MouseMotion {
+            x: delta_x as f64,
+            y: delta_y as f64,
+        });
+    }
+
+    /// Presses a mouse button on the next frame, and keeps it held until released
+    pub fn press_mouse(&mut self, button: MouseButton) {
+        self.push(InputEvent::MouseInput {
+            button,
+            state: ElementState::Pressed,
+        });
+    }
+
+    /// Releases a mouse button on the next frame
+    pub fn release_mouse(&mut self, button: MouseButton) {
+        self.push(InputEvent::MouseInput {
+            button,
+            state: ElementState::Released,
+        });
+    }
+
+    /// Presses a mouse button on the next frame and releases it on the frame after
+    pub fn click(&mut self, button: MouseButton) {
+        self.press_mouse(button);
+        self.push_at(
+            1,
+            InputEvent::MouseInput {
+                button,
+                state: ElementState::Released,
+            },
+        );
+    }
+
+    /// Scrolls the mouse wheel by the amount of lines on the next frame
+    pub fn scroll(&mut self, x: f32, y: f32) {
+        self.push(InputEvent::MouseWheel {
+            delta: MouseScrollDelta::LineDelta(x, y),
+        });
+    }
+
+    /// Sends a touch event of a finger on the next frame
+    pub fn touch(&mut self, id: u64, phase: TouchPhase, x: f32, y: f32) {
+        self.push(InputEvent::Touch {
+            id,
+            phase,
+            x: x as f64,
+            y: y as f64,
+            force: None,
+        });
+    }
+}
+
+/// Creates a keyboard event for the key, with the logical key of a US layout
+pub fn keyboard_event(key: KeyCode, state: ElementState) -> InputEvent {
+    InputEvent::Keyboard {
+        physical_key: PhysicalKey::Code(key),
+        logical_key: logical_key(key),
+        state,
+    }
+}
+
+/// Returns the logical key of the key code on a US layout, for the common keys
+fn logical_key(key: KeyCode) -> Key {
+    let character = match key {
+        KeyCode::KeyA => "a",
+        KeyCode::KeyB => "b",
+        KeyCode::KeyC => "c",
+        KeyCode::KeyD => "d",
+        KeyCode::KeyE => "e",
+        KeyCode::KeyF => "f",
+        KeyCode::KeyG => "g",
+        KeyCode::KeyH => "h",
+        KeyCode::KeyI => "i",
+        KeyCode::KeyJ => "j",
+        KeyCode::KeyK => "k",
+        KeyCode::KeyL => "l",
+        KeyCode::KeyM => "m",
+        KeyCode::KeyN => "n",
+        KeyCode::KeyO => "o",
+        KeyCode::KeyP => "p",
+        KeyCode::KeyQ => "q",
+        KeyCode::KeyR => "r",
+        KeyCode::KeyS => "s",
+        KeyCode::KeyT => "t",
+        KeyCode::KeyU => "u",
+        KeyCode::KeyV => "v",
+        KeyCode::KeyW => "w",
+        KeyCode::KeyX => "x",
+        KeyCode::KeyY => "y",
+        KeyCode::KeyZ => "z",
+        KeyCode::Digit0 => "0",
+        KeyCode::Digit1 => "1",
+        KeyCode::Digit2 => "2",
+        KeyCode::Digit3 => "3",
+        KeyCode::Digit4 => "4",
+        KeyCode::Digit5 => "5",
+        KeyCode::Digit6 => "6",
+        KeyCode::Digit7 => "7",
+        KeyCode::Digit8 => "8",
+        KeyCode::Digit9 => "9",
+        _ => {
+            let named = match key {
+                KeyCode::Space => NamedKey::Space,
+                KeyCode::Enter => NamedKey::Enter,
+                KeyCode::Escape => NamedKey::Escape,
+                KeyCode::Tab => NamedKey::Tab,
+                KeyCode::Backspace => NamedKey::Backspace,
+                KeyCode::ArrowUp => NamedKey::ArrowUp,
+                KeyCode::ArrowDown => NamedKey::ArrowDown,
+                KeyCode::ArrowLeft => NamedKey::ArrowLeft,
+                KeyCode::ArrowRight => NamedKey::ArrowRight,
+                KeyCode::ShiftLeft | KeyCode::ShiftRight => NamedKey::Shift,
+                KeyCode::ControlLeft | KeyCode::ControlRight => NamedKey::Control,
+                KeyCode::AltLeft | KeyCode::AltRight => NamedKey::Alt,
+                _ => return Key::Unidentified(NativeKey::Unidentified),
+            };
+            return Key::Named(named);
+        }
+    };
+    Key::Character(character.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::event::MouseButton;
+    use winit::keyboard::{Key, KeyCode};
+
+    #[test]
+    fn keys_per_frame() {
+        let mut engine = crate::utils::headless_test_engine();
+        engine.synthetic_input.tap_key(KeyCode::Space);
+        engine.synthetic_input.push_at(
+            2,
+            super::keyboard_event(KeyCode::KeyW, winit::event::ElementState::Pressed),
+        );
+
+        engine.step().unwrap();
+        assert!(engine.simple_input.key_pressed(KeyCode::Space));
+        assert!(engine.simple_input.key_held(KeyCode::Space));
+        assert!(
+            engine
+                .simple_input
+                .key_pressed_logical(Key::Named(winit::keyboard::NamedKey::Space))
+        );
+
+        engine.step().unwrap();
+        assert!(engine.simple_input.key_released(KeyCode::Space));
+        assert!(!engine.simple_input.key_held(KeyCode::Space));
+        assert!(!engine.simple_input.key_pressed(KeyCode::KeyW));
+
+        engine.step().unwrap();
+        assert!(engine.simple_input.key_pressed(KeyCode::KeyW));
+        assert!(engine.synthetic_input.is_empty());
+
+        engine.step().unwrap();
+        assert!(!engine.simple_input.key_pressed(KeyCode::KeyW));
+        assert!(engine.simple_input.key_held(KeyCode::KeyW));
+    }
+
+    #[test]
+    fn mouse_and_clicks() {
+        let mut engine = crate::utils::headless_test_engine();
+        engine.synthetic_input.move_cursor(10.0, 12.0);
+        engine.synthetic_input.click(MouseButton::Left);
+        engine.synthetic_input.scroll(0.0, 2.0);
+
+        engine.step().unwrap();
+        assert_eq!(engine.simple_input.cursor(), Some((10.0, 12.0)));
+        assert!(engine.simple_input.mouse_pressed(MouseButton::Left));
+        assert!(engine.simple_input.mouse_held(MouseButton::Left));
+        assert!(!engine.simple_input.mouse_pressed(MouseButton::Right));
+        assert_eq!(engine.simple_input.scroll_diff(), (0.0, 2.0));
+
+        engine.synthetic_input.move_cursor(15.0, 10.0);
+        engine.synthetic_input.move_mouse(3.0, -1.0);
+        engine.step().unwrap();
+        assert!(engine.simple_input.mouse_released(MouseButton::Left));
+        assert!(!engine.simple_input.mouse_held(MouseButton::Left));
+        assert_eq!(engine.simple_input.cursor_diff(), (5.0, -2.0));
+        assert_eq!(engine.simple_input.mouse_diff(), (3.0, -1.0));
+        assert_eq!(engine.simple_input.scroll_diff(), (0.0, 0.0));
+    }
+
+    #[test]
+    fn text() {
+        let mut engine = crate::utils::headless_test_engine();
+        engine.synthetic_input.type_text("hi!");
+
+        engine.step().unwrap();
+        assert_eq!(
+            engine.simple_input.text(),
+            &[
+                Key::Character("h".into()),
+                Key::Character("i".into()),
+                Key::Character("!".into())
+            ]
+        );
+
+        engine.step().unwrap();
+        assert!(engine.simple_input.text().is_empty());
+    }
+}
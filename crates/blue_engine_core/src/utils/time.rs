@@ -0,0 +1,335 @@
+/*
+ * Blue Engine by Elham Aryanpur
+ *
+ * The license is same as the one on the root.
+*/
+
+use std::time::{Duration, Instant};
+
+/// Where [`Time`] reads the current time from
+#[derive(Debug, Clone, Copy)]
+pub enum Clock {
+    /// The system clock
+    System {
+        /// The moment the clock was created
+        start: Instant,
+    },
+    /// A clock that only moves when advanced by hand, for tests and deterministic runs
+    Manual {
+        /// The time since the clock was created
+        now: Duration,
+    },
+}
+
+impl Clock {
+    /// Returns the time since the clock was created
+    pub fn now(&self) -> Duration {
+        match self {
+            Self::System { start } => start.elapsed(),
+            Self::Manual { now } => *now,
+        }
+    }
+}
+
+/// Frame timing of the engine.
+///
+/// The engine ticks it once at the start of every frame. There are two timelines:
+/// the real time which always follows the clock, and the game time which stops while
+/// paused and is multiplied by the time scale. Fixed updates, timers and anything
+/// simulated should follow the game time.
+#[derive(Debug, Clone)]
+pub struct Time {
+    clock: Clock,
+    last_tick: Option<Duration>,
+    real_delta: Duration,
+    real_elapsed: Duration,
+    delta: Duration,
+    elapsed: Duration,
+    frame_count: u64,
+    paused: bool,
+    time_scale: f32,
+    fps_cap: Option<f32>,
+}
+
+impl Time {
+    /// Creates a timer that follows the system clock
+    pub fn new() -> Self {
+        Self::with_clock(Clock::System {
+            start: Instant::now(),
+        })
+    }
+
+    /// Creates a timer with a clock that only moves through [`Time::advance`]
+    pub fn manual() -> Self {
+        Self::with_clock(Clock::Manual {
+            now: Duration::ZERO,
+        })
+    }
+
+    /// Creates a timer that reads from the given clock
+    pub fn with_clock(clock: Clock) -> Self {
+        Self {
+            clock,
+            last_tick: None,
+            real_delta: Duration::ZERO,
+            real_elapsed: Duration::ZERO,
+            delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            frame_count: 0,
+            paused: false,
+            time_scale: 1.0,
+            fps_cap: None,
+        }
+    }
+
+    /// Moves a manual clock forward. Does nothing for the system clock
+    pub fn advance(&mut self, by: Duration) {
+        if let Clock::Manual { now } = &mut self.clock {
+            *now += by;
+        }
+    }
+
+    /// Returns the clock the timer reads from
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// Starts a new frame, measuring the time since the last one.
+    ///
+    /// This is called by the engine at the start of every frame and should not be needed
+    /// unless you are driving the frames yourself.
+    pub fn tick(&mut self) {
+        let now = self.clock.now();
+        self.real_delta = self
+            .last_tick
+            .map(|last| now.saturating_sub(last))
+            .unwrap_or_default();
+        self.last_tick = Some(now);
+        self.real_elapsed += self.real_delta;
+
+        self.delta = if self.paused {
+            Duration::ZERO
+        } else {
+            // scales too large for a duration saturate instead of panicking
+            Duration::try_from_secs_f64(self.real_delta.as_secs_f64() * self.time_scale as f64)
+                .unwrap_or(Duration::MAX)
+        };
+        self.elapsed = self.elapsed.saturating_add(self.delta);
+        self.frame_count += 1;
+    }
+
+    /// Returns the game time that passed during the last frame
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// Returns the game time that passed during the last frame in seconds
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    /// Returns the game time since the engine started
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns the game time since the engine started in seconds
+    pub fn elapsed_seconds(&self) -> f64 {
+        self.elapsed.as_secs_f64()
+    }
+
+    /// Returns the real time that passed during the last frame, ignoring pause and time scale
+    pub fn real_delta(&self) -> Duration {
+        self.real_delta
+    }
+
+    /// Returns the real time since the engine started, ignoring pause and time scale
+    pub fn real_elapsed(&self) -> Duration {
+        self.real_elapsed
+    }
+
+    /// Returns the amount of frames since the engine started
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    /// Returns the frames per second based on the last frame
+    pub fn fps(&self) -> f32 {
+        if self.real_delta.is_zero() {
+            0.0
+        } else {
+            1.0 / self.real_delta.as_secs_f32()
+        }
+    }
+
+    /// Stops the game time
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Continues the game time
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Returns true if the game time is stopped
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Sets how fast the game time moves compared to the real time, where 1.0 is normal speed.
+    /// Infinite and NaN scales are ignored, and the game time of a frame saturates at the
+    /// largest duration
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        if !time_scale.is_finite() {
+            log::warn!("Ignoring the time scale {time_scale}, it has to be finite");
+            return;
+        }
+        self.time_scale = time_scale.max(0.0);
+    }
+
+    /// Returns how fast the game time moves compared to the real time
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// Limits the amount of frames per second, regardless of the present mode.
+    /// `None` removes the limit, and so do caps that are not positive. Caps that are not
+    /// finite, or too small to give a frame time, are ignored
+    pub fn set_fps_cap(&mut self, fps_cap: Option<f32>) {
+        match fps_cap {
+            Some(fps) if !fps.is_finite() || (fps > 0.0 && frame_time(fps).is_none()) => {
+                log::warn!("Ignoring the fps cap {fps}, it has to give a finite frame time");
+            }
+            fps_cap => self.fps_cap = fps_cap.filter(|fps| *fps > 0.0),
+        }
+    }
+
+    /// Returns the frames per second limit, if any
+    pub fn fps_cap(&self) -> Option<f32> {
+        self.fps_cap
+    }
+
+    /// Sleeps for the rest of the frame if the frame finished sooner than the FPS cap allows.
+    /// A manual clock is never waited on.
+    #[cfg(any(feature = "window", feature = "headless"))]
+    pub(crate) fn limit_frame_rate(&self) {
+        if let (Some(fps_cap), Some(last_tick), Clock::System { .. }) =
+            (self.fps_cap, self.last_tick, self.clock)
+        {
+            let frame_time = frame_time(fps_cap).unwrap_or_default();
+            let spent = self.clock.now().saturating_sub(last_tick);
+            if spent < frame_time {
+                std::thread::sleep(frame_time - spent);
+            }
+        }
+    }
+}
+
+/// Returns the time of one frame at the frames per second, if it fits in a [`Duration`]
+fn frame_time(fps: f32) -> Option<Duration> {
+    Duration::try_from_secs_f32(1.0 / fps).ok()
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manual_clock() {
+        let mut time = Time::manual();
+        time.tick();
+        assert_eq!(time.delta(), Duration::ZERO);
+        assert_eq!(time.frame_count(), 1);
+
+        time.advance(Duration::from_millis(16));
+        time.tick();
+        assert_eq!(time.delta(), Duration::from_millis(16));
+        assert_eq!(time.real_delta(), Duration::from_millis(16));
+        assert!((time.fps() - 62.5).abs() < 1e-3);
+
+        // without advancing, no time passes
+        time.tick();
+        assert_eq!(time.delta(), Duration::ZERO);
+        assert_eq!(time.fps(), 0.0);
+        assert_eq!(time.elapsed(), Duration::from_millis(16));
+        assert_eq!(time.frame_count(), 3);
+    }
+
+    #[test]
+    fn system_clock_is_not_advanced() {
+        let mut time = Time::new();
+        let before = time.clock().now();
+        time.advance(Duration::from_secs(60));
+        assert!(time.clock().now() - before < Duration::from_secs(60));
+    }
+
+    #[test]
+    fn pause_and_time_scale() {
+        let mut time = Time::manual();
+        time.tick();
+
+        time.set_time_scale(0.5);
+        time.advance(Duration::from_millis(20));
+        time.tick();
+        assert_eq!(time.delta(), Duration::from_millis(10));
+        assert_eq!(time.real_delta(), Duration::from_millis(20));
+
+        time.pause();
+        time.advance(Duration::from_millis(20));
+        time.tick();
+        assert!(time.is_paused());
+        assert_eq!(time.delta(), Duration::ZERO);
+        assert_eq!(time.elapsed(), Duration::from_millis(10));
+        assert_eq!(time.real_elapsed(), Duration::from_millis(40));
+
+        time.resume();
+        time.set_time_scale(-1.0);
+        time.advance(Duration::from_millis(20));
+        time.tick();
+        assert_eq!(time.time_scale(), 0.0);
+        assert_eq!(time.delta(), Duration::ZERO);
+    }
+
+    #[test]
+    fn non_finite_time_scale_is_ignored() {
+        let mut time = Time::manual();
+        time.set_time_scale(2.0);
+        time.set_time_scale(f32::INFINITY);
+        time.set_time_scale(f32::NAN);
+        assert_eq!(time.time_scale(), 2.0);
+
+        time.tick();
+        time.advance(Duration::from_millis(5));
+        time.tick();
+        assert_eq!(time.delta(), Duration::from_millis(10));
+
+        time.set_time_scale(f32::MAX);
+        time.advance(Duration::from_millis(5));
+        time.tick();
+        assert_eq!(time.delta(), Duration::MAX);
+        time.advance(Duration::from_millis(5));
+        time.tick();
+        assert_eq!(time.elapsed(), Duration::MAX);
+    }
+
+    #[test]
+    fn fps_cap() {
+        let mut time = Time::manual();
+        time.set_fps_cap(Some(60.0));
+        assert_eq!(time.fps_cap(), Some(60.0));
+        time.set_fps_cap(Some(1e-39));
+        time.set_fps_cap(Some(f32::NAN));
+        assert_eq!(time.fps_cap(), Some(60.0));
+        time.set_fps_cap(Some(f32::INFINITY));
+        assert_eq!(time.fps_cap(), Some(60.0));
+        time.set_fps_cap(Some(0.0));
+        assert_eq!(time.fps_cap(), None);
+    }
+}
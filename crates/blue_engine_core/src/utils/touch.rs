@@ -0,0 +1,511 @@
+/*
+ * Blue Engine by Elham Aryanpur
+ *
+ * The license is same as the one on the root.
+*/
+
+use std::collections::HashMap;
+use std::time::Duration;
+pub use winit::event::TouchPhase;
+
+/// A single finger on the touch screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TouchPoint {
+    /// Unique identifier of the finger, as long as it stays on the screen
+    pub id: u64,
+    /// The current position in pixels
+    pub position: (f32, f32),
+    /// The position at the start of the current step
+    pub previous_position: (f32, f32),
+    /// The position where the finger first touched the screen
+    pub start_position: (f32, f32),
+    /// The latest phase of the touch
+    pub phase: TouchPhase,
+    /// Normalized pressure between 0 and 1, if the platform supports it
+    pub force: Option<f32>,
+    /// The time the finger first touched the screen, on the clock of the input helper
+    /// (the real time of the engine)
+    pub started_at: Duration,
+    /// Whether the finger touched the screen during the current step
+    pub started_this_step: bool,
+}
+impl TouchPoint {
+    /// Returns the movement of the finger during the current step
+    pub fn delta(&self) -> (f32, f32) {
+        (
+            self.position.0 - self.previous_position.0,
+            self.position.1 - self.previous_position.1,
+        )
+    }
+
+    /// Returns the movement of the finger since it touched the screen
+    pub fn total_delta(&self) -> (f32, f32) {
+        (
+            self.position.0 - self.start_position.0,
+            self.position.1 - self.start_position.1,
+        )
+    }
+
+    /// Returns true if the finger was lifted or the touch was cancelled during the current step
+    pub fn ended(&self) -> bool {
+        matches!(self.phase, TouchPhase::Ended | TouchPhase::Cancelled)
+    }
+}
+
+/// Gestures recognized from the touch input
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gesture {
+    /// A short touch and release without movement
+    Tap {
+        /// Where the tap happened
+        position: (f32, f32),
+    },
+    /// Two taps in quick succession at the same place
+    DoubleTap {
+        /// Where the second tap happened
+        position: (f32, f32),
+    },
+    /// A touch held in place for a while. Reported once per touch
+    LongPress {
+        /// Where the finger is held
+        position: (f32, f32),
+    },
+    /// A single finger dragged across the screen
+    Pan {
+        /// The current position of the finger
+        position: (f32, f32),
+        /// The movement during the current step
+        delta: (f32, f32),
+    },
+    /// Two fingers moving closer or further apart
+    Pinch {
+        /// The point between the two fingers
+        center: (f32, f32),
+        /// The change of distance between the fingers during the current step.
+        /// Above 1 is zooming in, below 1 is zooming out
+        scale: f32,
+    },
+    /// Two fingers rotating around each other
+    Rotate {
+        /// The point between the two fingers
+        center: (f32, f32),
+        /// The change of angle in radians during the current step, clockwise on screen
+        angle: f32,
+    },
+}
+
+/// Thresholds used for recognizing gestures
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GestureSettings {
+    /// The longest a finger can stay on the screen to count as a tap
+    pub tap_max_duration: Duration,
+    /// The furthest in pixels a finger can move to still count as a tap or long press
+    pub tap_max_distance: f32,
+    /// The longest time between two taps to count as a double tap
+    pub double_tap_interval: Duration,
+    /// How long a finger must be held in place to count as a long press
+    pub long_press_duration: Duration,
+}
+impl Default for GestureSettings {
+    fn default() -> Self {
+        Self {
+            tap_max_duration: Duration::from_millis(250),
+            tap_max_distance: 10.0,
+            double_tap_interval: Duration::from_millis(300),
+            long_press_duration: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Turns the raw touch events into [`Gesture`]s
+#[derive(Debug, Clone, Default)]
+pub struct GestureRecognizer {
+    /// Thresholds used for recognizing gestures
+    pub settings: GestureSettings,
+    gestures: Vec<Gesture>,
+    last_tap: Option<(Duration, (f32, f32))>,
+    long_press_fired: bool,
+    panning: bool,
+    // more than one finger was used since the screen was last untouched
+    multi_touch: bool,
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+fn center(a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+    ((a.0 + b.0) * 0.5, (a.1 + b.1) * 0.5)
+}
+
+fn angle(a: (f32, f32), b: (f32, f32)) -> f32 {
+    (b.1 - a.1).atan2(b.0 - a.0)
+}
+
+impl GestureRecognizer {
+    /// Creates a recognizer with the given thresholds
+    pub fn new(settings: GestureSettings) -> Self {
+        Self {
+            settings,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the gestures recognized during the current step
+    pub fn gestures(&self) -> &[Gesture] {
+        &self.gestures
+    }
+
+    /// Starts a new step at the time `now`. Checks for long presses of the fingers still on
+    /// the screen.
+    pub fn step(&mut self, touches: &HashMap<u64, TouchPoint>, now: Duration) {
+        self.gestures.clear();
+
+        let mut active = touches.values().filter(|i| !i.ended());
+        if let (Some(touch), None) = (active.next(), active.next())
+            && !self.long_press_fired
+            && !self.panning
+            && !self.multi_touch
+            && now.saturating_sub(touch.started_at) >= self.settings.long_press_duration
+            && distance(touch.position, touch.start_position) <= self.settings.tap_max_distance
+        {
+            self.long_press_fired = true;
+            self.gestures.push(Gesture::LongPress {
+                position: touch.position,
+            });
+        }
+    }
+
+    /// Handles a touch event of the finger with the given id and position, at the time `now`.
+    /// The `touches` are the known fingers before the event is applied.
+    pub fn handle_touch(
+        &mut self,
+        id: u64,
+        phase: TouchPhase,
+        position: (f32, f32),
+        touches: &HashMap<u64, TouchPoint>,
+        now: Duration,
+    ) {
+        let others = touches
+            .values()
+            .filter(|i| i.id != id && !i.ended())
+            .collect::<Vec<_>>();
+
+        match phase {
+            TouchPhase::Started => {
+                if others.is_empty() {
+                    self.long_press_fired = false;
+                    self.panning = false;
+                    self.multi_touch = false;
+                } else {
+                    self.multi_touch = true;
+                }
+            }
+            TouchPhase::Moved => {
+                let Some(previous) = touches.get(&id) else {
+                    return;
+                };
+
+                match others.as_slice() {
+                    [] if !self.multi_touch
+                        && (self.panning
+                            || distance(position, previous.start_position)
+                                > self.settings.tap_max_distance) =>
+                    {
+                        self.panning = true;
+                        let delta = (
+                            position.0 - previous.position.0,
+                            position.1 - previous.position.1,
+                        );
+                        self.push_pan(position, delta);
+                    }
+                    [other] => {
+                        let old_distance = distance(previous.position, other.position);
+                        let new_distance = distance(position, other.position);
+                        let new_center = center(position, other.position);
+                        if old_distance > f32::EPSILON && new_distance > f32::EPSILON {
+                            self.push_pinch(new_center, new_distance / old_distance);
+                        }
+
+                        let mut angle_delta = angle(other.position, position)
+                            - angle(other.position, previous.position);
+                        // keep the delta in -PI..PI when crossing the atan2 boundary
+                        if angle_delta > std::f32::consts::PI {
+                            angle_delta -= std::f32::consts::TAU;
+                        } else if angle_delta < -std::f32::consts::PI {
+                            angle_delta += std::f32::consts::TAU;
+                        }
+                        self.push_rotate(new_center, angle_delta);
+                    }
+                    _ => {}
+                }
+            }
+            TouchPhase::Ended => {
+                let Some(previous) = touches.get(&id) else {
+                    return;
+                };
+
+                if others.is_empty()
+                    && !self.multi_touch
+                    && !self.long_press_fired
+                    && !self.panning
+                    && now.saturating_sub(previous.started_at) <= self.settings.tap_max_duration
+                    && distance(position, previous.start_position) <= self.settings.tap_max_distance
+                {
+                    match self.last_tap {
+                        Some((time, last_position))
+                            if now.saturating_sub(time) <= self.settings.double_tap_interval
+                                && distance(position, last_position)
+                                    <= self.settings.tap_max_distance =>
+                        {
+                            self.last_tap = None;
+                            self.gestures.push(Gesture::DoubleTap { position });
+                        }
+                        _ => {
+                            self.last_tap = Some((now, position));
+                            self.gestures.push(Gesture::Tap { position });
+                        }
+                    }
+                }
+            }
+            TouchPhase::Cancelled => {
+                self.panning = false;
+            }
+        }
+    }
+
+    // merges the movements of the same step into a single gesture
+    fn push_pan(&mut self, position: (f32, f32), delta: (f32, f32)) {
+        for gesture in self.gestures.iter_mut() {
+            if let Gesture::Pan {
+                position: old_position,
+                delta: old_delta,
+            } = gesture
+            {
+                *old_position = position;
+                old_delta.0 += delta.0;
+                old_delta.1 += delta.1;
+                return;
+            }
+        }
+        self.gestures.push(Gesture::Pan { position, delta });
+    }
+
+    fn push_pinch(&mut self, center: (f32, f32), scale: f32) {
+        for gesture in self.gestures.iter_mut() {
+            if let Gesture::Pinch {
+                center: old_center,
+                scale: old_scale,
+            } = gesture
+            {
+                *old_center = center;
+                *old_scale *= scale;
+                return;
+            }
+        }
+        self.gestures.push(Gesture::Pinch { center, scale });
+    }
+
+    fn push_rotate(&mut self, center: (f32, f32), angle: f32) {
+        for gesture in self.gestures.iter_mut() {
+            if let Gesture::Rotate {
+                center: old_center,
+                angle: old_angle,
+            } = gesture
+            {
+                *old_center = center;
+                *old_angle += angle;
+                return;
+            }
+        }
+        self.gestures.push(Gesture::Rotate { center, angle });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::input_event::InputEvent;
+    use crate::utils::winit_input_helper::WinitInputHelper;
+
+    fn touch(phase: TouchPhase, x: f64) -> InputEvent {
+        InputEvent::Touch {
+            id: 1,
+            phase,
+            x,
+            y: 0.0,
+            force: None,
+        }
+    }
+
+    /// Runs one input step at the time in milliseconds, and returns its gestures
+    fn step(input: &mut WinitInputHelper, millis: u64, events: &[InputEvent]) -> Vec<Gesture> {
+        input.set_time(Duration::from_millis(millis));
+        input.step();
+        for event in events {
+            input.process_input_event(event);
+        }
+        input.end_step();
+        input.gestures().to_vec()
+    }
+
+    #[test]
+    fn gestures_follow_the_given_time() {
+        let mut input = WinitInputHelper::new();
+        let tap = Gesture::Tap {
+            position: (5.0, 0.0),
+        };
+
+        step(&mut input, 0, &[touch(TouchPhase::Started, 5.0)]);
+        assert_eq!(
+            step(&mut input, 100, &[touch(TouchPhase::Ended, 5.0)]),
+            [tap]
+        );
+        assert_eq!(input.delta_time(), Some(Duration::from_millis(100)));
+
+        // the second tap is within the double tap interval
+        step(&mut input, 200, &[touch(TouchPhase::Started, 5.0)]);
+        assert_eq!(
+            step(&mut input, 250, &[touch(TouchPhase::Ended, 5.0)]),
+            [Gesture::DoubleTap {
+                position: (5.0, 0.0)
+            }]
+        );
+
+        // held for too long to be a tap, however long the steps took on the wall clock
+        step(&mut input, 1000, &[touch(TouchPhase::Started, 5.0)]);
+        assert!(step(&mut input, 1400, &[]).is_empty());
+        assert_eq!(
+            step(&mut input, 1500, &[]),
+            [Gesture::LongPress {
+                position: (5.0, 0.0)
+            }]
+        );
+        assert!(step(&mut input, 1600, &[touch(TouchPhase::Ended, 5.0)]).is_empty());
+        assert_eq!(input.time(), Duration::from_millis(1600));
+    }
+
+    fn finger(id: u64, phase: TouchPhase, x: f64, y: f64) -> InputEvent {
+        InputEvent::Touch {
+            id,
+            phase,
+            x,
+            y,
+            force: None,
+        }
+    }
+
+    #[test]
+    fn pan() {
+        let mut input = WinitInputHelper::new();
+
+        step(&mut input, 0, &[touch(TouchPhase::Started, 0.0)]);
+        // within the tap distance, so not a pan yet
+        assert!(step(&mut input, 16, &[touch(TouchPhase::Moved, 5.0)]).is_empty());
+        assert_eq!(
+            step(
+                &mut input,
+                32,
+                &[
+                    touch(TouchPhase::Moved, 20.0),
+                    touch(TouchPhase::Moved, 30.0)
+                ]
+            ),
+            [Gesture::Pan {
+                position: (30.0, 0.0),
+                delta: (25.0, 0.0)
+            }]
+        );
+        assert_eq!(
+            step(&mut input, 48, &[touch(TouchPhase::Moved, 28.0)]),
+            [Gesture::Pan {
+                position: (28.0, 0.0),
+                delta: (-2.0, 0.0)
+            }]
+        );
+        // a pan is not a tap
+        assert!(step(&mut input, 64, &[touch(TouchPhase::Ended, 28.0)]).is_empty());
+    }
+
+    #[test]
+    fn pinch() {
+        let mut input = WinitInputHelper::new();
+
+        step(
+            &mut input,
+            0,
+            &[
+                finger(1, TouchPhase::Started, 0.0, 0.0),
+                finger(2, TouchPhase::Started, 10.0, 0.0),
+            ],
+        );
+        let gestures = step(&mut input, 16, &[finger(2, TouchPhase::Moved, 20.0, 0.0)]);
+        assert!(gestures.contains(&Gesture::Pinch {
+            center: (10.0, 0.0),
+            scale: 2.0
+        }));
+
+        // both fingers moving in the same step give a single pinch
+        let gestures = step(
+            &mut input,
+            32,
+            &[
+                finger(1, TouchPhase::Moved, 5.0, 0.0),
+                finger(2, TouchPhase::Moved, 15.0, 0.0),
+            ],
+        );
+        let pinches = gestures
+            .iter()
+            .filter_map(|i| match i {
+                Gesture::Pinch { center, scale } => Some((*center, *scale)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(pinches.len(), 1);
+        assert_eq!(pinches[0].0, (10.0, 0.0));
+        assert!((pinches[0].1 - 0.5).abs() < 1e-6);
+
+        // the second finger moving alone is not a pan
+        step(&mut input, 48, &[finger(1, TouchPhase::Ended, 5.0, 0.0)]);
+        let gestures = step(&mut input, 64, &[finger(2, TouchPhase::Moved, 50.0, 0.0)]);
+        assert!(gestures.is_empty());
+    }
+
+    #[test]
+    fn rotate() {
+        let mut input = WinitInputHelper::new();
+        let rotation = |gestures: Vec<Gesture>| {
+            gestures.into_iter().find_map(|i| match i {
+                Gesture::Rotate { center, angle } => Some((center, angle)),
+                _ => None,
+            })
+        };
+
+        step(
+            &mut input,
+            0,
+            &[
+                finger(1, TouchPhase::Started, 0.0, 0.0),
+                finger(2, TouchPhase::Started, 10.0, 0.0),
+            ],
+        );
+        let (center, angle) = rotation(step(
+            &mut input,
+            16,
+            &[finger(2, TouchPhase::Moved, 0.0, 10.0)],
+        ))
+        .unwrap();
+        assert_eq!(center, (0.0, 5.0));
+        assert!((angle - std::f32::consts::FRAC_PI_2).abs() < 1e-6);
+
+        // crossing the opposite side turns a little instead of almost a full turn
+        step(&mut input, 32, &[finger(2, TouchPhase::Moved, -10.0, 1.0)]);
+        let (_, angle) = rotation(step(
+            &mut input,
+            48,
+            &[finger(2, TouchPhase::Moved, -10.0, -1.0)],
+        ))
+        .unwrap();
+        assert!((angle - 0.2).abs() < 0.01);
+    }
+}
@@ -7,6 +7,8 @@ use winit::keyboard::{Key, KeyCode, PhysicalKey};
 use super::current_input::{
     CurrentInput, KeyAction, MouseAction, ScanCodeAction, mouse_button_to_int,
 };
+use super::input_event::InputEvent;
+use super::touch::{Gesture, GestureRecognizer, GestureSettings, TouchPoint};
 use std::{path::PathBuf, time::Duration, time::Instant};
 
 /// The main struct of the API.
@@ -32,8 +34,11 @@ pub struct WinitInputHelper {
     scale_factor: Option<f64>,
     destroyed: bool,
     close_requested: bool,
-    step_start: Option<Instant>,
+    created: Instant,
+    time: Option<Duration>,
+    step_start: Option<Duration>,
     step_duration: Option<Duration>,
+    gestures: GestureRecognizer,
 }
 
 impl Default for WinitInputHelper {
@@ -53,8 +58,11 @@ impl WinitInputHelper {
             scale_factor: None,
             destroyed: false,
             close_requested: false,
+            created: Instant::now(),
+            time: None,
             step_start: None,
             step_duration: None,
+            gestures: GestureRecognizer::default(),
         }
     }
 
@@ -75,54 +83,92 @@ impl WinitInputHelper {
         self.end_step();
     }
 
+    /// Sets the current time, which the step durations and the touch gestures are measured
+    /// with. The engine sets it from the clock of its `Time` at every step, so the gestures
+    /// follow that clock, including a manual one. The time should not go backwards.
+    ///
+    /// Until it is called, the time elapsed since the creation of the helper is used.
+    pub fn set_time(&mut self, now: Duration) {
+        self.time = Some(now);
+    }
+
+    /// Returns the time set with [`WinitInputHelper::set_time`], or the time elapsed since
+    /// the creation of the helper if it was never set
+    pub fn time(&self) -> Duration {
+        self.time.unwrap_or_else(|| self.created.elapsed())
+    }
+
     pub fn step(&mut self) {
         self.dropped_file = None;
         self.window_resized = None;
         self.scale_factor_changed = None;
         self.close_requested = false;
         // Set the start time on the first event to avoid the first step appearing too long
-        self.step_start.get_or_insert(Instant::now());
+        let now = self.time();
+        self.step_start.get_or_insert(now);
         self.step_duration = None;
         if let Some(current) = &mut self.current {
             current.step();
+            self.gestures.step(&current.touches, now);
         }
     }
 
     pub fn process_window_event(&mut self, event: &WindowEvent) {
+        if let Some(event) = InputEvent::from_window_event(event) {
+            self.process_input_event(&event);
+        }
+    }
+
+    pub fn process_device_event(&mut self, event: &DeviceEvent) {
+        if let Some(event) = InputEvent::from_device_event(event) {
+            self.process_input_event(&event);
+        }
+    }
+
+    /// Processes an input event directly, such as the ones replayed from a recording.
+    pub fn process_input_event(&mut self, event: &InputEvent) {
         match event {
-            WindowEvent::CloseRequested => self.close_requested = true,
-            WindowEvent::Destroyed => self.destroyed = true,
-            WindowEvent::Focused(false) => self.current = None,
-            WindowEvent::Focused(true) => {
+            InputEvent::CloseRequested => self.close_requested = true,
+            InputEvent::Destroyed => self.destroyed = true,
+            InputEvent::Focused { focused: false } => self.current = None,
+            InputEvent::Focused { focused: true } => {
                 if self.current.is_none() {
                     self.current = Some(CurrentInput::new())
                 }
             }
-            WindowEvent::DroppedFile(path) => self.dropped_file = Some(path.clone()),
-            WindowEvent::Resized(size) => {
-                self.window_resized = Some(*size);
-                self.window_size = Some((*size).into());
+            InputEvent::DroppedFile { path } => self.dropped_file = Some(path.clone()),
+            InputEvent::Resized { width, height } => {
+                self.window_resized = Some(PhysicalSize::new(*width, *height));
+                self.window_size = Some((*width, *height));
             }
-            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+            InputEvent::ScaleFactorChanged { scale_factor } => {
                 self.scale_factor_changed = Some(*scale_factor);
                 self.scale_factor = Some(*scale_factor);
             }
             _ => {}
         }
+        let now = self.time();
         if let Some(current) = &mut self.current {
-            current.handle_event(event);
-        }
-    }
-
-    pub fn process_device_event(&mut self, event: &DeviceEvent) {
-        if let Some(ref mut current) = self.current {
-            current.handle_device_event(event);
+            if let InputEvent::Touch {
+                id, phase, x, y, ..
+            } = event
+            {
+                self.gestures.handle_touch(
+                    *id,
+                    *phase,
+                    (*x as f32, *y as f32),
+                    &current.touches,
+                    now,
+                );
+            }
+            current.handle_event(event, now);
         }
     }
 
     pub fn end_step(&mut self) {
-        self.step_duration = self.step_start.map(|start| start.elapsed());
-        self.step_start = Some(Instant::now());
+        let now = self.time();
+        self.step_duration = self.step_start.map(|start| now.saturating_sub(start));
+        self.step_start = Some(now);
     }
 
     /// Returns true when the key with the specified keycode goes from "not pressed" to "pressed".
@@ -372,6 +418,42 @@ impl WinitInputHelper {
         }
     }
 
+    /// Returns the fingers currently on the touch screen, including the ones lifted during the last step.
+    /// Returns nothing when the window is not focused.
+    pub fn touches(&self) -> impl Iterator<Item = &TouchPoint> {
+        self.current
+            .iter()
+            .flat_map(|current| current.touches.values())
+    }
+
+    /// Returns the finger with the specified id if it is on the touch screen, or was lifted during the last step.
+    /// Otherwise returns `None`.
+    pub fn touch(&self, id: u64) -> Option<&TouchPoint> {
+        self.current
+            .as_ref()
+            .and_then(|current| current.touches.get(&id))
+    }
+
+    /// Returns the amount of fingers currently on the touch screen.
+    pub fn touch_count(&self) -> usize {
+        self.touches().filter(|touch| !touch.ended()).count()
+    }
+
+    /// Returns the gestures recognized during the last step, such as taps, pans and pinches.
+    pub fn gestures(&self) -> &[Gesture] {
+        self.gestures.gestures()
+    }
+
+    /// Returns the thresholds used for recognizing gestures.
+    pub fn gesture_settings(&self) -> &GestureSettings {
+        &self.gestures.settings
+    }
+
+    /// Changes the thresholds used for recognizing gestures.
+    pub fn set_gesture_settings(&mut self, settings: GestureSettings) {
+        self.gestures.settings = settings;
+    }
+
     /// Returns the path to a file that has been drag-and-dropped onto the window.
     pub fn dropped_file(&self) -> Option<PathBuf> {
         self.dropped_file.clone()
@@ -420,3 +502,27 @@ impl WinitInputHelper {
         self.step_duration
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delta_time_without_set_time() {
+        let mut input = WinitInputHelper::new();
+        input.step();
+        std::thread::sleep(Duration::from_millis(5));
+        input.end_step();
+        assert!(input.delta_time().unwrap() >= Duration::from_millis(5));
+    }
+
+    #[test]
+    fn delta_time_follows_set_time() {
+        let mut input = WinitInputHelper::new();
+        input.set_time(Duration::from_secs(1));
+        input.step();
+        input.set_time(Duration::from_secs(3));
+        input.end_step();
+        assert_eq!(input.delta_time(), Some(Duration::from_secs(2)));
+    }
+}
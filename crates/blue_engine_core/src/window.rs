@@ -5,6 +5,7 @@
 */
 
 use crate::prelude::{Engine, Renderer};
+use crate::utils::input_event::InputEvent;
 use winit::{
     application::ApplicationHandler,
     event::{DeviceEvent, WindowEvent},
@@ -26,6 +27,171 @@ crate::macros::impl_deref_field!(
     window
 );
 
+/// The surface of an [`ExtraWindow`] and the buffers that match its size
+#[derive(Debug)]
+struct WindowSurface {
+    surface: wgpu::Surface<'static>,
+    config: wgpu::SurfaceConfiguration,
+    depth_buffer: (wgpu::Texture, wgpu::TextureView, wgpu::Sampler),
+    msaa_target: Option<wgpu::TextureView>,
+}
+
+/// A window opened next to the main one through [`Engine::open_window`], such as an
+/// inspector or a second display output. It has its own surface, and renders the scene
+/// with its own camera.
+#[derive(Debug)]
+pub struct ExtraWindow {
+    /// The winit window itself. It is created on the next turn of the event loop.
+    pub window: Option<std::sync::Arc<crate::winit::window::Window>>,
+    /// The attributes the window is created with
+    pub attributes: winit::window::WindowAttributes,
+    /// The name of the camera the scene is rendered with. Objects with a camera effect
+    /// keep their own camera
+    pub camera: std::sync::Arc<str>,
+    surface: Option<WindowSurface>,
+}
+crate::macros::impl_deref_field!(
+    ExtraWindow,
+    Option<std::sync::Arc<crate::winit::window::Window>>,
+    window
+);
+
+impl ExtraWindow {
+    /// Creates the window, which opens once the event loop runs
+    pub(crate) fn new(
+        attributes: winit::window::WindowAttributes,
+        camera: std::sync::Arc<str>,
+    ) -> Self {
+        Self {
+            window: None,
+            attributes,
+            camera,
+            surface: None,
+        }
+    }
+
+    /// Returns the id winit gives to the window, once it is open
+    pub fn id(&self) -> Option<winit::window::WindowId> {
+        self.window.as_ref().map(|window| window.id())
+    }
+
+    /// Opens the window and configures its surface
+    pub(crate) fn create(
+        &mut self,
+        event_loop: &winit::event_loop::ActiveEventLoop,
+        renderer: &Renderer,
+    ) -> Result<(), crate::error::Error> {
+        let window = std::sync::Arc::new(event_loop.create_window(self.attributes.clone())?);
+        let surface = renderer.instance.create_surface(window.clone())?;
+
+        // the shaders are built for the format of the main surface
+        let surface_capabilities = surface.get_capabilities(&renderer.adapter);
+        if !surface_capabilities
+            .formats
+            .contains(&renderer.config.format)
+        {
+            return Err(crate::error::Error::Custom(format!(
+                "The window does not support the format {:?} of the main window",
+                renderer.config.format
+            )));
+        }
+
+        let size = window.inner_size();
+        let mut config = renderer.config.clone();
+        config.width = size.width.max(1);
+        config.height = size.height.max(1);
+        if !surface_capabilities.usages.contains(config.usage) {
+            config.usage = wgpu::TextureUsages::RENDER_ATTACHMENT;
+        }
+        surface.configure(&renderer.device, &config);
+        let depth_buffer = Renderer::build_depth_buffer(
+            "Extra Window Depth Buffer",
+            &renderer.device,
+            &config,
+            renderer.sample_count,
+        );
+        let msaa_target =
+            renderer.build_msaa_target("Extra Window MSAA Target", config.width, config.height);
+
+        self.surface = Some(WindowSurface {
+            surface,
+            config,
+            depth_buffer,
+            msaa_target,
+        });
+        window.request_redraw();
+        self.window = Some(window);
+
+        Ok(())
+    }
+
+    /// Resizes the surface to the new size of the window
+    pub(crate) fn resize(&mut self, renderer: &Renderer, new_size: crate::WindowSize) {
+        if new_size.0 == 0 || new_size.1 == 0 {
+            return;
+        }
+        if let Some(surface) = self.surface.as_mut() {
+            surface.config.width = new_size.0;
+            surface.config.height = new_size.1;
+            surface.surface.configure(&renderer.device, &surface.config);
+            surface.depth_buffer = Renderer::build_depth_buffer(
+                "Extra Window Depth Buffer",
+                &renderer.device,
+                &surface.config,
+                renderer.sample_count,
+            );
+            surface.msaa_target =
+                renderer.build_msaa_target("Extra Window MSAA Target", new_size.0, new_size.1);
+        }
+    }
+
+    /// Rebuilds the depth buffer and the MSAA target, such as after the MSAA samples changed
+    pub(crate) fn rebuild_targets(&mut self, renderer: &Renderer) {
+        if let Some(size) = self
+            .surface
+            .as_ref()
+            .map(|surface| (surface.config.width, surface.config.height))
+        {
+            self.resize(renderer, size);
+        }
+    }
+
+    /// Renders the scene to the window and presents it
+    pub(crate) fn render(
+        &self,
+        renderer: &Renderer,
+        objects: &crate::ObjectStorage,
+        camera: &crate::CameraContainer,
+    ) {
+        let Some(surface) = self.surface.as_ref() else {
+            return;
+        };
+        let Ok(frame) = surface.surface.get_current_texture() else {
+            return;
+        };
+        let view = frame
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut encoder = renderer
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Extra Window Encoder"),
+            });
+        renderer.encode_scene(
+            &mut encoder,
+            &view,
+            &surface.depth_buffer.1,
+            surface.msaa_target.as_ref(),
+            &[(objects, camera)],
+            (surface.config.width, surface.config.height),
+            &self.camera,
+        );
+        renderer.queue.submit(Some(encoder.finish()));
+        frame.present();
+    }
+}
+
 impl ApplicationHandler for Engine {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         if self.window.is_none() {
@@ -35,30 +201,14 @@ impl ApplicationHandler for Engine {
                 let new_window = std::sync::Arc::new(new_window);
 
                 if self.renderer.surface.is_none() {
-                    if let Ok(surface) = self.renderer.instance.create_surface(new_window.clone()) {
-                        let surface_capabilities = surface.get_capabilities(&self.renderer.adapter);
-                        let tex_format = surface_capabilities
-                            .formats
-                            .iter()
-                            .copied()
-                            .find(|f| f.is_srgb())
-                            .unwrap_or(surface_capabilities.formats[0]);
-
-                        self.renderer.config.format = tex_format;
-                        self.renderer.config.view_formats = vec![tex_format];
-
-                        surface.configure(&self.renderer.device, &self.renderer.config);
-                        self.renderer.depth_buffer = Renderer::build_depth_buffer(
-                            "Depth Buffer",
-                            &self.renderer.device,
-                            &self.renderer.config,
-                        );
-                        self.renderer.surface = Some(surface);
-
-                        self.renderer.build_default_data();
-                        self.objects.iter_mut().for_each(|i| {
-                            i.1.update(&mut self.renderer);
-                        });
+                    let attached = self
+                        .renderer
+                        .instance
+                        .create_surface(new_window.clone())
+                        .map_err(crate::error::Error::from)
+                        .and_then(|surface| self.attach_surface(surface));
+                    if let Err(error) = attached {
+                        log::error!("Could not render to the window: {error}");
                     }
                 }
 
@@ -85,11 +235,7 @@ impl ApplicationHandler for Engine {
                 self.window.window = Some(new_window);
             }
 
-            let mut events = std::mem::take(&mut self.signals.events);
-            events.iter_mut().for_each(|i| {
-                i.1.init(self);
-            });
-            std::mem::swap(&mut self.signals.events, &mut events);
+            self.init_signals();
         }
     }
 
@@ -98,6 +244,7 @@ impl ApplicationHandler for Engine {
         _event_loop: &winit::event_loop::ActiveEventLoop,
         _cause: winit::event::StartCause,
     ) {
+        self.simple_input.set_time(self.time.clock().now());
         self.simple_input.step();
     }
 
@@ -107,13 +254,11 @@ impl ApplicationHandler for Engine {
         _device_id: winit::event::DeviceId,
         event: DeviceEvent,
     ) {
-        self.simple_input.process_device_event(&event);
+        if let Some(input_event) = InputEvent::from_device_event(&event) {
+            self.handle_input_event(input_event);
+        }
 
-        let mut events = std::mem::take(&mut self.signals.events);
-        events.iter_mut().for_each(|i| {
-            i.1.device_events(self, &event);
-        });
-        std::mem::swap(&mut self.signals.events, &mut events);
+        self.run_signals(|signal, engine| signal.device_events(engine, &event));
 
         self.raw_input = Some(event);
     }
@@ -121,63 +266,107 @@ impl ApplicationHandler for Engine {
     fn window_event(
         &mut self,
         event_loop: &winit::event_loop::ActiveEventLoop,
-        _window_id: winit::window::WindowId,
+        window_id: winit::window::WindowId,
         event: WindowEvent,
     ) {
-        self.simple_input.process_window_event(&event);
-        let mut events = std::mem::take(&mut self.signals.events);
-        events.iter_mut().for_each(|i| {
-            i.1.window_events(self, &event);
-        });
-        std::mem::swap(&mut self.signals.events, &mut events);
+        let extra_window = self.extra_window_name(window_id);
+        let is_main_window = self.window.as_ref().map(|window| window.id()) == Some(window_id);
+        if extra_window.is_none() && !is_main_window {
+            // such as the late events of a closed window
+            return;
+        }
+
+        if let Some(input_event) = InputEvent::from_window_event(&event) {
+            // closing an extra window does not close the engine
+            let closes_engine = matches!(
+                input_event,
+                InputEvent::CloseRequested | InputEvent::Destroyed
+            );
+            if is_main_window || !closes_engine {
+                self.handle_input_event(input_event);
+            }
+        }
+        self.run_signals(|signal, engine| signal.window_events(engine, window_id, &event));
+
+        if let Some(name) = extra_window {
+            self.extra_window_event(name, event);
+            return;
+        }
 
         match event {
             WindowEvent::CloseRequested => {
+                // the engine shuts down in `exiting`, and `update_loop` returns
                 event_loop.exit();
-                std::process::exit(0);
             }
             WindowEvent::Resized(size) => {
-                let size = (size.width, size.height);
-                self.renderer.resize(size);
-                self.camera.set_resolution(size);
-                self.camera.update_view_projection(&mut self.renderer);
+                self.resize(size.width, size.height);
             }
             WindowEvent::RedrawRequested => {
-                if self.window.should_close {
+                if self.window.should_close || self.exit_requested() {
                     event_loop.exit();
                 }
+                if let Err(error) = self.check_device() {
+                    log::error!("Could not recover the lost device: {error}");
+                    self.request_exit_with(1);
+                    event_loop.exit();
+                    return;
+                }
+
+                self.time.tick();
+                self.event_bus.next_frame();
+                self.apply_state_transitions();
+                self.rebuild_outdated_pipelines();
+                self.renderer
+                    .update_dynamic_resolution(self.time.real_delta());
 
                 if let Some(window_ref) = self.window.as_ref() {
                     let size = window_ref.inner_size();
                     let size = (size.width, size.height);
-                    if let Ok(Some((mut encoder, view, frame, headless_output))) =
-                        self.renderer.pre_render(&self.objects, size, &self.camera)
-                    {
-                        let mut events = std::mem::take(&mut self.signals.events);
-                        events.iter_mut().for_each(|i| {
-                            i.1.frame(self, &mut encoder, &view);
-                        });
-                        std::mem::swap(&mut self.signals.events, &mut events);
-
-                        for camera_value in self.camera.values_mut() {
-                            camera_value.update_view_projection(&mut self.renderer);
-                        }
-                        self.objects.iter_mut().for_each(|i| {
-                            if i.1.changed {
-                                i.1.update(&mut self.renderer);
+                    let layers = self.states.render_layers(&self.objects, &self.camera);
+                    match self.renderer.pre_render(&layers, size) {
+                        Ok(Some((mut encoder, view, frame, headless_output))) => {
+                            self.run_signals(|signal, engine| {
+                                signal.frame(engine, &mut encoder, &view)
+                            });
+
+                            for camera_value in self.camera.values_mut() {
+                                camera_value.update_view_projection(&mut self.renderer);
                             }
-                        });
+                            self.objects.iter_mut().for_each(|i| {
+                                if i.1.changed {
+                                    i.1.update(&mut self.renderer);
+                                }
+                            });
+
+                            self.update_input();
+
+                            self.run_fixed_updates();
+                            self.run_scheduler();
+                            self.run_signals(|signal, engine| signal.pre_update(engine));
+                            self.run_systems();
+                            self.update_states();
+
+                            let mut update_function = self.update_loop.take();
+                            if let Some(ref mut update_function) = update_function {
+                                update_function(self);
+                            }
+                            self.update_loop = update_function;
+                            self.end_input_frame();
 
-                        let mut update_function = self.update_loop.take();
-                        if let Some(ref mut update_function) = update_function {
-                            update_function(self);
+                            if let Err(error) =
+                                self.renderer.render(encoder, frame, headless_output)
+                            {
+                                log::error!("Could not render the frame: {error}");
+                            }
+                            self.run_signals(|signal, engine| signal.post_render(engine));
                         }
-                        self.update_loop = update_function;
-
-                        self.renderer.render(encoder, frame, headless_output);
+                        Ok(None) => {}
+                        Err(error) => log::error!("Could not render the frame: {error}"),
                     }
                 }
 
+                self.time.limit_frame_rate();
+
                 if let Some(window_inner) = &self.window.window {
                     window_inner.request_redraw();
                 }
@@ -186,8 +375,13 @@ impl ApplicationHandler for Engine {
         }
     }
 
+    fn exiting(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop) {
+        self.shutdown();
+    }
+
     fn about_to_wait(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         self.simple_input.end_step();
+        self.create_extra_windows(event_loop);
 
         if self.simple_input.close_requested() || self.simple_input.destroyed() {
             event_loop.exit();
@@ -199,6 +393,62 @@ impl ApplicationHandler for Engine {
     }
 }
 
+impl Engine {
+    /// Opens the extra windows that are waiting for the event loop
+    fn create_extra_windows(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
+        // they need the surface format of the main window
+        if self.renderer.surface.is_none() {
+            return;
+        }
+
+        let mut failed = Vec::new();
+        for (name, window) in self.extra_windows.iter_mut() {
+            if window.window.is_none()
+                && let Err(error) = window.create(event_loop, &self.renderer)
+            {
+                log::error!("Could not open the window \"{name}\": {error}");
+                failed.push(name.clone());
+            }
+        }
+        for name in failed {
+            self.extra_windows.remove(&name);
+        }
+    }
+
+    /// Handles the events of an extra window that the main window handles for itself
+    fn extra_window_event(&mut self, name: std::sync::Arc<str>, event: WindowEvent) {
+        match event {
+            WindowEvent::CloseRequested => {
+                self.extra_windows.remove(&name);
+            }
+            WindowEvent::Resized(size) => {
+                let size = (size.width, size.height);
+                let Some(window) = self.extra_windows.get_mut(&name) else {
+                    return;
+                };
+                window.resize(&self.renderer, size);
+
+                // the main camera follows the main window
+                if &*window.camera != "main"
+                    && let Some(camera) = self.camera.get_mut(&window.camera)
+                {
+                    camera.set_resolution(size);
+                    camera.update_view_projection(&mut self.renderer);
+                }
+            }
+            WindowEvent::RedrawRequested => {
+                if let Some(window) = self.extra_windows.get(&name) {
+                    window.render(&self.renderer, &self.objects, &self.camera);
+                    if let Some(window_inner) = &window.window {
+                        window_inner.request_redraw();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 macro_rules! gen_window_component_functions {
     ($fn_name:ident, $name:ident, String) => {
         /// see [winit::window::Window::$fn_name]
@@ -242,7 +492,7 @@ impl Window {
         }
     }
 
-    /// close the engine window
+    /// close the engine window, which shuts the engine down and returns from `update_loop`
     pub fn close_engine(&mut self) {
         self.should_close = true;
     }
@@ -9,7 +9,7 @@ fn main() -> Result<(), blue_engine::error::Error> {
         let mut engine = Engine::new_config(EngineSettings {
             width: 1280,
             height: 720,
-            title: "Animation test",
+            title: "Animation test".into(),
             ..Default::default()
         })?;
 
@@ -10,7 +10,7 @@ fn main() -> Result<(), blue_engine::error::Error> {
         let mut engine = Engine::new_config(EngineSettings {
             width: 1280,
             height: 720,
-            title: "Light test",
+            title: "Light test".into(),
             ..Default::default()
         })?;
 
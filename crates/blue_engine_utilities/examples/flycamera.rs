@@ -5,7 +5,7 @@ fn main() -> Result<(), blue_engine::error::Error> {
     let mut engine = Engine::new_config(EngineSettings {
         width: 1500,
         height: 1000,
-        title: "Fly Camera",
+        title: "Fly Camera".into(),
         present_mode: egui_wgpu::wgpu::PresentMode::Fifo,
         ..Default::default()
     })?;
@@ -9,7 +9,7 @@ fn main() -> Result<(), blue_engine::error::Error> {
         let mut engine = Engine::new_config(EngineSettings {
             width: 1280,
             height: 720,
-            title: "Model test",
+            title: "Model test".into(),
             ..Default::default()
         })?;
 
@@ -20,7 +20,7 @@ fn main() -> Result<(), blue_engine::error::Error> {
         let mut engine = Engine::new_config(EngineSettings {
             width: 1500,
             height: 1000,
-            title: "Fly Camera",
+            title: "Fly Camera".into(),
             ..Default::default()
         })?;
 
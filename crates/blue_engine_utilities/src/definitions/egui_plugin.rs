@@ -89,9 +89,12 @@ impl blue_engine::Signal for EGUIPlugin {
     fn window_events(
         &mut self,
         engine: &mut blue_engine::Engine,
+        window_id: blue_engine::winit::window::WindowId,
         event: &blue_engine::WindowEvent,
     ) {
+        // the context belongs to the main window, not the extra ones
         if let Some(window) = engine.window.window.as_ref()
+            && window.id() == window_id
             && let Some(platform) = self.platform.as_mut()
         {
             //? has a return, maybe useful in the future
@@ -1,45 +1,27 @@
 /*
  * Headless mode example of the Blue Engine. In this mode, the frames are
- * rendered without a window being available. However, texture are being
- * presented during the runtime as bytes format, so that it can be used
- * however it is needed.
+ * rendered offscreen without a window. Every frame is copied to
+ * `renderer.headless_texture_data`, so that it can be used however it is
+ * needed, or saved as an image.
  *
- * To use this feature, you MUST disable the window feature, which is turned
- * on by default, and enable headless mode. This feature does not work for
- * android yet.
- *
- * example in Cargo.toml:
+ * The run mode is chosen at runtime, so this works with the default features.
+ * Builds without a window can disable them and enable the `headless` feature:
  *
  * blue_engine = { version = "*", default-features = false, features = ["static_link", "debug", "headless"] }
  */
 
 use blue_engine::{
-    Engine, EngineSettings, ObjectSettings, image::ImageEncoder, primitive_shapes::triangle,
+    Engine, EngineSettings, HeadlessOutputFormat, ObjectSettings, RunMode,
+    primitive_shapes::triangle,
 };
 
-pub fn output_image_native(image_data: Vec<u8>, texture_dims: (usize, usize), path: &str) {
-    let writer = std::fs::File::create(path).unwrap();
-    let encoder = blue_engine::image::codecs::png::PngEncoder::new(writer);
-    encoder
-        .write_image(
-            &image_data,
-            texture_dims.0 as u32,
-            texture_dims.1 as u32,
-            blue_engine::image::ExtendedColorType::Rgba8,
-        )
-        .unwrap();
-
-    println!("generated a frame!");
-
-    // to be able to view the image. Remove it if you're testing it in other ways.
-    std::process::exit(0);
-}
-
 fn main() -> Result<(), blue_engine::error::Error> {
     let mut engine = Engine::new_config(EngineSettings {
-        // The width and height must be respecting of the 256 padding
-        height: 1024,
-        width: 1024,
+        // any resolution works, the rows are unpadded when the frame is copied
+        width: 1000,
+        height: 750,
+        run_mode: RunMode::Headless,
+        headless_output: HeadlessOutputFormat::Rgba8,
         ..Default::default()
     })?;
 
@@ -50,19 +32,15 @@ fn main() -> Result<(), blue_engine::error::Error> {
         &mut engine.objects,
     )?;
 
-    // let instant = std::time::Instant::now();
-    engine.update_loop(move |_engine| {
-        #[cfg(feature = "headless")]
-        output_image_native(
-            _engine.renderer.headless_texture_data.clone(),
-            (
-                // since we do not have a window, the width and
-                // height is taken from the configuration of the renderer
-                _engine.renderer.config.width as usize,
-                _engine.renderer.config.height as usize,
-            ),
-            "img.png",
-        );
-    })?;
+    // renders a single frame, and returns once the engine is shut down
+    engine.run_frames(1, |_| {})?;
+
+    // since there is no window, the size is taken from the configuration of the renderer
+    engine.renderer.save_headless_png("img.png")?;
+    println!(
+        "generated a {}x{} frame!",
+        engine.renderer.config.width, engine.renderer.config.height
+    );
+
     Ok(())
 }